@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Parsed, Warnings};
+use crate::nmt::Diff;
+
+/// Number of instances and bytes per class parsed from `GC.class_histogram`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassHistogram {
+    /// Classes ordered by bytes, largest first
+    pub entries: Vec<ClassHistogramEntry>,
+    pub total_instances: Option<u64>,
+    pub total_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassHistogramEntry {
+    pub instances: u64,
+    pub bytes: u64,
+    /// Class name as printed by the jvm (e.g. `java.lang.String` or `[B`)
+    pub class_name: String,
+    /// Module and version (e.g. `java.base@17.0.2`), not printed for classes of unnamed modules
+    pub module: Option<String>,
+}
+
+/// Change of the instances and bytes of a class between two histograms.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassHistogramDiff {
+    pub class_name: String,
+    pub instances: Diff,
+    pub bytes: Diff,
+}
+
+impl ClassHistogram {
+    /// Compares the histogram to an earlier one. Classes with the same name (e.g. loaded by
+    /// different class loaders) are summed up. The result is ordered by the change of bytes,
+    /// largest growth first. Classes missing in one of the histograms count as 0 there.
+    pub fn diff(&self, earlier: &ClassHistogram) -> Vec<ClassHistogramDiff> {
+        let mut classes: HashMap<&str, ([u64; 2], [u64; 2])> = HashMap::new();
+        for (index, histogram) in [earlier, self].into_iter().enumerate() {
+            for entry in &histogram.entries {
+                let (instances, bytes) = classes.entry(&entry.class_name).or_default();
+                instances[index] += entry.instances;
+                bytes[index] += entry.bytes;
+            }
+        }
+        let mut diffs: Vec<ClassHistogramDiff> = classes
+            .into_iter()
+            .map(|(class_name, (instances, bytes))| ClassHistogramDiff {
+                class_name: class_name.to_string(),
+                instances: diff(instances),
+                bytes: diff(bytes),
+            })
+            .collect();
+        diffs.sort_by(|a, b| {
+            b.bytes
+                .delta
+                .cmp(&a.bytes.delta)
+                .then_with(|| a.class_name.cmp(&b.class_name))
+        });
+        diffs
+    }
+}
+
+fn diff([earlier, later]: [u64; 2]) -> Diff {
+    Diff {
+        value: later,
+        delta: later as i64 - earlier as i64,
+    }
+}
+
+/// Parses the output of `jcmd <pid> GC.class_histogram`.
+///
+/// Rows look like `   1:         12345        1234567  [B (java.base@17.0.2)`, followed by a
+/// `Total` row.
+pub fn parse_class_histogram(output: &str) -> Parsed<ClassHistogram> {
+    let mut warnings = Warnings::new("GC.class_histogram");
+    let mut histogram = ClassHistogram::default();
+
+    for row in output.split('\n') {
+        let mut columns = row.split_whitespace();
+        let Some(first) = columns.next() else {
+            continue;
+        };
+        if first == "Total" {
+            histogram.total_instances =
+                parse_count(row, "instances", columns.next(), &mut warnings);
+            histogram.total_bytes = parse_count(row, "bytes", columns.next(), &mut warnings);
+            continue;
+        }
+        // Skip the header and the separator rows
+        let Some(rank) = first.strip_suffix(':') else {
+            continue;
+        };
+        // The pid row (e.g. `48213:`) has no further columns
+        if rank.parse::<u32>().is_err() || columns.clone().next().is_none() {
+            continue;
+        }
+        let instances = parse_count(row, "instances", columns.next(), &mut warnings);
+        let bytes = parse_count(row, "bytes", columns.next(), &mut warnings);
+        let Some(class_name) = columns.next() else {
+            warnings.add(row, "missing class name");
+            continue;
+        };
+        let (Some(instances), Some(bytes)) = (instances, bytes) else {
+            continue;
+        };
+        let module = columns
+            .next()
+            .and_then(|m| m.strip_prefix('('))
+            .and_then(|m| m.strip_suffix(')'))
+            .map(|m| m.to_string());
+        histogram.entries.push(ClassHistogramEntry {
+            instances,
+            bytes,
+            class_name: class_name.to_string(),
+            module,
+        });
+    }
+    warnings.into_parsed(histogram)
+}
+
+fn parse_count(row: &str, name: &str, value: Option<&str>, warnings: &mut Warnings) -> Option<u64> {
+    let count = value.and_then(|v| v.parse::<u64>().ok());
+    if count.is_none() {
+        warnings.add(row, format!("invalid {} count", name));
+    }
+    count
+}
@@ -0,0 +1,278 @@
+use std::fmt::{Display, Formatter};
+
+use serde::de::Error as _;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::processes::is_pid_row;
+
+/// Errors raised while running a jcmd command or parsing its output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JcmdError {
+    /// The jvm process terminated or never existed.
+    ProcessGone,
+    /// The jvm refused the attach request (e.g. different user or attach mechanism disabled).
+    AttachRefused { message: String },
+    /// The jvm was started without `-XX:NativeMemoryTracking`.
+    NmtDisabled,
+    /// A line of the output couldn't be parsed.
+    Parse {
+        command: String,
+        line: String,
+        message: String,
+    },
+    /// The jcmd binary couldn't be found.
+    JcmdMissing { path: String },
+    /// Running the jcmd command failed for another reason.
+    CommandFailed { command: String, message: String },
+    /// The jcmd command didn't complete in time and was killed.
+    Timeout { command: String },
+    /// A native memory diff was requested before a baseline was taken.
+    NoNmtBaseline,
+    /// Native memory details were requested from a jvm tracking only the summary.
+    NmtDetailDisabled,
+}
+
+impl JcmdError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            JcmdError::ProcessGone => "processGone",
+            JcmdError::AttachRefused { .. } => "attachRefused",
+            JcmdError::NmtDisabled => "nmtDisabled",
+            JcmdError::Parse { .. } => "parse",
+            JcmdError::JcmdMissing { .. } => "jcmdMissing",
+            JcmdError::CommandFailed { .. } => "commandFailed",
+            JcmdError::Timeout { .. } => "timeout",
+            JcmdError::NoNmtBaseline => "noNmtBaseline",
+            JcmdError::NmtDetailDisabled => "nmtDetailDisabled",
+        }
+    }
+}
+
+impl Display for JcmdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JcmdError::ProcessGone => write!(f, "No such process"),
+            JcmdError::AttachRefused { message } => {
+                write!(f, "Attaching to the process failed: {}", message)
+            }
+            JcmdError::NmtDisabled => write!(
+                f,
+                "Native memory tracking not activated. Start application with java \
+                -XX:NativeMemoryTracking=summary -jar ..."
+            ),
+            JcmdError::Parse {
+                command,
+                line,
+                message,
+            } => write!(
+                f,
+                "{} output couldn't be parsed: {} ({})",
+                command, message, line
+            ),
+            JcmdError::JcmdMissing { path } => write!(
+                f,
+                "jcmd not found at '{}'. Set JAVA_HOME or java.home in ~/.config/jcmd-ui/config",
+                path
+            ),
+            JcmdError::CommandFailed { command, message } => {
+                write!(f, "{} failed: {}", command, message)
+            }
+            JcmdError::Timeout { command } => write!(f, "{} timed out", command),
+            JcmdError::NoNmtBaseline => write!(f, "No native memory baseline has been set"),
+            JcmdError::NmtDetailDisabled => write!(
+                f,
+                "Native memory tracking details not activated. Start application with java \
+                -XX:NativeMemoryTracking=detail -jar ..."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for JcmdError {}
+
+/// Serialized as `{ kind, message, ...details }` to allow the frontend to show the message and
+/// to react on specific kinds. `detail` holds the message of the variant, so that the error can
+/// be deserialized again.
+impl Serialize for JcmdError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("JcmdError", 5)?;
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("message", &self.to_string())?;
+        match self {
+            JcmdError::AttachRefused { message } => {
+                s.serialize_field("detail", message)?;
+            }
+            JcmdError::Parse {
+                command,
+                line,
+                message,
+            } => {
+                s.serialize_field("detail", message)?;
+                s.serialize_field("command", command)?;
+                s.serialize_field("line", line)?;
+            }
+            JcmdError::CommandFailed { command, message } => {
+                s.serialize_field("detail", message)?;
+                s.serialize_field("command", command)?;
+            }
+            JcmdError::JcmdMissing { path } => {
+                s.serialize_field("path", path)?;
+            }
+            JcmdError::Timeout { command } => {
+                s.serialize_field("command", command)?;
+            }
+            _ => {}
+        }
+        s.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct SerializedJcmdError {
+    kind: String,
+    detail: Option<String>,
+    command: Option<String>,
+    line: Option<String>,
+    path: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for JcmdError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let e = SerializedJcmdError::deserialize(deserializer)?;
+        let field = |value: Option<String>, name: &'static str| {
+            value.ok_or_else(|| D::Error::missing_field(name))
+        };
+        match e.kind.as_str() {
+            "processGone" => Ok(JcmdError::ProcessGone),
+            "attachRefused" => Ok(JcmdError::AttachRefused {
+                message: field(e.detail, "detail")?,
+            }),
+            "nmtDisabled" => Ok(JcmdError::NmtDisabled),
+            "parse" => Ok(JcmdError::Parse {
+                command: field(e.command, "command")?,
+                line: field(e.line, "line")?,
+                message: field(e.detail, "detail")?,
+            }),
+            "jcmdMissing" => Ok(JcmdError::JcmdMissing {
+                path: field(e.path, "path")?,
+            }),
+            "commandFailed" => Ok(JcmdError::CommandFailed {
+                command: field(e.command, "command")?,
+                message: field(e.detail, "detail")?,
+            }),
+            "timeout" => Ok(JcmdError::Timeout {
+                command: field(e.command, "command")?,
+            }),
+            "noNmtBaseline" => Ok(JcmdError::NoNmtBaseline),
+            "nmtDetailDisabled" => Ok(JcmdError::NmtDetailDisabled),
+            kind => Err(D::Error::unknown_variant(
+                kind,
+                &[
+                    "processGone",
+                    "attachRefused",
+                    "nmtDisabled",
+                    "parse",
+                    "jcmdMissing",
+                    "commandFailed",
+                    "timeout",
+                    "noNmtBaseline",
+                    "nmtDetailDisabled",
+                ],
+            )),
+        }
+    }
+}
+
+/// A parse result. Lines which couldn't be parsed are skipped and reported as warnings, so that
+/// a single unexpected line doesn't discard the whole output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parsed<T> {
+    pub value: T,
+    pub warnings: Vec<JcmdError>,
+}
+
+impl<T> Parsed<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Parsed<U> {
+        Parsed {
+            value: f(self.value),
+            warnings: self.warnings,
+        }
+    }
+}
+
+/// Checks the output of a jcmd command for errors which make parsing the output pointless.
+pub fn check_output(output: &str) -> Result<(), JcmdError> {
+    if output.contains("IOException: No such process")
+        || output.contains("Could not find any processes matching")
+    {
+        return Err(JcmdError::ProcessGone);
+    }
+    if let Some(line) = output.split('\n').find(|l| {
+        l.contains("AttachNotSupportedException") || l.contains("AttachOperationFailedException")
+    }) {
+        return Err(JcmdError::AttachRefused {
+            message: line.trim().to_string(),
+        });
+    }
+    if output.contains("Native memory tracking is not enabled") {
+        return Err(JcmdError::NmtDisabled);
+    }
+    if output.contains("No baseline for comparison") {
+        return Err(JcmdError::NoNmtBaseline);
+    }
+    if output.contains("Detail tracking is not enabled") {
+        return Err(JcmdError::NmtDetailDisabled);
+    }
+    Ok(())
+}
+
+/// Returns the output of a failed command as [`JcmdError::CommandFailed`]. Commands print the
+/// reason of a failure followed by hints spanning several lines (e.g. `Could not find 5.` followed
+/// by `Use JFR.check without options to see list of all available recordings.`), so all lines
+/// except the pid are kept as message.
+pub(crate) fn command_failed(command: &str, output: &str) -> JcmdError {
+    let message: Vec<&str> = output
+        .split('\n')
+        .map(|row| row.trim())
+        .filter(|row| !row.is_empty() && !is_pid_row(row))
+        .collect();
+    JcmdError::CommandFailed {
+        command: command.to_string(),
+        message: if message.is_empty() {
+            "no output".to_string()
+        } else {
+            message.join(" ")
+        },
+    }
+}
+
+/// Collects warnings while parsing the output of a command.
+pub(crate) struct Warnings {
+    command: &'static str,
+    warnings: Vec<JcmdError>,
+}
+
+impl Warnings {
+    pub(crate) fn new(command: &'static str) -> Warnings {
+        Warnings {
+            command,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, line: &str, message: impl Into<String>) {
+        self.warnings.push(JcmdError::Parse {
+            command: self.command.to_string(),
+            line: line.trim().to_string(),
+            message: message.into(),
+        });
+    }
+
+    pub(crate) fn into_parsed<T>(self, value: T) -> Parsed<T> {
+        Parsed {
+            value,
+            warnings: self.warnings,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::JcmdError;
+
+/// Result of `GC.heap_dump`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeapDumpResult {
+    /// Size of the written file
+    pub bytes: Option<u64>,
+    pub seconds: Option<f64>,
+}
+
+/// Parses the output of `jcmd <pid> GC.heap_dump [-all] [-gz=<level>] <file>`.
+///
+/// A successful dump ends with `Heap dump file created [<bytes> bytes in <seconds> secs]`. Any
+/// other output (e.g. `File exists`) is returned as [`JcmdError::CommandFailed`] with its last
+/// line as message.
+pub fn parse_heap_dump(output: &str) -> Result<HeapDumpResult, JcmdError> {
+    let created = output
+        .split('\n')
+        .find_map(|row| row.trim().strip_prefix("Heap dump file created"));
+    let Some(created) = created else {
+        let message = output
+            .split('\n')
+            .map(|row| row.trim())
+            .rev()
+            .find(|row| !row.is_empty())
+            .unwrap_or("no output");
+        return Err(JcmdError::CommandFailed {
+            command: "GC.heap_dump".to_string(),
+            message: message.to_string(),
+        });
+    };
+    // `[28762538 bytes in 0.103 secs]`
+    let values: Vec<&str> = created
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split_whitespace()
+        .collect();
+    let value_before = |unit: &str| {
+        values
+            .iter()
+            .position(|v| *v == unit)
+            .and_then(|index| index.checked_sub(1))
+            .map(|index| values[index])
+    };
+    Ok(HeapDumpResult {
+        bytes: value_before("bytes").and_then(|v| v.parse().ok()),
+        seconds: value_before("secs").and_then(|v| v.parse().ok()),
+    })
+}
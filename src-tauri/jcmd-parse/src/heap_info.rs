@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Parsed, Warnings};
+use crate::units::parse_memory_size;
+
+/// Heap and metaspace usage parsed from `GC.heap_info`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeapInfo {
+    /// Used heap in bytes (young and old generation combined for generational collectors).
+    pub used: Option<u64>,
+    pub metaspace: SpaceInfo,
+    pub class_space: SpaceInfo,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpaceInfo {
+    pub used: Option<u64>,
+    pub committed: Option<u64>,
+    pub reserved: Option<u64>,
+}
+
+/// Parses the output of `jcmd <pid> GC.heap_info`.
+///
+/// Supported collectors are G1, ZGC, Shenandoah, Serial and Parallel.
+pub fn parse_heap_info(output: &str) -> Parsed<HeapInfo> {
+    let mut warnings = Warnings::new("GC.heap_info");
+    let mut heap_info = HeapInfo::default();
+    let mut is_shenandoah = false;
+
+    for row in output.split('\n') {
+        if row.starts_with("Shenandoah") {
+            is_shenandoah = true;
+        } else if is_shenandoah && heap_info.used.is_none() {
+            // parse used size from second line
+            heap_info.used = parse_memory_from_heap_info(row, "used", true, &mut warnings);
+        } else if row.starts_with(" ZHeap")
+            || row.starts_with(" garbage-first")
+            // def new generation is serial - new gen
+            || row.starts_with(" def new generation")
+        {
+            heap_info.used = parse_memory_from_heap_info(row, "used", false, &mut warnings);
+        } else if row.starts_with(" tenured generation") && heap_info.used.is_some() {
+            // serial - old gen
+            let old_gen_size = parse_memory_from_heap_info(row, "used", false, &mut warnings);
+            if let Some(old_gen) = old_gen_size {
+                heap_info.used = heap_info.used.map(|young_gen| young_gen + old_gen);
+            }
+        } else if row.starts_with(" PSYoungGen") {
+            // parallel - new gen
+            heap_info.used = parse_memory_from_heap_info(row, "used", false, &mut warnings);
+        } else if row.starts_with(" ParOldGen") && heap_info.used.is_some() {
+            // parallel - old gen
+            let old_gen_size = parse_memory_from_heap_info(row, "used", false, &mut warnings);
+            if let Some(old_gen) = old_gen_size {
+                heap_info.used = heap_info.used.map(|young_gen| young_gen + old_gen);
+            }
+        } else if row.starts_with(" Metaspace") {
+            heap_info.metaspace = parse_space_info(row, &mut warnings);
+        } else if row.starts_with("  class space") {
+            heap_info.class_space = parse_space_info(row, &mut warnings);
+        }
+    }
+    warnings.into_parsed(heap_info)
+}
+
+fn parse_space_info(row: &str, warnings: &mut Warnings) -> SpaceInfo {
+    SpaceInfo {
+        used: parse_memory_from_heap_info(row, "used", false, warnings),
+        committed: parse_memory_from_heap_info(row, "committed", false, warnings),
+        reserved: parse_memory_from_heap_info(row, "reserved", false, warnings),
+    }
+}
+
+/// Returns the size next to `memory_type` in a heap info row. The size is expected after the
+/// memory type (`used 23456K`) or, if `reversed` is set, in front of it (`23456K used`).
+fn parse_memory_from_heap_info(
+    row: &str,
+    memory_type: &str,
+    reversed: bool,
+    warnings: &mut Warnings,
+) -> Option<u64> {
+    let parts: Vec<&str> = row.split(' ').collect();
+    for (i, p) in parts.iter().enumerate() {
+        if p == &memory_type {
+            let size_index = if reversed {
+                i.checked_sub(1)
+            } else {
+                Some(i + 1)
+            };
+            let size = size_index
+                .and_then(|i| parts.get(i))
+                .and_then(|size_string| parse_memory_size(size_string));
+            if size.is_none() {
+                warnings.add(row, format!("invalid {} size", memory_type));
+            }
+            return size;
+        }
+    }
+    None
+}
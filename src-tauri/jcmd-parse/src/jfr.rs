@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{command_failed, JcmdError, Parsed, Warnings};
+
+/// Attributes of a recording printed by `JFR.check`, in the order they are printed.
+const ATTRIBUTES: [&str; 4] = ["name=", "duration=", "maxsize=", "maxage="];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JfrRecordingState {
+    New,
+    Delayed,
+    Running,
+    Stopped,
+    Closed,
+}
+
+/// A flight recording listed by `JFR.check`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JfrRecording {
+    pub id: u32,
+    pub name: String,
+    /// Duration as printed by the jvm (e.g. `1m` or `30s`), not printed for recordings without
+    /// duration
+    pub duration: Option<String>,
+    /// Maximum size as printed by the jvm (e.g. `250.0MB`)
+    pub max_size: Option<String>,
+    /// Maximum age as printed by the jvm (e.g. `1d`)
+    pub max_age: Option<String>,
+    pub state: JfrRecordingState,
+}
+
+/// Parses the output of `jcmd <pid> JFR.check`.
+///
+/// Recordings are printed as `Recording 1: name=1 duration=1m maxsize=250.0MB (running)`. Without
+/// any recording, `No available recordings.` is printed.
+pub fn parse_jfr_check(output: &str) -> Parsed<Vec<JfrRecording>> {
+    let mut warnings = Warnings::new("JFR.check");
+    let mut recordings = Vec::new();
+    for row in output.split('\n') {
+        let row = row.trim();
+        if !row.starts_with("Recording ") {
+            continue;
+        }
+        match parse_recording(row) {
+            Ok(recording) => recordings.push(recording),
+            Err(message) => warnings.add(row, message),
+        }
+    }
+    warnings.into_parsed(recordings)
+}
+
+fn parse_recording(row: &str) -> Result<JfrRecording, &'static str> {
+    let (id, rest) = row
+        .trim_start_matches("Recording ")
+        .split_once(':')
+        .ok_or("missing recording id")?;
+    let id = id
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| "invalid recording id")?;
+    let (attributes, state) = rest
+        .trim()
+        .rsplit_once(" (")
+        .ok_or("missing recording state")?;
+    let state = match state.trim_end_matches(')') {
+        "new" => JfrRecordingState::New,
+        "delayed" => JfrRecordingState::Delayed,
+        "running" => JfrRecordingState::Running,
+        "stopped" => JfrRecordingState::Stopped,
+        "closed" => JfrRecordingState::Closed,
+        _ => return Err("unknown recording state"),
+    };
+    let attribute = |key: &str| attribute(attributes, key).map(|v| v.to_string());
+    Ok(JfrRecording {
+        id,
+        name: attribute("name=").ok_or("missing recording name")?,
+        duration: attribute("duration="),
+        max_size: attribute("maxsize="),
+        max_age: attribute("maxage="),
+        state,
+    })
+}
+
+/// Returns the value of the attribute, which ends where the next attribute starts. Names of
+/// recordings may contain spaces.
+fn attribute<'a>(attributes: &'a str, key: &str) -> Option<&'a str> {
+    let start = if attributes.starts_with(key) {
+        key.len()
+    } else {
+        attributes.find(&format!(" {}", key))? + key.len() + 1
+    };
+    let value = &attributes[start..];
+    let end = ATTRIBUTES
+        .iter()
+        .filter_map(|k| value.find(&format!(" {}", k)))
+        .min()
+        .unwrap_or(value.len());
+    Some(&value[..end])
+}
+
+/// Parses the output of `jcmd <pid> JFR.start` into the id of the started recording.
+///
+/// Recordings started with a delay are reported as `Recording 1 scheduled to start in 1 m`
+/// instead of `Started recording 1.`. Any other output is returned as
+/// [`JcmdError::CommandFailed`].
+pub fn parse_jfr_start(output: &str) -> Result<u32, JcmdError> {
+    output
+        .split('\n')
+        .find_map(|row| {
+            let row = row.trim();
+            let id = match row.strip_prefix("Started recording ") {
+                Some(started) => started.split(['.', ' ']).next(),
+                None if row.contains(" scheduled to start") => {
+                    row.strip_prefix("Recording ")?.split(' ').next()
+                }
+                None => None,
+            };
+            id?.parse::<u32>().ok()
+        })
+        .ok_or_else(|| command_failed("JFR.start", output))
+}
+
+/// Parses the output of `jcmd <pid> JFR.dump` into the path of the written file.
+///
+/// The path is printed on its own line after `Dumped recording "1", 1.2 MB written to:`.
+pub fn parse_jfr_dump(output: &str) -> Result<String, JcmdError> {
+    if !output.contains("Dumped recording") {
+        return Err(command_failed("JFR.dump", output));
+    }
+    output
+        .split('\n')
+        .skip_while(|row| !row.contains("Dumped recording"))
+        .skip(1)
+        .map(|row| row.trim())
+        .find(|row| !row.is_empty())
+        .map(|row| row.to_string())
+        .ok_or_else(|| command_failed("JFR.dump", output))
+}
+
+/// Checks the output of `jcmd <pid> JFR.stop` (e.g. `Stopped recording "1".`).
+pub fn parse_jfr_stop(output: &str) -> Result<(), JcmdError> {
+    if output.contains("Stopped recording") {
+        Ok(())
+    } else {
+        Err(command_failed("JFR.stop", output))
+    }
+}
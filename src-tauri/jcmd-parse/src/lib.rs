@@ -0,0 +1,48 @@
+//! Parsers for the output of JDK's `jcmd` diagnostic commands.
+//!
+//! All parsers are pure functions taking the captured stdout of a `jcmd` invocation and
+//! returning typed structs. Running `jcmd` itself is left to the caller.
+//!
+//! Parsers don't fail on unexpected lines. Those are skipped and reported as warnings of the
+//! [`Parsed`] result. Outputs signaling a failed command (e.g. the process is gone) can be
+//! detected with [`check_output`] before parsing.
+
+pub mod class_histogram;
+pub mod error;
+pub mod heap_dump;
+pub mod heap_info;
+pub mod jfr;
+pub mod nmt;
+pub mod processes;
+pub mod system_properties;
+pub mod thread_dump;
+pub mod units;
+pub mod vm_flags;
+pub mod vm_info;
+
+pub use class_histogram::{
+    parse_class_histogram, ClassHistogram, ClassHistogramDiff, ClassHistogramEntry,
+};
+pub use error::{check_output, JcmdError, Parsed};
+pub use heap_dump::{parse_heap_dump, HeapDumpResult};
+pub use heap_info::{parse_heap_info, HeapInfo, SpaceInfo};
+pub use jfr::{
+    parse_jfr_check, parse_jfr_dump, parse_jfr_start, parse_jfr_stop, JfrRecording,
+    JfrRecordingState,
+};
+pub use nmt::{
+    parse_nmt_detail, parse_nmt_diff, parse_nmt_summary, Allocations, CommittedRegion, Diff,
+    MallocDiff, MallocSite, MmapDiff, NmtCategory, NmtCategoryDiff, NmtDetail, NmtDiff, NmtSummary,
+    ReservedCommitted, VirtualMemoryRegion,
+};
+pub use processes::{parse_jvm_processes, JvmProcessRef};
+pub use system_properties::{parse_system_properties, SystemProperty};
+pub use thread_dump::{
+    parse_thread_dump, ApplicationThread, Deadlock, DeadlockedThread, JvmThread, LockAction,
+    LockChain, LockRecord, LockWait, LockedSynchronizer, StackFrame, ThreadDump, ThreadStateCounts,
+};
+pub use vm_flags::{parse_set_flag, parse_vm_flags, VmFlag, VmFlagOrigin};
+pub use vm_info::{
+    parse_vm_info, ContainerInfo, Cpus, DynamicLibrary, EventLog, HeapRegion, SignalHandler,
+    VmArguments, VmEvent, VmInfoAttribute, VmInfoReport, VmResources,
+};
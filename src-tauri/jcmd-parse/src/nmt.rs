@@ -0,0 +1,578 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Parsed, Warnings};
+
+/// Native memory summary parsed from `VM.native_memory scale=b`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NmtSummary {
+    pub total: Option<ReservedCommitted>,
+    /// Memory categories (e.g. `Java Heap`, `Class`, `Thread`) in the order reported by the jvm.
+    pub categories: Vec<NmtCategory>,
+}
+
+impl NmtSummary {
+    pub fn category(&self, name: &str) -> Option<&NmtCategory> {
+        self.categories.iter().find(|c| c.name == name)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservedCommitted {
+    pub reserved: Option<u64>,
+    pub committed: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NmtCategory {
+    pub name: String,
+    pub reserved: Option<u64>,
+    pub committed: Option<u64>,
+    /// `(malloc=<size> #<count>)`
+    pub malloc: Option<Allocations>,
+    /// `(mmap: reserved=<size>, committed=<size>)`
+    pub mmap: Option<ReservedCommitted>,
+    /// `(arena=<size> #<count>)`
+    pub arena: Option<Allocations>,
+    /// Thread stacks of the `Thread` category, `(stack: reserved=<size>, committed=<size>)`
+    pub stack: Option<ReservedCommitted>,
+    /// Loaded classes of the `Class` category, `(classes #<count>)`
+    pub class_count: Option<u64>,
+    /// Threads of the `Thread` category, `(thread #<count>)` or `(threads #<count>)` on newer jdks
+    pub thread_count: Option<u64>,
+}
+
+/// Memory allocated with malloc or in arenas and the number of allocations.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Allocations {
+    pub size: Option<u64>,
+    pub count: Option<u64>,
+}
+
+/// Parses the output of `jcmd <pid> VM.native_memory scale=b`.
+pub fn parse_nmt_summary(output: &str) -> Parsed<NmtSummary> {
+    let mut warnings = Warnings::new("VM.native_memory");
+    let mut summary = NmtSummary::default();
+    let mut buffer: Vec<&str> = Vec::new();
+
+    // Append an empty row to also flush the last block
+    for row in output.split('\n').chain(std::iter::once("")) {
+        if row.trim() == "" {
+            // The rows following a category row belong to the category
+            let mut in_category = false;
+            for buffer_row in buffer {
+                if buffer_row.starts_with("Total:") {
+                    in_category = false;
+                    let (reserved, committed) = parse_reserved_committed(buffer_row, &mut warnings);
+                    summary.total = Some(ReservedCommitted {
+                        reserved,
+                        committed,
+                    });
+                } else if buffer_row.starts_with('-') {
+                    in_category = true;
+                    let (name, reserved, committed) =
+                        parse_name_reserved_committed(buffer_row, &mut warnings);
+                    summary.categories.push(NmtCategory {
+                        name,
+                        reserved,
+                        committed,
+                        ..NmtCategory::default()
+                    });
+                } else if in_category {
+                    if let Some(category) = summary.categories.last_mut() {
+                        parse_category_row(buffer_row, category, &mut warnings);
+                    }
+                } // else ignore
+            }
+
+            // reset buffer
+            buffer = Vec::new();
+        } else {
+            buffer.push(row);
+        }
+    }
+    warnings.into_parsed(summary)
+}
+
+fn parse_reserved_committed(row: &str, warnings: &mut Warnings) -> (Option<u64>, Option<u64>) {
+    let mut reserved = None;
+    let mut committed = None;
+    for part in row.replace(['(', ')', ','], " ").split(' ') {
+        if let Some(value) = part.strip_prefix("reserved=") {
+            reserved = parse_size(row, "reserved", value, warnings);
+        } else if let Some(value) = part.strip_prefix("committed=") {
+            committed = parse_size(row, "committed", value, warnings);
+        }
+    }
+    (reserved, committed)
+}
+
+/// Parses a row of a category block like `(malloc=355528 #6812)` or
+/// `(stack: reserved=24150016, committed=1281536)`. Other rows (e.g. the metadata of the `Class`
+/// category) are ignored.
+fn parse_category_row(row: &str, category: &mut NmtCategory, warnings: &mut Warnings) {
+    let trimmed = row.trim().trim_start_matches('(').trim_start();
+    let Some(kind) = trimmed
+        .split(|c: char| c == '=' || c == ':' || c.is_whitespace())
+        .next()
+    else {
+        return;
+    };
+    if ![
+        "malloc", "mmap", "arena", "stack", "classes", "thread", "threads",
+    ]
+    .contains(&kind)
+    {
+        return;
+    }
+    let values = parse_diff_values(row, warnings);
+    let value = |name: &str| values.get(name).map(|d| d.value);
+    match kind {
+        "malloc" => {
+            category.malloc = Some(Allocations {
+                size: value("malloc"),
+                count: value("#"),
+            })
+        }
+        "arena" => {
+            category.arena = Some(Allocations {
+                size: value("arena"),
+                count: value("#"),
+            })
+        }
+        "mmap" | "stack" => {
+            let reserved_committed = Some(ReservedCommitted {
+                reserved: value("reserved"),
+                committed: value("committed"),
+            });
+            if kind == "mmap" {
+                category.mmap = reserved_committed;
+            } else {
+                category.stack = reserved_committed;
+            }
+        }
+        "classes" => category.class_count = value("#"),
+        _ => category.thread_count = value("#"),
+    }
+}
+
+fn parse_size(row: &str, name: &str, value: &str, warnings: &mut Warnings) -> Option<u64> {
+    let size = value.trim().parse::<u64>().ok();
+    if size.is_none() {
+        warnings.add(row, format!("invalid {} size", name));
+    }
+    size
+}
+
+fn parse_name_reserved_committed(
+    row: &str,
+    warnings: &mut Warnings,
+) -> (String, Option<u64>, Option<u64>) {
+    let name = match row.find('(') {
+        Some(index) => &row[..index],
+        None => row,
+    };
+    let name = name.trim_start_matches('-').trim().to_string();
+    let (reserved, committed) = parse_reserved_committed(row, warnings);
+    (name, reserved, committed)
+}
+
+/// Change of the native memory since the baseline, parsed from
+/// `VM.native_memory summary.diff scale=b`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NmtDiff {
+    pub total: Option<NmtCategoryDiff>,
+    /// Memory categories in the order reported by the jvm.
+    pub categories: Vec<NmtCategoryDiff>,
+}
+
+impl NmtDiff {
+    pub fn category(&self, name: &str) -> Option<&NmtCategoryDiff> {
+        self.categories.iter().find(|c| c.name == name)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NmtCategoryDiff {
+    pub name: String,
+    pub reserved: Option<Diff>,
+    pub committed: Option<Diff>,
+    pub malloc: Option<MallocDiff>,
+    pub mmap: Option<MmapDiff>,
+}
+
+/// A current value and its change since the baseline.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diff {
+    pub value: u64,
+    pub delta: i64,
+}
+
+/// Memory allocated with malloc and the number of allocations.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MallocDiff {
+    pub size: Option<Diff>,
+    pub count: Option<Diff>,
+}
+
+/// Memory mapped with mmap.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MmapDiff {
+    pub reserved: Option<Diff>,
+    pub committed: Option<Diff>,
+}
+
+/// Parses the output of `jcmd <pid> VM.native_memory summary.diff scale=b`.
+///
+/// Values without a change have no delta in the output, their delta is 0.
+pub fn parse_nmt_diff(output: &str) -> Parsed<NmtDiff> {
+    let mut warnings = Warnings::new("VM.native_memory summary.diff");
+    let mut diff = NmtDiff::default();
+    // The malloc and mmap rows belong to the last total or category row
+    let mut current: Option<NmtCategoryDiff> = None;
+
+    for row in output.split('\n') {
+        let trimmed = row.trim().trim_start_matches('(').trim_start();
+        if row.starts_with("Total:") || row.starts_with('-') {
+            if let Some(entry) = current.take() {
+                push_diff_entry(&mut diff, entry);
+            }
+            let name = if row.starts_with("Total:") {
+                "Total".to_string()
+            } else {
+                let end = row.find('(').unwrap_or(row.len());
+                row[..end].trim_start_matches('-').trim().to_string()
+            };
+            let values = parse_diff_values(row, &mut warnings);
+            current = Some(NmtCategoryDiff {
+                name,
+                reserved: values.get("reserved"),
+                committed: values.get("committed"),
+                malloc: None,
+                mmap: None,
+            });
+        } else if let Some(entry) = current.as_mut() {
+            if trimmed.starts_with("malloc") {
+                let values = parse_diff_values(row, &mut warnings);
+                entry.malloc = Some(MallocDiff {
+                    size: values.get("malloc"),
+                    count: values.get("#"),
+                });
+            } else if trimmed.starts_with("mmap:") {
+                let values = parse_diff_values(row, &mut warnings);
+                entry.mmap = Some(MmapDiff {
+                    reserved: values.get("reserved"),
+                    committed: values.get("committed"),
+                });
+            } // else ignore
+        }
+    }
+    if let Some(entry) = current.take() {
+        push_diff_entry(&mut diff, entry);
+    }
+    warnings.into_parsed(diff)
+}
+
+fn push_diff_entry(diff: &mut NmtDiff, entry: NmtCategoryDiff) {
+    if entry.name == "Total" {
+        diff.total = Some(entry);
+    } else {
+        diff.categories.push(entry);
+    }
+}
+
+/// Values of a row by name (e.g. `reserved`, `malloc` or `#` for counts). Rows of the summary
+/// have no deltas.
+struct DiffValues(Vec<(String, Diff)>);
+
+impl DiffValues {
+    fn get(&self, name: &str) -> Option<Diff> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, d)| *d)
+    }
+}
+
+/// Parses the values of a row like `(malloc=356552 +1024 #6818 +6)`, `(arena=27864 #44)` or
+/// `malloc: 31458624 +1024 #98240 +6`. Only the first value with a name is kept, so that peak
+/// values of newer jdks are skipped.
+fn parse_diff_values(row: &str, warnings: &mut Warnings) -> DiffValues {
+    let mut values: Vec<(String, Diff)> = Vec::new();
+    let tokens: Vec<&str> = row
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == ',')
+        .filter(|t| !t.is_empty())
+        .collect();
+    let mut tokens = tokens.into_iter().peekable();
+    let mut name: Option<String> = None;
+    while let Some(token) = tokens.next() {
+        let (value_name, value) = if let Some(count) = token.strip_prefix('#') {
+            name = None;
+            ("#".to_string(), count)
+        } else if let Some((value_name, value)) = token.split_once('=') {
+            name = None;
+            (value_name.to_string(), value)
+        } else if let Some(value_name) = token.strip_suffix(':') {
+            // `malloc: <size>` of the total
+            name = Some(value_name.to_string());
+            continue;
+        } else if let Some(value_name) = name.take() {
+            (value_name, token)
+        } else {
+            continue;
+        };
+        // Percentages (e.g. `waste=311552 =1.39%`) and other non-numeric values are skipped
+        if value.is_empty() || value.ends_with('%') {
+            continue;
+        }
+        let Ok(value) = value.parse::<u64>() else {
+            if value.starts_with(|c: char| c.is_ascii_digit()) {
+                warnings.add(row, format!("invalid {} value", value_name));
+            }
+            continue;
+        };
+        let delta = match tokens.peek() {
+            Some(delta) if delta.starts_with(['+', '-']) => match delta.parse::<i64>() {
+                Ok(delta) => {
+                    tokens.next();
+                    delta
+                }
+                Err(_) => {
+                    warnings.add(row, format!("invalid {} delta", value_name));
+                    0
+                }
+            },
+            _ => 0,
+        };
+        if !values.iter().any(|(n, _)| *n == value_name) {
+            values.push((value_name, Diff { value, delta }));
+        }
+    }
+    DiffValues(values)
+}
+
+/// Native memory with call sites, parsed from `VM.native_memory detail scale=b`. Only available if
+/// the jvm runs with `-XX:NativeMemoryTracking=detail`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NmtDetail {
+    pub summary: NmtSummary,
+    /// Call sites allocating memory with malloc, in the order reported by the jvm.
+    pub malloc_sites: Vec<MallocSite>,
+    /// Reserved virtual memory regions with the call sites reserving and committing them.
+    pub regions: Vec<VirtualMemoryRegion>,
+}
+
+/// A call site allocating memory with malloc.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MallocSite {
+    pub category: String,
+    pub size: u64,
+    pub count: Option<u64>,
+    /// Frames of the call stack, innermost first (e.g. `os::malloc(unsigned long)+0x2a`).
+    pub stack: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualMemoryRegion {
+    pub category: String,
+    pub start: String,
+    pub end: String,
+    pub reserved: u64,
+    /// Committed size of the region, the sum of the committed sub regions.
+    pub committed: u64,
+    pub stack: Vec<String>,
+    pub committed_regions: Vec<CommittedRegion>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommittedRegion {
+    pub start: String,
+    pub end: String,
+    pub size: u64,
+    pub stack: Vec<String>,
+}
+
+/// Parses the output of `jcmd <pid> VM.native_memory detail scale=b`.
+///
+/// The output starts with the summary, followed by the malloc and mmap call sites (`Details:`)
+/// and the `Virtual memory map:`. The mmap call sites are skipped as the regions of the virtual
+/// memory map contain the same stacks.
+pub fn parse_nmt_detail(output: &str) -> Parsed<NmtDetail> {
+    let summary_end = ["Details:", "Virtual memory map:"]
+        .iter()
+        .find_map(|header| find_row(output, header))
+        .unwrap_or(output.len());
+    let Parsed {
+        value: summary,
+        warnings: summary_warnings,
+    } = parse_nmt_summary(&output[..summary_end]);
+
+    let mut warnings = Warnings::new("VM.native_memory detail");
+    let mut detail = NmtDetail {
+        summary,
+        ..NmtDetail::default()
+    };
+    // Frames of the current call site
+    let mut stack: Vec<String> = Vec::new();
+    let mut in_map = false;
+
+    for row in output[summary_end..].split('\n') {
+        let trimmed = row.trim();
+        if trimmed.starts_with("Virtual memory map:") {
+            in_map = true;
+            stack.clear();
+        } else if trimmed.is_empty() {
+            if !in_map {
+                stack.clear();
+            }
+        } else if in_map {
+            parse_map_row(row, &mut detail.regions, &mut warnings);
+        } else if let Some(frame) = parse_frame(trimmed) {
+            stack.push(frame);
+        } else if trimmed.starts_with("(malloc=") {
+            if let Some(site) = parse_malloc_site(trimmed, &mut warnings) {
+                detail.malloc_sites.push(MallocSite {
+                    stack: std::mem::take(&mut stack),
+                    ..site
+                });
+            }
+        } else if trimmed.starts_with("(mmap:") {
+            stack.clear();
+        } // else ignore
+    }
+
+    let mut parsed = warnings.into_parsed(detail);
+    parsed.warnings.splice(0..0, summary_warnings);
+    parsed
+}
+
+/// Returns the index of the first row starting with the prefix.
+fn find_row(output: &str, prefix: &str) -> Option<usize> {
+    if output.starts_with(prefix) {
+        return Some(0);
+    }
+    output.find(&format!("\n{}", prefix)).map(|index| index + 1)
+}
+
+/// Returns the frame of a stack row like `[0x00007f5c3b6f6d4b] Arena::grow(unsigned long)+0x41`.
+fn parse_frame(trimmed: &str) -> Option<String> {
+    let rest = trimmed.strip_prefix("[0x")?;
+    let (address, frame) = rest.split_once(']')?;
+    if address.contains(' ') {
+        // a region like `[0x... - 0x...]`
+        return None;
+    }
+    let frame = frame.trim();
+    Some(if frame.is_empty() {
+        format!("0x{}", address)
+    } else {
+        frame.to_string()
+    })
+}
+
+/// Parses a row like `(malloc=65536 type=Arena Chunk #2)`, optionally followed by
+/// `(at peak)` or `(peak=... #...)` on newer jdks.
+fn parse_malloc_site(trimmed: &str, warnings: &mut Warnings) -> Option<MallocSite> {
+    let end = trimmed.find(')').unwrap_or(trimmed.len());
+    let values = &trimmed["(malloc=".len()..end];
+    let (size, rest) = values.split_once(' ').unwrap_or((values, ""));
+    let Ok(size) = size.parse::<u64>() else {
+        warnings.add(trimmed, "invalid malloc size");
+        return None;
+    };
+    let (category, count) = match rest.rsplit_once('#') {
+        Some((category, count)) => (category, count.trim().parse::<u64>().ok()),
+        None => (rest, None),
+    };
+    let category = category
+        .trim()
+        .trim_start_matches("type=")
+        .trim_start_matches("tag=")
+        .trim();
+    if category.is_empty() {
+        warnings.add(trimmed, "missing malloc type");
+        return None;
+    }
+    Some(MallocSite {
+        category: category.to_string(),
+        size,
+        count,
+        stack: Vec::new(),
+    })
+}
+
+/// Parses a row of the virtual memory map. Reserved regions look like
+/// `[0x00000000c0000000 - 0x0000000100000000] reserved 1073741824 for Java Heap from`, committed
+/// regions are indented and look like `[0x... - 0x...] committed 67108864 from`. All other rows
+/// are frames of the last region.
+fn parse_map_row(row: &str, regions: &mut Vec<VirtualMemoryRegion>, warnings: &mut Warnings) {
+    let trimmed = row.trim();
+    if let Some(frame) = parse_frame(trimmed) {
+        let Some(region) = regions.last_mut() else {
+            return;
+        };
+        match region.committed_regions.last_mut() {
+            Some(committed) => committed.stack.push(frame),
+            None => region.stack.push(frame),
+        }
+        return;
+    }
+    let Some((range, rest)) = trimmed.strip_prefix('[').and_then(|r| r.split_once(']')) else {
+        return;
+    };
+    let Some((start, end)) = range.split_once(" - ") else {
+        warnings.add(row, "invalid memory range");
+        return;
+    };
+    let (start, end) = (start.trim().to_string(), end.trim().to_string());
+    let rest = rest.trim();
+    let rest = rest.strip_suffix("from").unwrap_or(rest).trim_end();
+
+    if let Some(rest) = rest.strip_prefix("committed ") {
+        let Some(region) = regions.last_mut() else {
+            warnings.add(row, "committed region without reserved region");
+            return;
+        };
+        let Ok(size) = rest.trim().parse::<u64>() else {
+            warnings.add(row, "invalid committed size");
+            return;
+        };
+        region.committed += size;
+        region.committed_regions.push(CommittedRegion {
+            start,
+            end,
+            size,
+            stack: Vec::new(),
+        });
+    } else if let Some(rest) = rest.strip_prefix("reserved ") {
+        let (rest, fully_committed) = match rest.strip_prefix("and committed ") {
+            Some(rest) => (rest, true),
+            None => (rest, false),
+        };
+        let (size, category) = rest.split_once(" for ").unwrap_or((rest, ""));
+        let Ok(reserved) = size.trim().parse::<u64>() else {
+            warnings.add(row, "invalid reserved size");
+            return;
+        };
+        regions.push(VirtualMemoryRegion {
+            category: category.trim().to_string(),
+            start,
+            end,
+            reserved,
+            committed: if fully_committed { reserved } else { 0 },
+            stack: Vec::new(),
+            committed_regions: Vec::new(),
+        });
+    } else {
+        warnings.add(row, "unknown memory region");
+    }
+}
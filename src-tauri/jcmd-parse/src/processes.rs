@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JvmProcessRef {
+    pub id: String,
+    pub name: String,
+    pub path: Option<String>,
+}
+
+/// Parses the process list printed by `jcmd` when it is called without arguments.
+///
+/// The `jcmd` process itself is not part of the result.
+pub fn parse_jvm_processes(output: &str) -> Vec<JvmProcessRef> {
+    let mut processes: Vec<JvmProcessRef> = Vec::new();
+    for line in output.split('\n') {
+        if line.contains("jdk.jcmd") {
+            continue;
+        }
+        let parts: Vec<&str> = line.split(' ').collect();
+        if parts.len() < 2 {
+            continue;
+        }
+        let path = if parts.len() >= 3 {
+            Some(parts[2..].join(" "))
+        } else {
+            None
+        };
+        processes.push(JvmProcessRef {
+            id: parts[0].to_string(),
+            name: parts[1].to_string(),
+            path,
+        })
+    }
+    processes
+}
+
+/// Checks whether the row is the pid (e.g. `48213:`), which `jcmd` prints before the output of a
+/// command.
+pub(crate) fn is_pid_row(row: &str) -> bool {
+    row.trim()
+        .strip_suffix(':')
+        .is_some_and(|pid| !pid.is_empty() && pid.chars().all(|c| c.is_ascii_digit()))
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Parsed, Warnings};
+use crate::processes::is_pid_row;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemProperty {
+    pub key: String,
+    pub value: String,
+}
+
+/// Parses the output of `jcmd <pid> VM.system_properties`.
+///
+/// The properties are printed in the format of `java.util.Properties` files: `key=value` rows
+/// with escaped separators (e.g. `path.separator=\:`), line breaks (`\n`) and non-latin-1
+/// characters (`\u00FC`), preceded by a `#` comment holding the time. The properties keep the
+/// order of the output, which is not sorted.
+pub fn parse_system_properties(output: &str) -> Parsed<Vec<SystemProperty>> {
+    let mut warnings = Warnings::new("VM.system_properties");
+    let mut properties = Vec::new();
+    for row in output.split('\n') {
+        let trimmed = row.trim_start();
+        if trimmed.trim_end().is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.starts_with('!')
+            || is_pid_row(trimmed)
+        {
+            continue;
+        }
+        let Some(separator) = separator(trimmed) else {
+            warnings.add(row, "missing separator");
+            continue;
+        };
+        let key = unescape(&trimmed[..separator]);
+        let value = unescape(trimmed[separator + 1..].trim_start());
+        match (key, value) {
+            (Some(key), Some(value)) => properties.push(SystemProperty { key, value }),
+            _ => warnings.add(row, "invalid escape sequence"),
+        }
+    }
+    warnings.into_parsed(properties)
+}
+
+/// Returns the index of the first unescaped `=` or `:`.
+fn separator(row: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in row.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '=' | ':' if !escaped => return Some(index),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            't' => unescaped.push('\t'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            'f' => unescaped.push('\u{c}'),
+            'u' => {
+                let code: String = chars.by_ref().take(4).collect();
+                let code = u32::from_str_radix(&code, 16).ok()?;
+                unescaped.push(char::from_u32(code)?);
+            }
+            other => unescaped.push(other),
+        }
+    }
+    Some(unescaped)
+}
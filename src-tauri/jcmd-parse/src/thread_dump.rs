@@ -0,0 +1,671 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Parsed, Warnings};
+use crate::units::parse_thread_time;
+
+/// Threads parsed from `Thread.print -e`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadDump {
+    pub application_threads: Vec<ApplicationThread>,
+    pub jvm_threads: Vec<JvmThread>,
+    /// Deadlocks reported by the jvm below the threads (`Found one Java-level deadlock:`)
+    #[serde(default)]
+    pub deadlocks: Vec<Deadlock>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationThread {
+    pub name: String,
+    pub id: u32,
+    pub daemon: bool,
+    pub prio: u16,
+    /// Negative on windows, e.g. `os_prio=-2`
+    pub os_thread_prio: i32,
+    /// Consumed cpu time in milliseconds as reported by the jvm.
+    //https://www.linkedin.com/pulse/getting-java-thread-cpu-utilization-vishalendu-pandey
+    pub cpu: f32,
+    pub elapsed: f32,
+    pub allocated: String,
+    pub defined_classes: u16,
+    pub thread_id: String,
+    pub os_thread_id: String,
+    pub status: String,
+    pub last_known_java_stack_pointer: String,
+    /// Stack frames, innermost first
+    #[serde(default)]
+    pub stack: Vec<StackFrame>,
+    /// `Locked ownable synchronizers` (e.g. a `ReentrantLock`), only listed by `Thread.print -l`
+    #[serde(default)]
+    pub locked_synchronizers: Vec<LockedSynchronizer>,
+}
+
+/// A frame of a stack, e.g. `at com.example.Worker.run(Worker.java:24)`, with the locks listed
+/// below it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackFrame {
+    /// Class and method (e.g. `com.example.Worker.run`)
+    pub method: String,
+    /// Module, file and line (e.g. `java.base@17.0.9/Thread.java:840`) or `Native Method`
+    pub location: String,
+    pub locks: Vec<LockRecord>,
+}
+
+/// A lock line of a stack frame, e.g. `- waiting to lock <0x0000000712a4b6d8> (a java.lang.Object)`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockRecord {
+    pub action: LockAction,
+    /// Address of the lock object, missing if it isn't available (e.g. `<no object reference
+    /// available>`)
+    pub address: Option<String>,
+    pub class_name: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LockAction {
+    /// `locked`: the monitor is held by the thread
+    Locked,
+    /// `waiting to lock`: the thread is blocked on entering the monitor
+    WaitingToLock,
+    /// `waiting on`: the thread released the monitor in `Object.wait()`
+    WaitingOn,
+    /// `waiting to re-lock in wait()`: the thread is blocked on entering the monitor again after
+    /// `Object.wait()`
+    WaitingToRelock,
+    /// `parking to wait for`: the thread is parked on a `java.util.concurrent` lock or condition
+    ParkingToWaitFor,
+    /// `eliminated`: the lock was removed by the jit compiler
+    Eliminated,
+}
+
+/// A `java.util.concurrent` synchronizer owned by a thread, e.g.
+/// `- <0x0000000712b01a88> (a java.util.concurrent.locks.ReentrantLock$NonfairSync)`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedSynchronizer {
+    pub address: String,
+    pub class_name: String,
+}
+
+/// A cycle of threads reported by the jvm, each waiting for a lock held by the next one.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deadlock {
+    pub threads: Vec<DeadlockedThread>,
+}
+
+/// A thread of a [`Deadlock`], e.g.
+/// ```text
+/// "worker-a":
+///   waiting to lock monitor 0x00007f3c4c003200 (object 0x0000000712a4b6d8, a java.lang.Object),
+///   which is held by "worker-b"
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadlockedThread {
+    pub name: String,
+    /// `waiting to lock` for monitors, `parking to wait for` for ownable synchronizers
+    pub lock: LockRecord,
+    /// Name of the thread holding the lock
+    pub held_by: String,
+}
+
+/// Threads waiting for locks held by the next thread of the chain, from the waits-for graph of a
+/// thread dump.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockChain {
+    /// The last thread waits for the first one
+    pub deadlock: bool,
+    pub threads: Vec<LockWait>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockWait {
+    pub name: String,
+    /// Java thread id, missing if a thread of a deadlock reported by the jvm isn't in the dump
+    pub id: Option<u32>,
+    /// Lock held by the next thread, missing for the last thread of a chain which isn't a deadlock
+    pub lock: Option<LockRecord>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JvmThread {
+    pub name: String,
+    pub os_thread_prio: i32,
+    /// Consumed cpu time in milliseconds as reported by the jvm.
+    pub cpu: f32,
+    pub elapsed: f32,
+    pub thread_id: String,
+    pub os_thread_id: String,
+    pub status: String,
+}
+
+/// Number of threads per thread state.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadStateCounts {
+    pub new_count: u32,
+    pub runnable_count: u32,
+    pub waiting_count: u32,
+    pub timed_waiting_count: u32,
+    pub blocked_count: u32,
+}
+
+impl ThreadDump {
+    pub fn application_thread_states(&self) -> ThreadStateCounts {
+        let mut counts = ThreadStateCounts::default();
+        for thread in &self.application_threads {
+            // The java.lang.Thread class contains a static State enum
+            if thread.status.starts_with("NEW") {
+                counts.new_count += 1;
+            } else if thread.status.starts_with("RUNNABLE") {
+                counts.runnable_count += 1;
+            } else if thread.status.starts_with("TIMED_WAITING") {
+                counts.timed_waiting_count += 1;
+            } else if thread.status.starts_with("WAITING") {
+                counts.waiting_count += 1;
+            } else if thread.status.starts_with("BLOCKED") {
+                counts.blocked_count += 1;
+            }
+        }
+        counts
+    }
+
+    pub fn jvm_thread_states(&self) -> ThreadStateCounts {
+        let mut counts = ThreadStateCounts::default();
+        for thread in &self.jvm_threads {
+            if thread.status.starts_with("new") {
+                counts.new_count += 1;
+            } else if thread.status.starts_with("runnable") {
+                counts.runnable_count += 1;
+            } else if thread.status.starts_with("waiting on condition") {
+                // There's no separation of thread state waiting and timed waiting for jvm threads
+                counts.waiting_count += 1;
+            } else if thread.status.starts_with("blocked") {
+                counts.blocked_count += 1;
+            }
+        }
+        counts
+    }
+
+    /// Returns the deadlocks and the chains of at least `min_length` threads waiting for each
+    /// other.
+    ///
+    /// Deadlocks come first, starting with the ones reported by the jvm. The waits-for graph
+    /// built from the locks of the stacks finds the others, e.g. of `ReentrantLock`s which are
+    /// only known with the locked ownable synchronizers (`-l`). A thread waits for the thread
+    /// holding the monitor it is waiting to lock (again) or the synchronizer it is parking for.
+    pub fn lock_chains(&self, min_length: usize) -> Vec<LockChain> {
+        let threads = &self.application_threads;
+        let mut owners = HashMap::new();
+        for (index, thread) in threads.iter().enumerate() {
+            for address in thread.held_locks() {
+                owners.insert(address, index);
+            }
+        }
+        // The lock each thread is waiting for and the index of the thread holding it
+        let waits_for: Vec<Option<(&LockRecord, usize)>> = threads
+            .iter()
+            .enumerate()
+            .map(|(index, thread)| {
+                thread
+                    .stack
+                    .iter()
+                    .flat_map(|f| &f.locks)
+                    .filter(|l| l.action.is_waiting())
+                    .find_map(|l| {
+                        let owner = *owners.get(l.address.as_deref()?)?;
+                        (owner != index).then_some((l, owner))
+                    })
+            })
+            .collect();
+
+        let mut chains: Vec<LockChain> = self
+            .deadlocks
+            .iter()
+            .map(|d| LockChain {
+                deadlock: true,
+                threads: d
+                    .threads
+                    .iter()
+                    .map(|t| LockWait {
+                        name: t.name.clone(),
+                        id: threads.iter().find(|a| a.name == t.name).map(|a| a.id),
+                        lock: Some(t.lock.clone()),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let mut deadlocked: HashSet<&str> = self
+            .deadlocks
+            .iter()
+            .flat_map(|d| &d.threads)
+            .map(|t| t.name.as_str())
+            .collect();
+        let waited_for: HashSet<usize> = waits_for.iter().flatten().map(|(_, o)| *o).collect();
+
+        // Chains start at threads nobody waits for. Cycles have no such thread, so all threads
+        // are followed until a known deadlock or a thread seen before is reached.
+        let starts = (0..threads.len())
+            .filter(|i| !waited_for.contains(i))
+            .chain(0..threads.len());
+        let mut visited = HashSet::new();
+        for start in starts {
+            let mut path = Vec::new();
+            let mut index = start;
+            let mut cycle_start = None;
+            loop {
+                if deadlocked.contains(threads[index].name.as_str()) {
+                    break;
+                }
+                if let Some(position) = path.iter().position(|i| *i == index) {
+                    cycle_start = Some(position);
+                    break;
+                }
+                path.push(index);
+                match waits_for[index] {
+                    Some((_, owner)) => index = owner,
+                    None => break,
+                }
+            }
+            // Chains of the second pass are the tails of the chains found before
+            let new = path.iter().filter(|i| visited.insert(**i)).count() > 0;
+            let lock_wait = |index: usize| LockWait {
+                name: threads[index].name.clone(),
+                id: Some(threads[index].id),
+                lock: waits_for[index].map(|(lock, _)| lock.clone()),
+            };
+            if let Some(position) = cycle_start {
+                let cycle = &path[position..];
+                deadlocked.extend(cycle.iter().map(|i| threads[*i].name.as_str()));
+                chains.push(LockChain {
+                    deadlock: true,
+                    threads: cycle.iter().map(|i| lock_wait(*i)).collect(),
+                });
+            } else if new && path.len() >= min_length && waits_for[*path.last().unwrap()].is_none()
+            {
+                chains.push(LockChain {
+                    deadlock: false,
+                    threads: path.iter().map(|i| lock_wait(*i)).collect(),
+                });
+            }
+        }
+        chains.sort_by_key(|c| !c.deadlock);
+        chains
+    }
+}
+
+impl ApplicationThread {
+    /// Returns the addresses of the monitors and synchronizers held by the thread.
+    ///
+    /// A thread in `Object.wait()` still lists the monitor as `locked` in the frame which entered
+    /// it, although it released the monitor (`waiting on`) or is blocked on entering it again
+    /// (`waiting to re-lock in wait()`). Such monitors aren't held.
+    pub fn held_locks(&self) -> Vec<&str> {
+        let locks = || self.stack.iter().flat_map(|f| &f.locks);
+        let released: HashSet<&str> = locks()
+            .filter(|l| {
+                matches!(
+                    l.action,
+                    LockAction::WaitingOn | LockAction::WaitingToRelock
+                )
+            })
+            .filter_map(|l| l.address.as_deref())
+            .collect();
+        let monitors = locks()
+            .filter(|l| l.action == LockAction::Locked)
+            .filter_map(|l| l.address.as_deref())
+            .filter(|a| !released.contains(a));
+        let synchronizers = self.locked_synchronizers.iter().map(|s| s.address.as_str());
+        monitors.chain(synchronizers).collect()
+    }
+}
+
+impl LockAction {
+    /// Whether the thread is blocked on the lock (and not in `Object.wait()`)
+    pub fn is_waiting(self) -> bool {
+        matches!(
+            self,
+            LockAction::WaitingToLock | LockAction::WaitingToRelock | LockAction::ParkingToWaitFor
+        )
+    }
+}
+
+const DEADLOCK_HEADER: &str = "Found one Java-level deadlock:";
+
+/// Parses the output of `jcmd <pid> Thread.print -e` (optionally with `-l`).
+///
+/// Threads with a java thread id (`#<id>`) are reported as application threads, all others
+/// (gc, compiler, vm threads) as jvm threads.
+// Intro to thread dumps: https://dzone.com/articles/how-to-read-a-thread-dump
+pub fn parse_thread_dump(output: &str) -> Parsed<ThreadDump> {
+    let mut warnings = Warnings::new("Thread.print");
+    let mut thread_dump = ThreadDump::default();
+
+    // The deadlock report follows the threads. Its rows look like threads, but aren't.
+    let (threads, deadlock_report) = match output.find(DEADLOCK_HEADER) {
+        Some(index) => output.split_at(index),
+        None => (output, ""),
+    };
+    thread_dump.deadlocks = parse_deadlocks(deadlock_report, &mut warnings);
+
+    for block in threads.split("\n\n") {
+        let rows: Vec<&str> = block.split('\n').collect();
+        let first_row = rows[0];
+        if first_row.trim() == "Locked ownable synchronizers:" {
+            // Printed after the stack of the thread, separated by an empty line
+            if let Some(thread) = thread_dump.application_threads.last_mut() {
+                thread.locked_synchronizers = parse_locked_synchronizers(&rows[1..], &mut warnings);
+            }
+            continue;
+        }
+        if !first_row.starts_with('\"') {
+            continue;
+        }
+
+        let thread_name_end_quote = first_row.rfind('\"').unwrap_or(0);
+        if thread_name_end_quote == 0 {
+            warnings.add(first_row, "missing end of thread name");
+            continue;
+        }
+        let name = first_row[1..thread_name_end_quote].to_string();
+        let rest = &first_row[thread_name_end_quote + 1..];
+
+        if rest.contains('#') {
+            let mut status = "".to_string();
+            if rows.len() > 1 {
+                let thread_state_input = rows[1].replace("java.lang.Thread.State:", "");
+                let thread_state_parts: Vec<&str> =
+                    thread_state_input.split(' ').filter(|p| p != &"").collect();
+                status = thread_state_parts.join(" ");
+            }
+            let mut thread = parse_application_thread(name, rest, status, &mut warnings);
+            thread.stack = parse_stack(rows.get(2..).unwrap_or_default(), &mut warnings);
+            thread_dump.application_threads.push(thread);
+        } else {
+            thread_dump
+                .jvm_threads
+                .push(parse_jvm_thread(name, rest, &mut warnings));
+        }
+    }
+    warnings.into_parsed(thread_dump)
+}
+
+fn parse_application_thread(
+    name: String,
+    rest: &str,
+    status: String,
+    warnings: &mut Warnings,
+) -> ApplicationThread {
+    let mut id = 0;
+    let mut daemon = false;
+    let mut prio = 0;
+    let mut os_thread_prio = 0;
+    let mut cpu = 0.0;
+    let mut elapsed = 0.0;
+    let mut allocated = "".to_string();
+    let mut defined_classes = 0;
+    let mut tid = "".to_string();
+    let mut nid = "".to_string();
+    let mut last_known_java_stack_pointer = "".to_string();
+    for e in rest.split(' ') {
+        if e.starts_with('#') {
+            id = parse_number(rest, e, "#", warnings);
+        } else if e == "daemon" {
+            daemon = true;
+        } else if e.starts_with("prio=") {
+            prio = parse_number(rest, e, "prio=", warnings);
+        } else if e.starts_with("cpu=") {
+            cpu = parse_time(rest, e, "cpu=", warnings);
+        } else if e.starts_with("elapsed=") {
+            elapsed = parse_time(rest, e, "elapsed=", warnings);
+        } else if e.starts_with("allocated=") {
+            allocated = e.replace("allocated=", "");
+        } else if e.starts_with("defined_classes=") {
+            defined_classes = parse_number(rest, e, "defined_classes=", warnings);
+        } else if e.starts_with("os_prio=") {
+            os_thread_prio = parse_number(rest, e, "os_prio=", warnings);
+        } else if e.starts_with("tid=") {
+            tid = e.replace("tid=", "");
+        } else if e.starts_with("nid=") {
+            nid = e.replace("nid=", "");
+        } else if e.starts_with('[') {
+            last_known_java_stack_pointer = e.replace(['[', ']'], "");
+        }
+    }
+
+    ApplicationThread {
+        name,
+        id,
+        daemon,
+        prio,
+        os_thread_prio,
+        cpu,
+        elapsed,
+        allocated,
+        defined_classes,
+        thread_id: tid,
+        os_thread_id: nid,
+        status,
+        last_known_java_stack_pointer,
+        stack: Vec::new(),
+        locked_synchronizers: Vec::new(),
+    }
+}
+
+/// Parses the frames of a stack and the locks listed below each frame. Other rows (e.g. `No
+/// compile task` of compiler threads) are skipped.
+fn parse_stack(rows: &[&str], warnings: &mut Warnings) -> Vec<StackFrame> {
+    let mut stack: Vec<StackFrame> = Vec::new();
+    for row in rows {
+        let row = row.trim();
+        if let Some(frame) = row.strip_prefix("at ") {
+            let (method, location) = frame.split_once('(').unwrap_or((frame, ""));
+            stack.push(StackFrame {
+                method: method.to_string(),
+                location: location.trim_end_matches(')').to_string(),
+                locks: Vec::new(),
+            });
+        } else if let Some(lock) = row.strip_prefix("- ") {
+            match (parse_lock(lock), stack.last_mut()) {
+                (Some(lock), Some(frame)) => frame.locks.push(lock),
+                _ => warnings.add(row, "invalid lock"),
+            }
+        }
+    }
+    stack
+}
+
+fn parse_lock(lock: &str) -> Option<LockRecord> {
+    let actions = [
+        ("locked ", LockAction::Locked),
+        ("waiting to lock ", LockAction::WaitingToLock),
+        ("waiting on ", LockAction::WaitingOn),
+        ("waiting to re-lock in wait() ", LockAction::WaitingToRelock),
+        ("parking to wait for ", LockAction::ParkingToWaitFor),
+        ("eliminated ", LockAction::Eliminated),
+    ];
+    let (object, action) = actions
+        .iter()
+        .find_map(|(prefix, action)| lock.strip_prefix(prefix).map(|o| (o.trim(), *action)))?;
+    let (address, class_name) = parse_lock_object(object);
+    Some(LockRecord {
+        action,
+        address,
+        class_name,
+    })
+}
+
+/// Parses a lock object like `<0x0000000712a4b6d8> (a java.lang.Object)`.
+fn parse_lock_object(object: &str) -> (Option<String>, Option<String>) {
+    let address = object
+        .strip_prefix("<0x")
+        .and_then(|o| o.split_once('>'))
+        .map(|(address, _)| format!("0x{}", address));
+    let class_name = object
+        .split_once("(a ")
+        .map(|(_, class_name)| class_name.trim_end_matches(')').to_string());
+    (address, class_name)
+}
+
+fn parse_locked_synchronizers(rows: &[&str], warnings: &mut Warnings) -> Vec<LockedSynchronizer> {
+    let mut synchronizers = Vec::new();
+    for row in rows {
+        let synchronizer = row.trim().trim_start_matches("- ");
+        if synchronizer == "None" || synchronizer.is_empty() {
+            continue;
+        }
+        match parse_lock_object(synchronizer) {
+            (Some(address), Some(class_name)) => synchronizers.push(LockedSynchronizer {
+                address,
+                class_name,
+            }),
+            _ => warnings.add(row, "invalid locked synchronizer"),
+        }
+    }
+    synchronizers
+}
+
+/// Parses the deadlocks reported by the jvm. Each deadlock lists the threads with the lock they
+/// are waiting for, followed by their stacks which are skipped (they are part of the dump):
+/// ```text
+/// Found one Java-level deadlock:
+/// =============================
+/// "worker-a":
+///   waiting to lock monitor 0x00007f3c4c003200 (object 0x0000000712a4b6d8, a java.lang.Object),
+///   which is held by "worker-b"
+///
+/// "worker-b":
+///   waiting to lock monitor 0x00007f3c4c006500 (object 0x0000000712a4b6e8, a java.lang.Object),
+///   which is held by "worker-a"
+///
+/// Java stack information for the threads listed above:
+/// ===================================================
+/// ...
+/// Found 1 deadlock.
+/// ```
+fn parse_deadlocks(report: &str, warnings: &mut Warnings) -> Vec<Deadlock> {
+    let mut deadlocks: Vec<Deadlock> = Vec::new();
+    let mut in_stacks = false;
+    let mut name = None;
+    let mut lock = None;
+    for row in report.lines() {
+        let row = row.trim();
+        if row == DEADLOCK_HEADER {
+            deadlocks.push(Deadlock::default());
+            in_stacks = false;
+        } else if row == "Java stack information for the threads listed above:" {
+            in_stacks = true;
+        } else if in_stacks
+            || row.is_empty()
+            || row.starts_with("===")
+            || (row.starts_with("Found ") && row.contains(" deadlock"))
+        {
+            continue;
+        } else if let Some(thread) = row.strip_prefix('"').and_then(|r| r.strip_suffix("\":")) {
+            name = Some(thread.to_string());
+        } else if let Some(monitor) = row.strip_prefix("waiting to lock monitor ") {
+            lock = parse_deadlock_lock(LockAction::WaitingToLock, monitor);
+        } else if let Some(synchronizer) = row.strip_prefix("waiting for ownable synchronizer ") {
+            lock = parse_deadlock_lock(LockAction::ParkingToWaitFor, synchronizer);
+        } else if let Some(owner) = row.strip_prefix("which is held by ") {
+            match (name.take(), lock.take(), deadlocks.last_mut()) {
+                (Some(name), Some(lock), Some(deadlock)) => {
+                    deadlock.threads.push(DeadlockedThread {
+                        name,
+                        lock,
+                        held_by: owner.trim_matches('"').to_string(),
+                    })
+                }
+                _ => warnings.add(row, "invalid deadlock"),
+            }
+        } else {
+            warnings.add(row, "unknown row of deadlock");
+        }
+    }
+    deadlocks
+}
+
+/// Parses the lock of a deadlocked thread, e.g. `0x00007f3c4c003200 (object 0x0000000712a4b6d8,
+/// a java.lang.Object),` of a monitor or `0x0000000712b01a88, (a java.util.concurrent.locks.
+/// ReentrantLock$NonfairSync),` of an ownable synchronizer.
+fn parse_deadlock_lock(action: LockAction, lock: &str) -> Option<LockRecord> {
+    // The address of a monitor isn't the one of the object shown in the stacks
+    let (address, class_name) = match lock.split_once("(object ") {
+        Some((_, object)) => object.split_once(", a ")?,
+        None => lock.split_once(", (a ")?,
+    };
+    Some(LockRecord {
+        action,
+        address: Some(address.trim().to_string()),
+        class_name: Some(
+            class_name
+                .trim_end_matches(',')
+                .trim_end_matches(')')
+                .to_string(),
+        ),
+    })
+}
+
+fn parse_jvm_thread(name: String, rest: &str, warnings: &mut Warnings) -> JvmThread {
+    let mut os_thread_prio = 0;
+    let mut cpu = 0.0;
+    let mut elapsed = 0.0;
+    let mut tid = "".to_string();
+    let mut nid = "".to_string();
+    let mut status: Vec<&str> = Vec::new();
+    for e in rest.split(' ') {
+        if e.starts_with("os_prio=") {
+            os_thread_prio = parse_number(rest, e, "os_prio=", warnings);
+        } else if e.starts_with("cpu=") {
+            cpu = parse_time(rest, e, "cpu=", warnings);
+        } else if e.starts_with("elapsed=") {
+            elapsed = parse_time(rest, e, "elapsed=", warnings);
+        } else if e.starts_with("tid=") {
+            tid = e.replace("tid=", "");
+        } else if e.starts_with("nid=") {
+            nid = e.replace("nid=", "");
+        } else if !nid.is_empty() && !e.is_empty() && !e.starts_with('[') {
+            status.push(e);
+        }
+    }
+
+    JvmThread {
+        name,
+        os_thread_prio,
+        cpu,
+        elapsed,
+        thread_id: tid,
+        os_thread_id: nid,
+        status: status.join(" "),
+    }
+}
+
+fn parse_number<T: FromStr + Default>(
+    row: &str,
+    e: &str,
+    prefix: &str,
+    warnings: &mut Warnings,
+) -> T {
+    e.replace(prefix, "").parse().unwrap_or_else(|_| {
+        warnings.add(row, format!("invalid value of {}", e));
+        T::default()
+    })
+}
+
+fn parse_time(row: &str, e: &str, column: &str, warnings: &mut Warnings) -> f32 {
+    parse_thread_time(e, column).unwrap_or_else(|| {
+        warnings.add(row, format!("invalid value of {}", e));
+        0.0
+    })
+}
@@ -0,0 +1,45 @@
+/// Parses a memory size as printed by `GC.heap_info` (e.g. `23456K,` or `40M`) into bytes. Sizes
+/// beyond `u64` aren't valid.
+pub fn parse_memory_size(value: &str) -> Option<u64> {
+    let size_unit_factor: u64 = if value.contains('K') {
+        1024
+    } else if value.contains('M') {
+        1024 * 1024
+    } else if value.contains('G') {
+        1024 * 1024 * 1024
+    } else if value.contains('T') {
+        1024 * 1024 * 1024 * 1024
+    } else {
+        1
+    };
+
+    let size = value
+        .replace(['K', 'M', 'G', 'T', ','], "")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    size.checked_mul(size_unit_factor)
+}
+
+/// Parses a thread time attribute (e.g. `cpu=123.45ms` or `elapsed=12.34s`) into milliseconds.
+pub fn parse_thread_time(e: &str, column: &str) -> Option<f32> {
+    let factor = if e.contains("ms") {
+        1
+    } else if e.contains('m') {
+        60 * 1000
+    } else if e.contains('s') {
+        1000
+    } else {
+        1
+    };
+    let time = e
+        .replace(column, "")
+        .replace("ms", "")
+        .replace('s', "")
+        // On linux it seem to be required to replace the comma
+        // with a dot to be able to parse the number
+        .replace(',', ".")
+        .parse::<f32>()
+        .ok()?;
+    Some(time * factor as f32)
+}
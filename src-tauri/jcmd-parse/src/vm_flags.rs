@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{command_failed, JcmdError, Parsed, Warnings};
+use crate::processes::is_pid_row;
+
+/// Where the value of a flag was set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VmFlagOrigin {
+    Default,
+    CommandLine,
+    Environment,
+    ConfigFile,
+    Management,
+    Ergonomic,
+    Attach,
+    Internal,
+    Jimage,
+}
+
+/// A flag printed by `VM.flags -all`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VmFlag {
+    pub name: String,
+    /// Type of the value (e.g. `bool`, `uintx`, `size_t` or `ccstr`)
+    #[serde(rename = "type")]
+    pub flag_type: String,
+    pub value: String,
+    /// Kinds of the flag (e.g. `product`, `pd`, `manageable` or `diagnostic`)
+    pub kinds: Vec<String>,
+    pub origin: VmFlagOrigin,
+}
+
+impl VmFlag {
+    /// Manageable flags can be changed at runtime with `VM.set_flag`.
+    pub fn is_manageable(&self) -> bool {
+        self.kinds.iter().any(|k| k == "manageable")
+    }
+}
+
+/// Parses the output of `jcmd <pid> VM.flags -all`.
+///
+/// Rows look like `     bool UseG1GC   = true   {product} {ergonomic}`, where the value may be
+/// empty.
+pub fn parse_vm_flags(output: &str) -> Parsed<Vec<VmFlag>> {
+    let mut warnings = Warnings::new("VM.flags");
+    let mut flags = Vec::new();
+    for row in output.split('\n') {
+        let trimmed = row.trim();
+        // Skip the `[Global flags]` header
+        if trimmed.is_empty() || trimmed.starts_with('[') || is_pid_row(trimmed) {
+            continue;
+        }
+        match parse_flag(trimmed) {
+            Ok(flag) => flags.push(flag),
+            Err(message) => warnings.add(row, message),
+        }
+    }
+    warnings.into_parsed(flags)
+}
+
+fn parse_flag(row: &str) -> Result<VmFlag, &'static str> {
+    let (rest, origin) = row.rsplit_once(" {").ok_or("missing origin")?;
+    let origin = match origin.trim_end_matches('}') {
+        "default" => VmFlagOrigin::Default,
+        "command line" => VmFlagOrigin::CommandLine,
+        "environment" => VmFlagOrigin::Environment,
+        "config file" => VmFlagOrigin::ConfigFile,
+        "management" => VmFlagOrigin::Management,
+        "ergonomic" => VmFlagOrigin::Ergonomic,
+        "attach" => VmFlagOrigin::Attach,
+        "internal" => VmFlagOrigin::Internal,
+        "jimage" => VmFlagOrigin::Jimage,
+        _ => return Err("unknown origin"),
+    };
+    let (rest, kinds) = rest.trim_end().rsplit_once(" {").ok_or("missing kind")?;
+    // Type and name don't contain `=`, but values may
+    let (declaration, value) = rest.split_once('=').ok_or("missing value")?;
+    let mut declaration = declaration.split_whitespace();
+    let (Some(flag_type), Some(name), None) =
+        (declaration.next(), declaration.next(), declaration.next())
+    else {
+        return Err("invalid type or name");
+    };
+    Ok(VmFlag {
+        name: name.to_string(),
+        flag_type: flag_type.to_string(),
+        value: value.trim().to_string(),
+        kinds: kinds
+            .trim_end_matches('}')
+            .split_whitespace()
+            .map(|k| k.to_string())
+            .collect(),
+        origin,
+    })
+}
+
+/// Checks the output of `jcmd <pid> VM.set_flag <name> <value>`, which prints nothing but the pid
+/// on success and the reason otherwise (e.g. `only 'writeable' flags can be set`).
+pub fn parse_set_flag(output: &str) -> Result<(), JcmdError> {
+    if output
+        .split('\n')
+        .all(|row| row.trim().is_empty() || is_pid_row(row))
+    {
+        Ok(())
+    } else {
+        Err(command_failed("VM.set_flag", output))
+    }
+}
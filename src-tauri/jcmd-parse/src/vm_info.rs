@@ -0,0 +1,488 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{Parsed, Warnings};
+use crate::units::parse_memory_size;
+
+/// Report of `VM.info` split into its sections. Sections missing in the output (e.g. the heap
+/// regions of collectors other than G1) are left empty.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct VmInfoReport {
+    /// `JRE version` of the header
+    pub jre_version: Option<String>,
+    /// `Java VM` of the header, including the mode and the gc (e.g. `mixed mode, ..., g1 gc`)
+    pub vm_version: Option<String>,
+    pub command_line: Option<String>,
+    pub host: Option<String>,
+    pub time: Option<String>,
+    /// Uptime of the jvm
+    pub elapsed_seconds: Option<f64>,
+    pub vm_arguments: Option<VmArguments>,
+    pub vm_resources: Option<VmResources>,
+    /// All entries of the `GC Precious Log`
+    pub gc_precious_log: Vec<VmInfoAttribute>,
+    /// Regions of the G1 heap (`Heap Regions`)
+    pub heap_regions: Vec<HeapRegion>,
+    /// Event logs (e.g. `Compilation events`, `GC Heap History` or `Classes loaded`) in the order
+    /// of the output
+    pub event_logs: Vec<EventLog>,
+    pub dynamic_libraries: Vec<DynamicLibrary>,
+    pub environment_variables: Vec<VmInfoAttribute>,
+    pub signal_handlers: Vec<SignalHandler>,
+    /// `OS` section (e.g. the release, `uname`, `OS uptime`, `libc` and `rlimit`)
+    pub os: Vec<VmInfoAttribute>,
+    /// `container (cgroup) information` section
+    pub container: Vec<VmInfoAttribute>,
+    /// Limits and usage of the `container (cgroup) information` section
+    pub container_info: Option<ContainerInfo>,
+    pub cpu: Option<String>,
+    pub memory: Option<String>,
+    /// `vm_info` with the version and build of the jvm
+    pub build: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VmArguments {
+    pub jvm_args: Option<String>,
+    pub java_command: Option<String>,
+}
+
+/// Resources of the `GC Precious Log`. Sizes are in bytes.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct VmResources {
+    #[serde(deserialize_with = "deserialize_cpus")]
+    pub cpus: Option<Cpus>,
+    /// Memory available to the jvm (the physical memory or the limit of the container)
+    #[serde(deserialize_with = "deserialize_size")]
+    pub memory: Option<u64>,
+    #[serde(deserialize_with = "deserialize_size")]
+    pub heap_size_min: Option<u64>,
+    #[serde(deserialize_with = "deserialize_size")]
+    pub heap_size_init: Option<u64>,
+    #[serde(deserialize_with = "deserialize_size")]
+    pub heap_size_max: Option<u64>,
+    /// `memory_limit_in_bytes` of the `container (cgroup) information`, missing if the memory of
+    /// the container is not limited
+    pub container_memory_limit: Option<u64>,
+}
+
+/// Limits and usage of the cgroup of the jvm. Sizes are in bytes. Values which are `unlimited`,
+/// `not supported` (e.g. by cgroup v2) or not set (e.g. `no quota`) are missing.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ContainerInfo {
+    /// `cgroupv1` or `cgroupv2`
+    pub container_type: Option<String>,
+    pub active_processor_count: Option<u32>,
+    /// Cpu time in microseconds the container may use per `cpu_period`
+    pub cpu_quota: Option<u64>,
+    pub cpu_period: Option<u64>,
+    pub cpu_shares: Option<u64>,
+    pub memory_limit: Option<u64>,
+    pub memory_and_swap_limit: Option<u64>,
+    pub memory_soft_limit: Option<u64>,
+    pub memory_usage: Option<u64>,
+    pub memory_max_usage: Option<u64>,
+    pub rss_usage: Option<u64>,
+    pub cache_usage: Option<u64>,
+    pub current_tasks: Option<u64>,
+    pub maximum_tasks: Option<u64>,
+}
+
+/// Cpus of the machine and the ones available to the jvm (e.g. `8 total, 2 available`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cpus {
+    pub total: u32,
+    pub available: u32,
+}
+
+/// A `name: value` (or `NAME=value`) row of a section.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VmInfoAttribute {
+    pub name: String,
+    pub value: String,
+}
+
+/// A region of the G1 heap, e.g.
+/// `|   1|0x0000000700200000, 0x00000007002d1a40, 0x0000000700400000| 40%| O|  |TAMS ...`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeapRegion {
+    pub index: u32,
+    pub bottom: String,
+    pub top: String,
+    pub end: String,
+    pub used_percent: u8,
+    /// Type of the region (e.g. `E` for eden, `S` for survivor, `O` for old, `H` for humongous
+    /// or `F` for free)
+    #[serde(rename = "type")]
+    pub region_type: String,
+    /// Whether the region is part of the collection set of the next gc
+    pub collection_set: bool,
+}
+
+/// The latest events of an event log. `count` is the number of events logged in total, of
+/// which only the latest ones are printed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventLog {
+    pub name: String,
+    pub count: Option<u32>,
+    pub events: Vec<VmEvent>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VmEvent {
+    /// Seconds since the start of the jvm
+    pub time: f64,
+    /// Description of the event, which may span several lines (e.g. the heap before and after a
+    /// gc)
+    pub description: String,
+}
+
+/// A memory mapping of a library or file (`Dynamic libraries`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicLibrary {
+    /// Address range of the mapping (e.g. `7f3c93a00000-7f3c94a1a000`)
+    pub address: String,
+    /// Permissions of the mapping (e.g. `r-xp`)
+    pub permissions: String,
+    /// Mapped file, missing for anonymous mappings
+    pub path: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalHandler {
+    pub signal: String,
+    /// Handler and library (e.g. `crash_handler in libjvm.so`)
+    pub handler: String,
+    pub mask: Option<String>,
+    pub flags: Option<String>,
+}
+
+/// Parses the report of `jcmd <pid> VM.info`. Missing `VM Arguments` and `GC Precious Log`
+/// sections are reported as warnings, other sections are optional.
+pub fn parse_vm_info(output: &str) -> Parsed<VmInfoReport> {
+    let mut warnings = Warnings::new("VM.info");
+    let mut report = VmInfoReport::default();
+
+    for block in output.split("\n\n") {
+        let rows: Vec<&str> = block.split('\n').filter(|r| !r.trim().is_empty()).collect();
+        let Some(first_row) = rows.first().copied() else {
+            continue;
+        };
+        if first_row.starts_with("VM Arguments:") {
+            report.vm_arguments = Some(parse_vm_arguments(&rows));
+        } else if first_row.starts_with("GC Precious Log:") {
+            report.vm_resources = Some(parse_vm_resources(&rows, &mut warnings));
+            report.gc_precious_log = attributes(&rows[1..], ": ");
+        } else if first_row.starts_with("Heap Regions:") {
+            report.heap_regions = parse_heap_regions(&rows[1..], &mut warnings);
+        } else if let Some((name, count)) = event_log_header(first_row) {
+            report.event_logs.push(EventLog {
+                name: name.to_string(),
+                count,
+                events: parse_events(&rows[1..], &mut warnings),
+            });
+        } else if first_row.starts_with("Dynamic libraries:") {
+            report.dynamic_libraries = parse_dynamic_libraries(&rows[1..]);
+        } else if first_row.starts_with("Environment Variables:") {
+            report.environment_variables = attributes(&rows[1..], "=");
+        } else if first_row.starts_with("Signal Handlers:") {
+            report.signal_handlers = parse_signal_handlers(&rows[1..], &mut warnings);
+        } else if first_row.starts_with("OS:") {
+            report.os = rows[1..]
+                .iter()
+                .filter_map(|row| attribute(row, ": ").or_else(|| attribute(row, "=")))
+                .collect();
+        } else if first_row.starts_with("container (cgroup) information:") {
+            report.container = attributes(&rows[1..], ": ");
+            report.container_info = Some(parse_container_info(&report.container));
+        } else {
+            parse_rows(&rows, &mut report);
+        }
+    }
+    if report.vm_arguments.is_none() {
+        warnings.add("VM Arguments:", "section not found");
+    }
+    if let Some(resources) = report.vm_resources.as_mut() {
+        resources.container_memory_limit =
+            report.container_info.as_ref().and_then(|c| c.memory_limit);
+    } else {
+        warnings.add("GC Precious Log:", "section not found");
+    }
+    warnings.into_parsed(report)
+}
+
+/// Picks the single rows of the header, the summary and the system sections.
+fn parse_rows(rows: &[&str], report: &mut VmInfoReport) {
+    for row in rows {
+        let value = |prefix: &str| row.strip_prefix(prefix).map(|v| v.trim().to_string());
+        if let Some(jre_version) = value("# JRE version:") {
+            report.jre_version = Some(jre_version);
+        } else if let Some(vm_version) = value("# Java VM:") {
+            report.vm_version = Some(vm_version);
+        } else if let Some(command_line) = value("Command Line:") {
+            report.command_line = Some(command_line);
+        } else if let Some(host) = value("Host:") {
+            report.host = Some(host);
+        } else if let Some(time) = value("Time:") {
+            // `Mon Jan 15 10:42:17 2024 CET elapsed time: 62.412345 seconds (0d 0h 1m 2s)`
+            let (time, elapsed) = time.split_once(" elapsed time: ").unwrap_or((&time, ""));
+            report.elapsed_seconds = elapsed
+                .split_whitespace()
+                .next()
+                .and_then(|s| s.parse().ok());
+            report.time = Some(time.to_string());
+        } else if let Some(cpu) = value("CPU:") {
+            report.cpu = Some(cpu);
+        } else if let Some(memory) = value("Memory:") {
+            report.memory = Some(memory);
+        } else if let Some(build) = value("vm_info:") {
+            report.build = Some(build);
+        }
+    }
+}
+
+fn attributes(rows: &[&str], separator: &str) -> Vec<VmInfoAttribute> {
+    rows.iter()
+        .filter_map(|row| attribute(row, separator))
+        .collect()
+}
+
+fn attribute(row: &str, separator: &str) -> Option<VmInfoAttribute> {
+    let (name, value) = row.split_once(separator)?;
+    Some(VmInfoAttribute {
+        name: name.trim().to_string(),
+        value: value.trim().to_string(),
+    })
+}
+
+/// Parses headers of event logs like `Compilation events (250 events):`.
+fn event_log_header(row: &str) -> Option<(&str, Option<u32>)> {
+    let (name, count) = row.strip_suffix(" events):")?.rsplit_once(" (")?;
+    Some((name, count.parse().ok()))
+}
+
+fn parse_events(rows: &[&str], warnings: &mut Warnings) -> Vec<VmEvent> {
+    let mut events: Vec<VmEvent> = Vec::new();
+    for row in rows {
+        if let Some(event) = row.strip_prefix("Event: ") {
+            let (time, description) = event.split_once(' ').unwrap_or((event, ""));
+            match time.parse::<f64>() {
+                Ok(time) => events.push(VmEvent {
+                    time,
+                    description: description.trim_end().to_string(),
+                }),
+                Err(_) => warnings.add(row, "invalid event time"),
+            }
+        } else if let Some(event) = events.last_mut() {
+            // Continuation of a multi-line event
+            event.description.push('\n');
+            event.description.push_str(row.trim_end());
+        }
+    }
+    events
+}
+
+fn parse_heap_regions(rows: &[&str], warnings: &mut Warnings) -> Vec<HeapRegion> {
+    let mut regions = Vec::new();
+    for row in rows {
+        let columns: Vec<&str> = row.split('|').map(|c| c.trim()).collect();
+        // The first column is empty, as rows start with `|`
+        if columns.len() < 6 {
+            warnings.add(row, "invalid heap region");
+            continue;
+        }
+        let addresses: Vec<&str> = columns[2].split(',').map(|a| a.trim()).collect();
+        let (Ok(index), Ok(used_percent), [bottom, top, end]) = (
+            columns[1].parse::<u32>(),
+            columns[3].trim_end_matches('%').parse::<u8>(),
+            addresses.as_slice(),
+        ) else {
+            warnings.add(row, "invalid heap region");
+            continue;
+        };
+        regions.push(HeapRegion {
+            index,
+            bottom: bottom.to_string(),
+            top: top.to_string(),
+            end: end.to_string(),
+            used_percent,
+            region_type: columns[4].to_string(),
+            collection_set: columns[5] == "CS",
+        });
+    }
+    regions
+}
+
+/// Parses rows like `7f3c93a00000-7f3c94a1a000 r-xp 00000000 08:02 2232011   /lib/libjvm.so`.
+fn parse_dynamic_libraries(rows: &[&str]) -> Vec<DynamicLibrary> {
+    rows.iter()
+        .filter_map(|row| {
+            let mut columns = row.split_whitespace();
+            let address = columns.next()?;
+            let permissions = columns.next()?;
+            // Skip offset, device and inode
+            let path = columns.skip(3).collect::<Vec<&str>>().join(" ");
+            Some(DynamicLibrary {
+                address: address.to_string(),
+                permissions: permissions.to_string(),
+                path: (!path.is_empty()).then_some(path),
+            })
+        })
+        .collect()
+}
+
+/// Parses rows like `SIGSEGV: crash_handler in libjvm.so, mask=11100100..., flags=SA_RESTART`.
+fn parse_signal_handlers(rows: &[&str], warnings: &mut Warnings) -> Vec<SignalHandler> {
+    let mut handlers = Vec::new();
+    for row in rows {
+        let Some((signal, rest)) = row.trim().split_once(": ") else {
+            warnings.add(row, "invalid signal handler");
+            continue;
+        };
+        let mut parts = rest.split(", ");
+        let handler = parts.next().unwrap_or_default().to_string();
+        let mut mask = None;
+        let mut flags = None;
+        for part in parts {
+            if let Some(value) = part.strip_prefix("mask=") {
+                mask = Some(value.to_string());
+            } else if let Some(value) = part.strip_prefix("flags=") {
+                flags = Some(value.to_string());
+            }
+        }
+        handlers.push(SignalHandler {
+            signal: signal.to_string(),
+            handler,
+            mask,
+            flags,
+        });
+    }
+    handlers
+}
+
+fn parse_vm_arguments(rows: &[&str]) -> VmArguments {
+    let mut jvm_args = None;
+    let mut java_command = None;
+    for row in rows {
+        if let Some(value) = row.strip_prefix("jvm_args:") {
+            jvm_args = Some(value.trim().to_string())
+        } else if let Some(value) = row.strip_prefix("java_command:") {
+            java_command = Some(value.trim().to_string())
+        }
+    }
+    VmArguments {
+        jvm_args,
+        java_command,
+    }
+}
+
+fn parse_vm_resources(rows: &[&str], warnings: &mut Warnings) -> VmResources {
+    let mut resources = VmResources::default();
+    for row in rows {
+        let Some((name, value)) = row.trim().split_once(": ") else {
+            continue;
+        };
+        let mut size = || {
+            let size = parse_memory_size(value);
+            if size.is_none() {
+                warnings.add(row, "invalid size");
+            }
+            size
+        };
+        match name {
+            "CPUs" => {
+                resources.cpus = parse_cpus(value);
+                if resources.cpus.is_none() {
+                    warnings.add(row, "invalid cpus");
+                }
+            }
+            "Memory" => resources.memory = size(),
+            // zgc omits the `Heap` prefix
+            "Heap Min Capacity" | "Min Capacity" => resources.heap_size_min = size(),
+            "Heap Initial Capacity" | "Initial Capacity" => resources.heap_size_init = size(),
+            "Heap Max Capacity" | "Max Capacity" => resources.heap_size_max = size(),
+            _ => {}
+        }
+    }
+    resources
+}
+
+/// Parses the cpus of the `GC Precious Log` (e.g. `8 total, 8 available`).
+fn parse_cpus(value: &str) -> Option<Cpus> {
+    let (total, available) = value.split_once(", ")?;
+    Some(Cpus {
+        total: total.strip_suffix(" total")?.parse().ok()?,
+        available: available.strip_suffix(" available")?.parse().ok()?,
+    })
+}
+
+/// Parses the attributes of the `container (cgroup) information` section (e.g.
+/// `memory_limit_in_bytes: 1048576 k` or `cpu_quota: no quota`).
+fn parse_container_info(attributes: &[VmInfoAttribute]) -> ContainerInfo {
+    let mut container = ContainerInfo::default();
+    for attribute in attributes {
+        let value = attribute.value.as_str();
+        let count = value.parse::<u64>().ok();
+        let size = parse_memory_size(&value.to_uppercase());
+        match attribute.name.as_str() {
+            "container_type" => container.container_type = Some(value.to_string()),
+            "active_processor_count" => {
+                container.active_processor_count = value.parse().ok();
+            }
+            "cpu_quota" => container.cpu_quota = count,
+            "cpu_period" => container.cpu_period = count,
+            "cpu_shares" => container.cpu_shares = count,
+            "memory_limit_in_bytes" => container.memory_limit = size,
+            "memory_and_swap_limit_in_bytes" => container.memory_and_swap_limit = size,
+            "memory_soft_limit_in_bytes" => container.memory_soft_limit = size,
+            "memory_usage_in_bytes" => container.memory_usage = size,
+            "memory_max_usage_in_bytes" => container.memory_max_usage = size,
+            "rss_usage_in_bytes" => container.rss_usage = size,
+            "cache_usage_in_bytes" => container.cache_usage = size,
+            "current number of tasks" => container.current_tasks = count,
+            "maximum number of tasks" => container.maximum_tasks = count,
+            _ => {}
+        }
+    }
+    container
+}
+
+/// Session files written before the resources were typed contain the sizes as strings (e.g.
+/// `4G`).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SizeValue {
+    Bytes(u64),
+    Text(String),
+}
+
+fn deserialize_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    Ok(match Option::<SizeValue>::deserialize(deserializer)? {
+        Some(SizeValue::Bytes(bytes)) => Some(bytes),
+        Some(SizeValue::Text(text)) => parse_memory_size(&text),
+        None => None,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CpusValue {
+    Counts(Cpus),
+    Text(String),
+}
+
+fn deserialize_cpus<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Cpus>, D::Error> {
+    Ok(match Option::<CpusValue>::deserialize(deserializer)? {
+        Some(CpusValue::Counts(cpus)) => Some(cpus),
+        Some(CpusValue::Text(text)) => parse_cpus(&text),
+        None => None,
+    })
+}
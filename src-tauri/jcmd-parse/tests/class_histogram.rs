@@ -0,0 +1,85 @@
+use jcmd_parse::{parse_class_histogram, ClassHistogramEntry, Diff};
+
+#[test]
+fn parses_classes_and_total() {
+    let parsed = parse_class_histogram(include_str!("fixtures/jdk17/g1/GC.class_histogram.txt"));
+
+    assert_eq!(parsed.warnings, vec![]);
+    let histogram = parsed.value;
+    assert_eq!(histogram.entries.len(), 20);
+    assert_eq!(
+        histogram.entries[0],
+        ClassHistogramEntry {
+            instances: 60912,
+            bytes: 9341424,
+            class_name: "[B".to_string(),
+            module: Some("java.base@17.0.2".to_string()),
+        }
+    );
+    // Classes of unnamed modules have no module
+    let entry = &histogram.entries[8];
+    assert_eq!(entry.class_name, "com.example.cache.CacheEntry");
+    assert_eq!(entry.module, None);
+    assert_eq!(
+        histogram.entries[14].class_name,
+        "com.example.Application$$Lambda$312/0x0000000800d2b6a8"
+    );
+    assert_eq!(histogram.total_instances, Some(228253));
+    assert_eq!(histogram.total_bytes, Some(17610648));
+}
+
+#[test]
+fn diffs_histograms_by_growth() {
+    let earlier = parse_class_histogram(
+        "   1:          100           1600  java.lang.String (java.base@17.0.2)\n   2:           10            480  com.example.cache.CacheEntry\n   3:            5            120  com.example.Gone\nTotal          115           2200\n",
+    )
+    .value;
+    let later = parse_class_histogram(
+        "   1:         2000          96000  com.example.cache.CacheEntry\n   2:           90           1440  java.lang.String (java.base@17.0.2)\n   3:           40            640  java.lang.String\nTotal         2130          98080\n",
+    )
+    .value;
+
+    let diff = later.diff(&earlier);
+
+    assert_eq!(diff.len(), 3);
+    assert_eq!(diff[0].class_name, "com.example.cache.CacheEntry");
+    assert_eq!(
+        diff[0].bytes,
+        Diff {
+            value: 96000,
+            delta: 95520
+        }
+    );
+    // Classes with the same name are summed up
+    assert_eq!(diff[1].class_name, "java.lang.String");
+    assert_eq!(
+        diff[1].instances,
+        Diff {
+            value: 130,
+            delta: 30
+        }
+    );
+    // Classes missing in the later histogram shrank to 0
+    assert_eq!(diff[2].class_name, "com.example.Gone");
+    assert_eq!(
+        diff[2].bytes,
+        Diff {
+            value: 0,
+            delta: -120
+        }
+    );
+}
+
+#[test]
+fn reports_unparsable_rows_as_warning() {
+    let parsed = parse_class_histogram(
+        "   1:          100           1.6K  java.lang.String (java.base@17.0.2)\n   2:           10            480  com.example.cache.CacheEntry\n",
+    );
+
+    assert_eq!(parsed.value.entries.len(), 1);
+    assert_eq!(parsed.warnings.len(), 1);
+    assert_eq!(
+        parsed.warnings[0].to_string(),
+        "GC.class_histogram output couldn't be parsed: invalid bytes count (1:          100           1.6K  java.lang.String (java.base@17.0.2))"
+    );
+}
@@ -0,0 +1,196 @@
+use jcmd_parse::units::parse_memory_size;
+use jcmd_parse::{
+    check_output, parse_class_histogram, parse_heap_info, parse_nmt_detail, parse_nmt_diff,
+    parse_nmt_summary, parse_thread_dump, JcmdError,
+};
+
+#[test]
+fn detects_terminated_process() {
+    let output = "48213:\ncom.sun.tools.attach.AttachNotSupportedException: java.io.IOException: No such process\n";
+    assert_eq!(check_output(output), Err(JcmdError::ProcessGone));
+}
+
+#[test]
+fn detects_refused_attach() {
+    let output = "48213:\ncom.sun.tools.attach.AttachNotSupportedException: Unable to open socket file /proc/48213/root/tmp/.java_pid48213: target process 48213 doesn't respond within 10500ms or HotSpot VM not loaded\n\tat jdk.attach/sun.tools.attach.VirtualMachineImpl.<init>(VirtualMachineImpl.java:100)\n";
+    match check_output(output) {
+        Err(JcmdError::AttachRefused { message }) => {
+            assert!(message.starts_with("com.sun.tools.attach.AttachNotSupportedException"))
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn detects_disabled_native_memory_tracking() {
+    let output = "48213:\nNative memory tracking is not enabled\n";
+    assert_eq!(check_output(output), Err(JcmdError::NmtDisabled));
+    assert_eq!(
+        check_output(include_str!("fixtures/jdk17/g1/VM.native_memory.txt")),
+        Ok(())
+    );
+}
+
+#[test]
+fn detects_missing_native_memory_baseline() {
+    let output = "48213:\nNo baseline for comparison\n";
+    assert_eq!(check_output(output), Err(JcmdError::NoNmtBaseline));
+}
+
+#[test]
+fn detects_disabled_native_memory_details() {
+    let output = "48213:\nDetail tracking is not enabled\n";
+    assert_eq!(check_output(output), Err(JcmdError::NmtDetailDisabled));
+}
+
+#[test]
+fn reports_unparsable_heap_size_as_warning() {
+    let output = " garbage-first heap   total 260096K, used 33.7M [0x0000000707800000, 0x0000000800000000)\n Metaspace       used 21456K, committed 21824K, reserved 1114112K\n";
+    let parsed = parse_heap_info(output);
+
+    assert_eq!(parsed.value.used, None);
+    assert_eq!(parsed.value.metaspace.used, Some(21456 * 1024));
+    assert_eq!(
+        parsed.warnings,
+        vec![JcmdError::Parse {
+            command: "GC.heap_info".to_string(),
+            line: "garbage-first heap   total 260096K, used 33.7M [0x0000000707800000, 0x0000000800000000)".to_string(),
+            message: "invalid used size".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn keeps_remaining_categories_on_unparsable_native_memory_line() {
+    let output = "Total: reserved=1000, committed=500\n\n-                 Java Heap (reserved=4GB, committed=256MB)\n \n-                     Class (reserved=200, committed=100)\n";
+    let parsed = parse_nmt_summary(output);
+
+    assert_eq!(parsed.value.categories.len(), 2);
+    assert_eq!(parsed.value.categories[0].reserved, None);
+    assert_eq!(parsed.value.categories[1].committed, Some(100));
+    assert_eq!(parsed.warnings.len(), 2);
+}
+
+#[test]
+fn parses_negative_os_priority_of_windows_threads() {
+    let output = "\"main\" #1 prio=5 os_prio=-2 cpu=15.63ms elapsed=12.34s tid=0x000001b8 nid=0x2a3c waiting on condition  [0x0000001c]\n   java.lang.Thread.State: RUNNABLE\n\n\"VM Thread\" os_prio=-2 cpu=4.21ms elapsed=12.40s tid=0x000001a0 nid=0x2a30 runnable  \n";
+    let parsed = parse_thread_dump(output);
+
+    assert_eq!(parsed.warnings, vec![]);
+    let main = &parsed.value.application_threads[0];
+    assert_eq!(main.name, "main");
+    assert_eq!(main.os_thread_prio, -2);
+    assert_eq!(main.cpu, 15.63);
+    assert_eq!(parsed.value.jvm_threads[0].os_thread_prio, -2);
+}
+
+#[test]
+fn keeps_thread_with_unexpected_header_values() {
+    let output = "\"main\" #1 prio=5 os_prio=high cpu=15.63ms elapsed=12.34s tid=0x000001b8 nid=0x2a3c waiting on condition  [0x0000001c]\n   java.lang.Thread.State: RUNNABLE\n";
+    let parsed = parse_thread_dump(output);
+
+    let main = &parsed.value.application_threads[0];
+    assert_eq!(main.os_thread_prio, 0);
+    assert_eq!(main.cpu, 15.63);
+    assert_eq!(parsed.warnings.len(), 1);
+    assert_eq!(
+        parsed.warnings[0].to_string(),
+        "Thread.print output couldn't be parsed: invalid value of os_prio=high (#1 prio=5 os_prio=high cpu=15.63ms elapsed=12.34s tid=0x000001b8 nid=0x2a3c waiting on condition  [0x0000001c])"
+    );
+}
+
+#[test]
+fn rejects_memory_sizes_beyond_u64() {
+    assert_eq!(parse_memory_size("16777215T"), Some(16777215 << 40));
+    assert_eq!(parse_memory_size("16777216T"), None);
+    assert_eq!(parse_memory_size("18446744073709551615G"), None);
+
+    let output = " garbage-first heap   total 260096K, used 17179869184G [0x0000000707800000, 0x0000000800000000)\n";
+    let parsed = parse_heap_info(output);
+    assert_eq!(parsed.value.used, None);
+    assert_eq!(parsed.warnings.len(), 1);
+    assert_eq!(
+        parsed.warnings[0].to_string(),
+        "GC.heap_info output couldn't be parsed: invalid used size (garbage-first heap   total 260096K, used 17179869184G [0x0000000707800000, 0x0000000800000000))"
+    );
+}
+
+#[test]
+fn parses_fixtures_without_warnings() {
+    for heap_info in [
+        include_str!("fixtures/jdk17/g1/GC.heap_info.txt"),
+        include_str!("fixtures/jdk17/zgc/GC.heap_info.txt"),
+        include_str!("fixtures/jdk17/shenandoah/GC.heap_info.txt"),
+        include_str!("fixtures/jdk17/serial/GC.heap_info.txt"),
+        include_str!("fixtures/jdk17/parallel/GC.heap_info.txt"),
+    ] {
+        assert_eq!(parse_heap_info(heap_info).warnings, vec![]);
+    }
+    for native_memory in [
+        include_str!("fixtures/jdk17/g1/VM.native_memory.txt"),
+        include_str!("fixtures/jdk17/zgc/VM.native_memory.txt"),
+        include_str!("fixtures/jdk17/shenandoah/VM.native_memory.txt"),
+        include_str!("fixtures/jdk17/serial/VM.native_memory.txt"),
+        include_str!("fixtures/jdk17/parallel/VM.native_memory.txt"),
+    ] {
+        assert_eq!(parse_nmt_summary(native_memory).warnings, vec![]);
+    }
+    assert_eq!(
+        parse_nmt_diff(include_str!(
+            "fixtures/jdk17/g1/VM.native_memory.summary.diff.txt"
+        ))
+        .warnings,
+        vec![]
+    );
+    assert_eq!(
+        parse_class_histogram(include_str!("fixtures/jdk17/g1/GC.class_histogram.txt")).warnings,
+        vec![]
+    );
+    assert_eq!(
+        parse_nmt_detail(include_str!(
+            "fixtures/jdk17/g1/VM.native_memory.detail.txt"
+        ))
+        .warnings,
+        vec![]
+    );
+    for thread_dump in [
+        include_str!("fixtures/jdk17/g1/Thread.print.txt"),
+        include_str!("fixtures/jdk17/zgc/Thread.print.txt"),
+        include_str!("fixtures/jdk17/shenandoah/Thread.print.txt"),
+        include_str!("fixtures/jdk17/serial/Thread.print.txt"),
+        include_str!("fixtures/jdk17/parallel/Thread.print.txt"),
+    ] {
+        assert_eq!(parse_thread_dump(thread_dump).warnings, vec![]);
+    }
+}
+
+#[test]
+fn errors_survive_serialization() {
+    let errors = vec![
+        JcmdError::ProcessGone,
+        JcmdError::AttachRefused {
+            message: "AttachNotSupportedException".to_string(),
+        },
+        JcmdError::NmtDisabled,
+        JcmdError::Parse {
+            command: "GC.heap_info".to_string(),
+            line: "garbage-first heap   total 264192K, used x".to_string(),
+            message: "invalid used size".to_string(),
+        },
+        JcmdError::JcmdMissing {
+            path: "/opt/jdk/bin/jcmd".to_string(),
+        },
+        JcmdError::CommandFailed {
+            command: "VM.info".to_string(),
+            message: "exit status: 1".to_string(),
+        },
+        JcmdError::Timeout {
+            command: "Thread.print".to_string(),
+        },
+        JcmdError::NoNmtBaseline,
+        JcmdError::NmtDetailDisabled,
+    ];
+    let json = serde_json::to_string(&errors).unwrap();
+    let deserialized: Vec<JcmdError> = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, errors);
+}
@@ -0,0 +1,44 @@
+import java.util.concurrent.CountDownLatch;
+
+/**
+ * The application of the captured fixtures (see capture.sh). Two threads wait for a monitor in
+ * {@code Object.wait()}, one of them is notified and waits to re-lock it while the owner keeps
+ * holding it, and another thread is blocked on it.
+ */
+public class Fixture {
+    static final Object lock = new Object();
+
+    public static void main(String[] args) throws Exception {
+        CountDownLatch waiting = new CountDownLatch(2);
+        Thread owner = new Thread(() -> {
+            try {
+                waiting.await();
+                Thread.sleep(200);
+                synchronized (lock) {
+                    lock.notify();
+                    Thread.sleep(Long.MAX_VALUE);
+                }
+            } catch (InterruptedException e) {
+            }
+        }, "owner");
+        owner.start();
+        for (int i = 1; i <= 2; i++) {
+            new Thread(() -> {
+                synchronized (lock) {
+                    waiting.countDown();
+                    try {
+                        lock.wait();
+                    } catch (InterruptedException e) {
+                    }
+                }
+            }, "waiter-" + i).start();
+            Thread.sleep(100);
+        }
+        Thread.sleep(500);
+        new Thread(() -> {
+            synchronized (lock) {
+            }
+        }, "blocked").start();
+        Thread.sleep(Long.MAX_VALUE);
+    }
+}
@@ -0,0 +1,49 @@
+#!/bin/sh
+# Captures the fixtures of a jdk: the jcmd output of Fixture.java with every collector the jdk
+# supports, written to jdk<version>/<collector>/ next to this script (or the given directory).
+# Uses the java, javac and jcmd of JAVA_HOME:
+#
+#   JAVA_HOME=/usr/lib/jvm/java-21-openjdk-amd64 tests/fixtures/capture.sh
+#
+# VM.info only reports container limits if the jvm runs with some, e.g. in
+# `docker run --cpus 2 --memory 1g`.
+set -eu
+
+fixtures=$(cd "$(dirname "$0")" && pwd)
+bin=${JAVA_HOME:?JAVA_HOME is not set}/bin
+version=$("$bin/java" -XshowSettings:properties -version 2>&1 |
+    sed -n 's/^ *java.specification.version = //p')
+out=${1:-$fixtures/jdk$version}
+
+classes=$(mktemp -d)
+trap 'rm -rf "$classes"' EXIT
+"$bin/javac" -d "$classes" "$fixtures/Fixture.java"
+
+capture() {
+    collector=$1
+    shift
+    if ! "$bin/java" "$@" -version >/dev/null 2>&1; then
+        echo "Skipping $collector, $* isn't supported"
+        return
+    fi
+    echo "Capturing $collector"
+    "$bin/java" "$@" -Xms256m -Xmx256m -XX:NativeMemoryTracking=summary -cp "$classes" Fixture &
+    pid=$!
+    # Fixture.java needs a second to set up its threads
+    sleep 3
+    mkdir -p "$out/$collector"
+    "$bin/jcmd" "$pid" Thread.print -e >"$out/$collector/Thread.print.txt"
+    "$bin/jcmd" "$pid" GC.heap_info >"$out/$collector/GC.heap_info.txt"
+    "$bin/jcmd" "$pid" VM.native_memory >"$out/$collector/VM.native_memory.txt"
+    "$bin/jcmd" "$pid" VM.info >"$out/$collector/VM.info.txt"
+    kill "$pid"
+    wait "$pid" || true
+}
+
+capture g1 -XX:+UseG1GC
+capture parallel -XX:+UseParallelGC
+capture serial -XX:+UseSerialGC
+capture shenandoah -XX:+UseShenandoahGC
+capture zgc -XX:+UseZGC
+# Generational ZGC is optional on jdk 21 and 22
+capture zgc-generational -XX:+UseZGC -XX:+ZGenerational
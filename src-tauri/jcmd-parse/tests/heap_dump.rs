@@ -0,0 +1,25 @@
+use jcmd_parse::{parse_heap_dump, HeapDumpResult, JcmdError};
+
+#[test]
+fn parses_created_heap_dump() {
+    let output = "48213:\nDumping heap to /tmp/heapdump.hprof ...\nHeap dump file created [28762538 bytes in 0.103 secs]\n";
+    assert_eq!(
+        parse_heap_dump(output),
+        Ok(HeapDumpResult {
+            bytes: Some(28762538),
+            seconds: Some(0.103),
+        })
+    );
+}
+
+#[test]
+fn reports_failed_heap_dump() {
+    let output = "48213:\nDumping heap to /tmp/heapdump.hprof ...\nUnable to create /tmp/heapdump.hprof: File exists\n";
+    assert_eq!(
+        parse_heap_dump(output),
+        Err(JcmdError::CommandFailed {
+            command: "GC.heap_dump".to_string(),
+            message: "Unable to create /tmp/heapdump.hprof: File exists".to_string(),
+        })
+    );
+}
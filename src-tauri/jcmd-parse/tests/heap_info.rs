@@ -0,0 +1,53 @@
+use jcmd_parse::{parse_heap_info, SpaceInfo};
+
+const K: u64 = 1024;
+const M: u64 = 1024 * 1024;
+
+#[test]
+fn parses_g1() {
+    let heap_info = parse_heap_info(include_str!("fixtures/jdk17/g1/GC.heap_info.txt")).value;
+    assert_eq!(heap_info.used, Some(33791 * K));
+    assert_eq!(
+        heap_info.metaspace,
+        SpaceInfo {
+            used: Some(21456 * K),
+            committed: Some(21824 * K),
+            reserved: Some(1114112 * K),
+        }
+    );
+    assert_eq!(
+        heap_info.class_space,
+        SpaceInfo {
+            used: Some(2771 * K),
+            committed: Some(2944 * K),
+            reserved: Some(1048576 * K),
+        }
+    );
+}
+
+#[test]
+fn parses_zgc() {
+    let heap_info = parse_heap_info(include_str!("fixtures/jdk17/zgc/GC.heap_info.txt")).value;
+    assert_eq!(heap_info.used, Some(58 * M));
+    assert_eq!(heap_info.metaspace.reserved, Some(1069056 * K));
+}
+
+#[test]
+fn parses_shenandoah() {
+    let heap_info =
+        parse_heap_info(include_str!("fixtures/jdk17/shenandoah/GC.heap_info.txt")).value;
+    assert_eq!(heap_info.used, Some(47104 * K));
+    assert_eq!(heap_info.metaspace.used, Some(21088 * K));
+}
+
+#[test]
+fn parses_serial_young_and_old_generation() {
+    let heap_info = parse_heap_info(include_str!("fixtures/jdk17/serial/GC.heap_info.txt")).value;
+    assert_eq!(heap_info.used, Some((23161 + 4096) * K));
+}
+
+#[test]
+fn parses_parallel_young_and_old_generation() {
+    let heap_info = parse_heap_info(include_str!("fixtures/jdk17/parallel/GC.heap_info.txt")).value;
+    assert_eq!(heap_info.used, Some((19660 + 2048) * K));
+}
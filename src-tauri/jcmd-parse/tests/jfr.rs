@@ -0,0 +1,75 @@
+use jcmd_parse::{
+    parse_jfr_check, parse_jfr_dump, parse_jfr_start, parse_jfr_stop, JcmdError, JfrRecording,
+    JfrRecordingState,
+};
+
+#[test]
+fn parses_recordings_jdk17() {
+    let parsed = parse_jfr_check(include_str!("fixtures/jdk17/g1/JFR.check.txt"));
+
+    assert_eq!(parsed.warnings, vec![]);
+    let recordings = parsed.value;
+    assert_eq!(recordings.len(), 3);
+    assert_eq!(
+        recordings[0],
+        JfrRecording {
+            id: 1,
+            name: "1".to_string(),
+            duration: None,
+            max_size: Some("250.0MB".to_string()),
+            max_age: None,
+            state: JfrRecordingState::Running,
+        }
+    );
+    // Names may contain spaces
+    assert_eq!(recordings[1].name, "startup profile");
+    assert_eq!(recordings[1].duration, Some("1m".to_string()));
+    assert_eq!(recordings[1].state, JfrRecordingState::Stopped);
+    assert_eq!(recordings[2].max_age, Some("1d".to_string()));
+}
+
+#[test]
+fn parses_missing_recordings() {
+    let output =
+        "48213:\nNo available recordings.\n\nUse jcmd 48213 JFR.start to start a recording.\n";
+    let parsed = parse_jfr_check(output);
+
+    assert_eq!(parsed.value, vec![]);
+    assert_eq!(parsed.warnings, vec![]);
+}
+
+#[test]
+fn parses_started_recording() {
+    let output = "48213:\nStarted recording 4. No limit specified, using maxsize=250MB as default.\n\nUse jcmd 48213 JFR.dump name=4 filename=FILENAME to copy recording data to file.\n";
+    assert_eq!(parse_jfr_start(output), Ok(4));
+
+    let delayed = "48213:\nRecording 5 scheduled to start in 30 s.\n";
+    assert_eq!(parse_jfr_start(delayed), Ok(5));
+
+    let failed = "48213:\nCould not parse settings file 'foo'\n";
+    assert_eq!(
+        parse_jfr_start(failed),
+        Err(JcmdError::CommandFailed {
+            command: "JFR.start".to_string(),
+            message: "Could not parse settings file 'foo'".to_string(),
+        })
+    );
+}
+
+#[test]
+fn parses_dumped_and_stopped_recording() {
+    let output = "48213:\nDumped recording \"1\", 1.2 MB written to:\n\n/tmp/recording.jfr\n";
+    assert_eq!(parse_jfr_dump(output), Ok("/tmp/recording.jfr".to_string()));
+    assert_eq!(parse_jfr_stop("48213:\nStopped recording \"1\".\n"), Ok(()));
+
+    let failed = "48213:\nCould not find 7.\n\nUse JFR.check without options to see list of all available recordings.\n";
+    assert_eq!(
+        parse_jfr_stop(failed),
+        Err(JcmdError::CommandFailed {
+            command: "JFR.stop".to_string(),
+            message: "Could not find 7. Use JFR.check without options to see list of all \
+                available recordings."
+                .to_string(),
+        })
+    );
+}
@@ -0,0 +1,337 @@
+use jcmd_parse::{
+    parse_nmt_detail, parse_nmt_diff, parse_nmt_summary, Allocations, CommittedRegion, Diff,
+    MallocDiff, MmapDiff, ReservedCommitted,
+};
+
+#[test]
+fn parses_total_and_categories_jdk17() {
+    let summary = parse_nmt_summary(include_str!("fixtures/jdk17/g1/VM.native_memory.txt")).value;
+
+    assert_eq!(
+        summary.total,
+        Some(ReservedCommitted {
+            reserved: Some(5796729528),
+            committed: Some(402010328),
+        })
+    );
+    assert_eq!(summary.categories.len(), 21);
+
+    let heap = summary.category("Java Heap").unwrap();
+    assert_eq!(heap.reserved, Some(4143972352));
+    assert_eq!(heap.committed, Some(266338304));
+
+    let nmt = summary.category("Native Memory Tracking").unwrap();
+    assert_eq!(nmt.reserved, Some(1688552));
+
+    let last_category = summary.categories.last().unwrap();
+    assert_eq!(last_category.name, "Object Monitors");
+}
+
+/// Summary in the format of jdk 21 (written by hand, there's no capture of jdk 21 yet): the
+/// malloc total and categories include the peak, G1 reports its card set as own category.
+const SUMMARY_JDK21: &str = "
+Native Memory Tracking:
+
+(Omitting categories weighting less than 1B)
+
+Total: reserved=5368709120, committed=402653184
+       malloc: 33554432 #98000, peak=41943040 #101000
+       mmap:   reserved=5335154688, committed=369098752
+
+-                 Java Heap (reserved=4294967296, committed=268435456)
+                            (mmap: reserved=4294967296, committed=268435456) 
+ 
+-                     Class (reserved=1073876992, committed=3145728)
+                            (classes #4300)
+                            (  instance classes #4000, array classes #300)
+                            (malloc=135168 #6800) (peak=135168 #6810) 
+                            (mmap: reserved=1073741824, committed=3010560) 
+ 
+-                    Thread (reserved=25165824, committed=1310720)
+                            (threads #24)
+                            (stack: reserved=25100288, committed=1245184)
+                            (malloc=49152 #146) (peak=65536 #160) 
+                            (arena=16384 #44) (peak=32768 #46)
+ 
+-                 GCCardSet (reserved=524288, committed=524288)
+                            (malloc=524288 #1100) (peak=655360 #1200) 
+ 
+";
+
+#[test]
+fn parses_total_and_categories_jdk21() {
+    let parsed = parse_nmt_summary(SUMMARY_JDK21);
+    assert_eq!(parsed.warnings, vec![]);
+    let summary = parsed.value;
+
+    assert_eq!(summary.total.as_ref().unwrap().committed, Some(402653184));
+    assert_eq!(summary.categories.len(), 4);
+
+    let card_set = summary.category("GCCardSet").unwrap();
+    assert_eq!(card_set.reserved, Some(524288));
+    assert_eq!(card_set.committed, Some(524288));
+}
+
+#[test]
+fn parses_category_details_with_peak_jdk21() {
+    let summary = parse_nmt_summary(SUMMARY_JDK21).value;
+
+    let thread = summary.category("Thread").unwrap();
+    assert_eq!(thread.thread_count, Some(24));
+    // The current values, not the peak
+    assert_eq!(
+        thread.malloc,
+        Some(Allocations {
+            size: Some(49152),
+            count: Some(146),
+        })
+    );
+
+    let class = summary.category("Class").unwrap();
+    assert_eq!(class.class_count, Some(4300));
+    assert_eq!(class.malloc.as_ref().unwrap().size, Some(135168));
+}
+
+#[test]
+fn parses_zgc_heap_reservation() {
+    // ZGC maps the heap three times on jdk17
+    let summary = parse_nmt_summary(include_str!("fixtures/jdk17/zgc/VM.native_memory.txt")).value;
+    let heap = summary.category("Java Heap").unwrap();
+    assert_eq!(heap.reserved, Some(48 * 256 * 1024 * 1024));
+    assert_eq!(heap.committed, Some(98566144));
+    assert_eq!(summary.category("GC").unwrap().reserved, Some(8623984910));
+    assert_eq!(summary.category("Thread").unwrap().thread_count, Some(24));
+}
+
+#[test]
+fn parses_gc_category_of_all_collectors() {
+    for (fixture, gc_malloc) in [
+        (
+            include_str!("fixtures/jdk17/shenandoah/VM.native_memory.txt"),
+            1172335,
+        ),
+        (
+            include_str!("fixtures/jdk17/serial/VM.native_memory.txt"),
+            6162,
+        ),
+        (
+            include_str!("fixtures/jdk17/parallel/VM.native_memory.txt"),
+            6566734,
+        ),
+    ] {
+        let summary = parse_nmt_summary(fixture).value;
+        assert_eq!(summary.categories.len(), 20);
+        assert_eq!(
+            summary.category("Java Heap").unwrap().reserved,
+            Some(256 * 1024 * 1024)
+        );
+        let gc = summary.category("GC").unwrap();
+        assert_eq!(gc.malloc.as_ref().unwrap().size, Some(gc_malloc));
+        assert!(gc.mmap.is_some());
+        // The card set is a category of G1 only
+        assert!(summary.category("GCCardSet").is_none());
+    }
+}
+
+#[test]
+fn parses_category_details_jdk17() {
+    let summary = parse_nmt_summary(include_str!("fixtures/jdk17/g1/VM.native_memory.txt")).value;
+
+    let class = summary.category("Class").unwrap();
+    assert_eq!(class.class_count, Some(3894));
+    assert_eq!(
+        class.malloc,
+        Some(Allocations {
+            size: Some(355528),
+            count: Some(6812),
+        })
+    );
+    // The metadata rows aren't the mmap values
+    assert_eq!(
+        class.mmap,
+        Some(ReservedCommitted {
+            reserved: Some(1073741824),
+            committed: Some(2690048),
+        })
+    );
+
+    let thread = summary.category("Thread").unwrap();
+    assert_eq!(thread.thread_count, Some(23));
+    assert_eq!(
+        thread.stack,
+        Some(ReservedCommitted {
+            reserved: Some(24150016),
+            committed: Some(1281536),
+        })
+    );
+    assert_eq!(
+        thread.arena,
+        Some(Allocations {
+            size: Some(27864),
+            count: Some(44),
+        })
+    );
+    assert_eq!(thread.mmap, None);
+
+    let heap = summary.category("Java Heap").unwrap();
+    assert_eq!(heap.malloc, None);
+    assert_eq!(heap.mmap.as_ref().unwrap().committed, Some(266338304));
+    assert_eq!(heap.class_count, None);
+}
+
+#[test]
+fn parses_diff_to_baseline_jdk17() {
+    let diff = parse_nmt_diff(include_str!(
+        "fixtures/jdk17/g1/VM.native_memory.summary.diff.txt"
+    ))
+    .value;
+
+    let total = diff.total.clone().unwrap();
+    assert_eq!(
+        total.committed,
+        Some(Diff {
+            value: 405213784,
+            delta: 3203456
+        })
+    );
+    assert_eq!(
+        total.malloc,
+        Some(MallocDiff {
+            size: Some(Diff {
+                value: 33735552,
+                delta: 2277952
+            }),
+            count: Some(Diff {
+                value: 99180,
+                delta: 946
+            }),
+        })
+    );
+    assert_eq!(diff.categories.len(), 21);
+
+    // Unchanged values have no delta
+    let heap = diff.category("Java Heap").unwrap();
+    assert_eq!(
+        heap.reserved,
+        Some(Diff {
+            value: 4143972352,
+            delta: 0
+        })
+    );
+    assert_eq!(
+        heap.mmap,
+        Some(MmapDiff {
+            reserved: Some(Diff {
+                value: 4143972352,
+                delta: 0
+            }),
+            committed: Some(Diff {
+                value: 267386880,
+                delta: 1048576
+            }),
+        })
+    );
+    assert_eq!(heap.malloc, None);
+
+    let compiler = diff.category("Compiler").unwrap();
+    assert_eq!(compiler.committed.unwrap().delta, -3584);
+    let malloc = compiler.malloc.as_ref().unwrap();
+    assert_eq!(malloc.size.unwrap().delta, -48);
+    assert_eq!(malloc.count.unwrap().delta, -1);
+
+    // The class counts and metadata rows of the class category aren't malloc or mmap values
+    let class = diff.category("Class").unwrap();
+    assert_eq!(class.malloc.as_ref().unwrap().count.unwrap().value, 6903);
+    assert_eq!(
+        class.mmap.as_ref().unwrap().committed.unwrap().value,
+        2690048
+    );
+}
+
+#[test]
+fn skips_peak_values_of_diff_jdk21() {
+    let output = "Total: reserved=5797262336 +1024, committed=402543136 +1024\n       malloc: 35652608 +1024 #98240 +6, peak=36700160 #101020\n       mmap:   reserved=5761610752, committed=366891552\n\n-                     Class (reserved=1074098376 +1024, committed=3046600 +1024)\n                            (classes #4318 +6)\n                            (malloc=356552 +1024 #6818 +6) (peak=356552 #6818) \n";
+    let parsed = parse_nmt_diff(output);
+
+    assert_eq!(parsed.warnings, vec![]);
+    let total_malloc = parsed.value.total.unwrap().malloc.unwrap();
+    assert_eq!(total_malloc.size.unwrap().value, 35652608);
+    assert_eq!(total_malloc.count.unwrap().value, 98240);
+    let class_malloc = parsed.value.categories[0].malloc.clone().unwrap();
+    assert_eq!(
+        class_malloc.count,
+        Some(Diff {
+            value: 6818,
+            delta: 6
+        })
+    );
+}
+
+#[test]
+fn parses_malloc_sites_and_regions_of_detail_jdk17() {
+    let detail = parse_nmt_detail(include_str!(
+        "fixtures/jdk17/g1/VM.native_memory.detail.txt"
+    ))
+    .value;
+
+    assert_eq!(detail.summary.categories.len(), 21);
+    assert_eq!(detail.summary.total.unwrap().committed, Some(402010328));
+
+    // The mmap call site isn't a malloc site
+    assert_eq!(detail.malloc_sites.len(), 4);
+    let arena = &detail.malloc_sites[1];
+    assert_eq!(arena.category, "Arena Chunk");
+    assert_eq!(arena.size, 65536);
+    assert_eq!(arena.count, Some(2));
+    assert_eq!(arena.stack.len(), 4);
+    assert_eq!(
+        arena.stack[0],
+        "ChunkPool::allocate(unsigned long, AllocFailStrategy::AllocFailEnum)+0x4b"
+    );
+    // Frames without symbol keep the address or library
+    assert_eq!(detail.malloc_sites[2].stack[2], "0x00007f5c2477a3e8");
+    assert_eq!(detail.malloc_sites[3].stack[2], "in libzip.so");
+
+    assert_eq!(detail.regions.len(), 3);
+    let heap = &detail.regions[0];
+    assert_eq!(heap.category, "Java Heap");
+    assert_eq!(heap.start, "0x00000000c0000000");
+    assert_eq!(heap.end, "0x0000000100000000");
+    assert_eq!(heap.reserved, 1073741824);
+    assert_eq!(heap.committed, 67108864 + 2097152);
+    assert_eq!(heap.stack.len(), 4);
+    assert_eq!(heap.committed_regions.len(), 2);
+    assert_eq!(
+        heap.committed_regions[1],
+        CommittedRegion {
+            start: "0x00000000ffe00000".to_string(),
+            end: "0x0000000100000000".to_string(),
+            size: 2097152,
+            stack: vec![
+                "G1PageBasedVirtualSpace::commit(unsigned long, unsigned long)+0x143".to_string(),
+                "HeapRegionManager::expand_at(unsigned int, unsigned int, WorkGang*)+0x7d"
+                    .to_string(),
+            ],
+        }
+    );
+
+    let safepoint = &detail.regions[2];
+    assert_eq!(safepoint.category, "Safepoint");
+    assert_eq!(safepoint.committed, 4096);
+    assert_eq!(safepoint.stack.len(), 2);
+}
+
+#[test]
+fn parses_malloc_sites_of_detail_with_peak_jdk21() {
+    let output = "Details:\n\n[0x00007f1e2c8a2b4d] os::malloc(unsigned long, MEMFLAGS, NativeCallStack const&)+0x10f\n[0x00007f1e2c3b9e21] Unsafe_AllocateMemory0+0x71\n                             (malloc=1048576 type=Other #1) (at peak)\n\n[0x00007f1e2c8a2b4d] os::malloc(unsigned long, MEMFLAGS, NativeCallStack const&)+0x10f\n                             (malloc=2048 type=Symbol #16) (peak=4096 #32)\n";
+    let parsed = parse_nmt_detail(output);
+
+    assert_eq!(parsed.warnings, vec![]);
+    let sites = parsed.value.malloc_sites;
+    assert_eq!(sites.len(), 2);
+    assert_eq!(sites[0].category, "Other");
+    assert_eq!(sites[0].size, 1048576);
+    assert_eq!(sites[0].stack.len(), 2);
+    assert_eq!(sites[1].category, "Symbol");
+    assert_eq!(sites[1].count, Some(16));
+}
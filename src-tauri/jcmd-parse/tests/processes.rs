@@ -0,0 +1,19 @@
+use jcmd_parse::parse_jvm_processes;
+
+#[test]
+fn parses_process_list_without_jcmd() {
+    let output = "48213 com.example.Application --spring.profiles.active=local\n\
+                  48390 /app/service.jar\n\
+                  49001 jdk.jcmd/sun.tools.jcmd.JCmd\n";
+    let processes = parse_jvm_processes(output);
+
+    assert_eq!(processes.len(), 2);
+    assert_eq!(processes[0].id, "48213");
+    assert_eq!(processes[0].name, "com.example.Application");
+    assert_eq!(
+        processes[0].path.as_deref(),
+        Some("--spring.profiles.active=local")
+    );
+    assert_eq!(processes[1].name, "/app/service.jar");
+    assert_eq!(processes[1].path, None);
+}
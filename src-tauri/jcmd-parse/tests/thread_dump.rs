@@ -0,0 +1,475 @@
+use jcmd_parse::{
+    parse_thread_dump, DeadlockedThread, LockAction, LockRecord, LockedSynchronizer,
+    ThreadStateCounts,
+};
+
+#[test]
+fn parses_application_threads_jdk17() {
+    let dump = parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.txt")).value;
+
+    assert_eq!(dump.application_threads.len(), 9);
+    let main = &dump.application_threads[0];
+    assert_eq!(main.name, "main");
+    assert_eq!(main.id, 1);
+    assert!(!main.daemon);
+    assert_eq!(main.prio, 5);
+    assert_eq!(main.cpu, 412.37);
+    assert_eq!(main.elapsed, 62410.0);
+    assert_eq!(main.allocated, "24385K");
+    assert_eq!(main.defined_classes, 1892);
+    assert_eq!(main.thread_id, "0x00007f3c8c027040");
+    assert_eq!(main.os_thread_id, "0xbc56");
+    assert_eq!(main.status, "TIMED_WAITING (sleeping)");
+    assert_eq!(main.last_known_java_stack_pointer, "0x00007f3c93ffe000");
+
+    let worker = &dump.application_threads[6];
+    assert_eq!(worker.name, "worker-1");
+    assert_eq!(worker.cpu, 2410.0);
+}
+
+#[test]
+fn parses_jvm_threads_jdk17() {
+    let dump = parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.txt")).value;
+
+    assert_eq!(dump.jvm_threads.len(), 7);
+    let gc_thread = &dump.jvm_threads[1];
+    assert_eq!(gc_thread.name, "GC Thread#0");
+    assert_eq!(gc_thread.cpu, 21.03);
+    assert_eq!(gc_thread.os_thread_id, "0xbc58");
+    assert_eq!(gc_thread.status, "runnable");
+    assert_eq!(
+        dump.jvm_threads.last().unwrap().status,
+        "waiting on condition"
+    );
+}
+
+#[test]
+fn counts_thread_states() {
+    let dump = parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.txt")).value;
+
+    assert_eq!(
+        dump.application_thread_states(),
+        ThreadStateCounts {
+            new_count: 0,
+            runnable_count: 5,
+            waiting_count: 1,
+            timed_waiting_count: 2,
+            blocked_count: 1,
+        }
+    );
+    assert_eq!(
+        dump.jvm_thread_states(),
+        ThreadStateCounts {
+            new_count: 0,
+            runnable_count: 6,
+            waiting_count: 1,
+            timed_waiting_count: 0,
+            blocked_count: 0,
+        }
+    );
+}
+
+/// Thread dump of Fixture.java in the format of jdk 21 (written by hand, there's no capture of
+/// jdk 21 yet): native ids are decimal and also follow the java thread id, `Object.wait()` waits
+/// in the native `Object.wait0`.
+const WAIT_JDK21: &str = "4712:
+2026-10-18 06:30:52
+Full thread dump OpenJDK 64-Bit Server VM (21.0.2+13-58 mixed mode, sharing):
+
+\"main\" #1 [4713] prio=5 os_prio=0 cpu=57.32ms elapsed=3.31s allocated=359K defined_classes=17 tid=0x00007f687c017f70 nid=4713 waiting on condition  [0x00007f68811fe000]
+   java.lang.Thread.State: TIMED_WAITING (sleeping)
+\tat java.lang.Thread.sleep0(java.base@21.0.2/Native Method)
+\tat java.lang.Thread.sleep(java.base@21.0.2/Thread.java:509)
+\tat Fixture.main(Fixture.java:41)
+
+\"owner\" #29 [4741] prio=5 os_prio=0 cpu=0.49ms elapsed=3.26s allocated=552B defined_classes=3 tid=0x00007f687c0cc060 nid=4741 waiting on condition  [0x00007f68642a6000]
+   java.lang.Thread.State: TIMED_WAITING (sleeping)
+\tat java.lang.Thread.sleep0(java.base@21.0.2/Native Method)
+\tat java.lang.Thread.sleep(java.base@21.0.2/Thread.java:509)
+\tat Fixture.lambda$main$0(Fixture.java:19)
+\t- locked <0x00000000f5d32660> (a java.lang.Object)
+\tat Fixture$$Lambda/0x00007f6808000a08.run(Unknown Source)
+\tat java.lang.Thread.runWith(java.base@21.0.2/Thread.java:1596)
+\tat java.lang.Thread.run(java.base@21.0.2/Thread.java:1583)
+
+\"waiter-1\" #30 [4742] prio=5 os_prio=0 cpu=0.14ms elapsed=3.24s allocated=0B defined_classes=0 tid=0x00007f687c0d1c00 nid=4742 in Object.wait()  [0x00007f68641a6000]
+   java.lang.Thread.State: BLOCKED (on object monitor)
+\tat java.lang.Object.wait0(java.base@21.0.2/Native Method)
+\t- waiting to re-lock in wait() <0x00000000f5d32660> (a java.lang.Object)
+\tat java.lang.Object.wait(java.base@21.0.2/Object.java:366)
+\tat java.lang.Object.wait(java.base@21.0.2/Object.java:339)
+\tat Fixture.lambda$main$1(Fixture.java:30)
+\t- locked <0x00000000f5d32660> (a java.lang.Object)
+\tat Fixture$$Lambda/0x00007f6808000c30.run(Unknown Source)
+\tat java.lang.Thread.runWith(java.base@21.0.2/Thread.java:1596)
+\tat java.lang.Thread.run(java.base@21.0.2/Thread.java:1583)
+
+\"waiter-2\" #31 [4743] prio=5 os_prio=0 cpu=0.17ms elapsed=3.14s allocated=0B defined_classes=0 tid=0x00007f687c0d29a0 nid=4743 in Object.wait()  [0x00007f6855ffe000]
+   java.lang.Thread.State: WAITING (on object monitor)
+\tat java.lang.Object.wait0(java.base@21.0.2/Native Method)
+\t- waiting on <0x00000000f5d32660> (a java.lang.Object)
+\tat java.lang.Object.wait(java.base@21.0.2/Object.java:366)
+\tat java.lang.Object.wait(java.base@21.0.2/Object.java:339)
+\tat Fixture.lambda$main$1(Fixture.java:30)
+\t- locked <0x00000000f5d32660> (a java.lang.Object)
+\tat Fixture$$Lambda/0x00007f6808000c30.run(Unknown Source)
+\tat java.lang.Thread.runWith(java.base@21.0.2/Thread.java:1596)
+\tat java.lang.Thread.run(java.base@21.0.2/Thread.java:1583)
+
+\"blocked\" #32 [4744] prio=5 os_prio=0 cpu=0.10ms elapsed=2.53s allocated=0B defined_classes=0 tid=0x00007f687c0d4690 nid=4744 waiting for monitor entry  [0x00007f6855efe000]
+   java.lang.Thread.State: BLOCKED (on object monitor)
+\tat Fixture.lambda$main$2(Fixture.java:38)
+\t- waiting to lock <0x00000000f5d32660> (a java.lang.Object)
+\tat Fixture$$Lambda/0x00007f6808002800.run(Unknown Source)
+\tat java.lang.Thread.runWith(java.base@21.0.2/Thread.java:1596)
+\tat java.lang.Thread.run(java.base@21.0.2/Thread.java:1583)
+
+\"VM Thread\" os_prio=0 cpu=1.19ms elapsed=3.29s tid=0x00007f687c0aaa20 nid=4720 runnable  
+
+\"GC Thread#0\" os_prio=0 cpu=0.05ms elapsed=3.31s tid=0x00007f687c03dc60 nid=4714 runnable  
+
+JNI global refs: 4, weak refs: 0
+";
+
+#[test]
+fn parses_decimal_native_ids_jdk21() {
+    let parsed = parse_thread_dump(WAIT_JDK21);
+    assert_eq!(parsed.warnings, vec![]);
+    let dump = parsed.value;
+
+    assert_eq!(dump.application_threads.len(), 5);
+    assert_eq!(dump.jvm_threads.len(), 2);
+
+    let owner = &dump.application_threads[1];
+    assert_eq!(owner.name, "owner");
+    assert_eq!(owner.id, 29);
+    assert_eq!(owner.os_thread_id, "4741");
+    assert_eq!(owner.last_known_java_stack_pointer, "0x00007f68642a6000");
+    assert_eq!(dump.jvm_threads[0].os_thread_id, "4720");
+}
+
+#[test]
+fn parses_gc_threads_of_all_collectors() {
+    for (fixture, application_threads, gc_threads) in [
+        (
+            include_str!("fixtures/jdk17/zgc/Thread.print.txt"),
+            15,
+            &[
+                "ZStat",
+                "ZDirector",
+                "ZDriver",
+                "ZUncommitter",
+                "ZUnmapper",
+                "ZWorker#0",
+            ][..],
+        ),
+        (
+            include_str!("fixtures/jdk17/shenandoah/Thread.print.txt"),
+            15,
+            &["Shenandoah Control Thread", "Shenandoah GC Threads#0"][..],
+        ),
+        (
+            include_str!("fixtures/jdk17/parallel/Thread.print.txt"),
+            15,
+            &["GC Thread#0"][..],
+        ),
+        (
+            include_str!("fixtures/jdk17/serial/Thread.print.txt"),
+            15,
+            &[][..],
+        ),
+    ] {
+        let dump = parse_thread_dump(fixture).value;
+        assert_eq!(dump.application_threads.len(), application_threads);
+
+        // The vm and periodic task threads run with every collector
+        let names: Vec<&str> = dump.jvm_threads.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names[..2], ["VM Periodic Task Thread", "VM Thread"]);
+        for gc_thread in gc_threads {
+            assert!(
+                names.contains(gc_thread),
+                "{gc_thread} missing in {names:?}"
+            );
+        }
+        assert!(dump
+            .jvm_threads
+            .iter()
+            .all(|t| t.status == "runnable" || t.status == "waiting on condition"));
+    }
+}
+
+#[test]
+fn parses_stack_frames() {
+    let dump = parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.txt")).value;
+
+    let main = &dump.application_threads[0];
+    assert_eq!(main.stack.len(), 2);
+    assert_eq!(main.stack[0].method, "java.lang.Thread.sleep");
+    assert_eq!(main.stack[0].location, "java.base@17.0.9/Native Method");
+    assert_eq!(main.stack[1].method, "com.example.Application.main");
+    assert_eq!(main.stack[1].location, "Application.java:27");
+
+    // `No compile task` is not a frame
+    let compiler = &dump.application_threads[4];
+    assert_eq!(compiler.name, "C2 CompilerThread0");
+    assert!(compiler.stack.is_empty());
+
+    let finalizer = &dump.application_threads[2];
+    assert_eq!(
+        finalizer.stack[0].locks,
+        vec![LockRecord {
+            action: LockAction::WaitingOn,
+            address: Some("0x0000000708c02f30".to_string()),
+            class_name: Some("java.lang.ref.ReferenceQueue$Lock".to_string()),
+        }]
+    );
+    assert_eq!(finalizer.stack[1].locks[0].action, LockAction::Locked);
+    assert!(finalizer.stack[2].locks.is_empty());
+}
+
+#[test]
+fn parses_locks_and_ownable_synchronizers() {
+    let parsed = parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.l.txt"));
+    assert!(parsed.warnings.is_empty());
+    let dump = parsed.value;
+
+    assert_eq!(dump.application_threads.len(), 9);
+    assert_eq!(dump.jvm_threads.len(), 4);
+
+    let worker_1 = &dump.application_threads[4];
+    assert_eq!(worker_1.name, "worker-1");
+    assert_eq!(
+        worker_1.stack[1].locks,
+        vec![LockRecord {
+            action: LockAction::Locked,
+            address: Some("0x0000000712a4b6d8".to_string()),
+            class_name: Some("java.lang.Object".to_string()),
+        }]
+    );
+    assert_eq!(
+        worker_1.locked_synchronizers,
+        vec![
+            LockedSynchronizer {
+                address: "0x0000000712a4c010".to_string(),
+                class_name: "java.util.concurrent.ThreadPoolExecutor$Worker".to_string(),
+            },
+            LockedSynchronizer {
+                address: "0x0000000712b01a88".to_string(),
+                class_name: "java.util.concurrent.locks.ReentrantLock$NonfairSync".to_string(),
+            }
+        ]
+    );
+
+    let worker_2 = &dump.application_threads[5];
+    assert_eq!(worker_2.stack[0].locks[0].action, LockAction::WaitingToLock);
+    assert_eq!(
+        worker_2.stack[0].locks[0].address.as_deref(),
+        Some("0x0000000712a4b6d8")
+    );
+
+    let worker_3 = &dump.application_threads[6];
+    assert_eq!(worker_3.stack.len(), 9);
+    assert_eq!(
+        worker_3.stack[0].locks,
+        vec![LockRecord {
+            action: LockAction::ParkingToWaitFor,
+            address: Some("0x0000000712b01a88".to_string()),
+            class_name: Some("java.util.concurrent.locks.ReentrantLock$NonfairSync".to_string()),
+        }]
+    );
+    assert!(worker_3.locked_synchronizers.is_empty());
+}
+
+#[test]
+fn parses_deadlocks() {
+    let parsed = parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.deadlock.txt"));
+    assert!(parsed.warnings.is_empty());
+    let dump = parsed.value;
+
+    // The threads of the deadlock report aren't threads of the dump
+    assert_eq!(dump.application_threads.len(), 9);
+    assert_eq!(dump.jvm_threads.len(), 3);
+
+    assert_eq!(dump.deadlocks.len(), 2);
+    assert_eq!(
+        dump.deadlocks[0].threads,
+        vec![
+            DeadlockedThread {
+                name: "transfer-a".to_string(),
+                lock: LockRecord {
+                    action: LockAction::WaitingToLock,
+                    address: Some("0x0000000712c01a20".to_string()),
+                    class_name: Some("com.example.Account".to_string()),
+                },
+                held_by: "transfer-b".to_string(),
+            },
+            DeadlockedThread {
+                name: "transfer-b".to_string(),
+                lock: LockRecord {
+                    action: LockAction::WaitingToLock,
+                    address: Some("0x0000000712c01a10".to_string()),
+                    class_name: Some("com.example.Account".to_string()),
+                },
+                held_by: "transfer-a".to_string(),
+            }
+        ]
+    );
+    assert_eq!(
+        dump.deadlocks[1].threads[0].lock,
+        LockRecord {
+            action: LockAction::ParkingToWaitFor,
+            address: Some("0x0000000712c02b48".to_string()),
+            class_name: Some("java.util.concurrent.locks.ReentrantLock$NonfairSync".to_string()),
+        }
+    );
+    assert_eq!(dump.deadlocks[1].threads[0].held_by, "ledger-2");
+}
+
+#[test]
+fn finds_deadlocks_and_lock_chains() {
+    let mut dump =
+        parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.deadlock.txt")).value;
+
+    let names = |dump: &jcmd_parse::ThreadDump| -> Vec<(bool, Vec<String>)> {
+        dump.lock_chains(3)
+            .into_iter()
+            .map(|c| (c.deadlock, c.threads.into_iter().map(|t| t.name).collect()))
+            .collect()
+    };
+    let expected = vec![
+        (
+            true,
+            vec!["transfer-a".to_string(), "transfer-b".to_string()],
+        ),
+        (true, vec!["ledger-1".to_string(), "ledger-2".to_string()]),
+        (
+            false,
+            vec![
+                "report-1".to_string(),
+                "report-2".to_string(),
+                "report-3".to_string(),
+            ],
+        ),
+    ];
+    assert_eq!(names(&dump), expected);
+
+    let chains = dump.lock_chains(3);
+    assert_eq!(chains[0].threads[0].id, Some(14));
+    assert_eq!(
+        chains[2].threads[1]
+            .lock
+            .as_ref()
+            .unwrap()
+            .address
+            .as_deref(),
+        Some("0x0000000712c03d80")
+    );
+    assert_eq!(chains[2].threads[2].lock, None);
+
+    // The waits-for graph finds the same deadlocks without the report of the jvm
+    dump.deadlocks.clear();
+    assert_eq!(names(&dump), expected);
+}
+
+#[test]
+fn ignores_short_lock_chains() {
+    let dump = parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.l.txt")).value;
+
+    // worker-2 and worker-3 wait for worker-1
+    assert!(dump.lock_chains(3).is_empty());
+    let chains = dump.lock_chains(2);
+    assert_eq!(chains.len(), 2);
+    assert!(chains.iter().all(|c| !c.deadlock));
+    assert_eq!(chains[0].threads[1].name, "worker-1");
+}
+
+#[test]
+fn skips_monitors_released_in_wait() {
+    let parsed = parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.wait.txt"));
+    assert!(parsed.warnings.is_empty());
+    let dump = parsed.value;
+    let thread = |name: &str| {
+        dump.application_threads
+            .iter()
+            .find(|t| t.name == name)
+            .unwrap()
+    };
+
+    // All threads list the monitor as locked, but only the owner holds it
+    let monitor = "0x00000000f5d32660";
+    assert_eq!(thread("owner").held_locks(), vec![monitor]);
+    for name in ["waiter-1", "waiter-2", "blocked", "Finalizer"] {
+        assert!(thread(name).held_locks().is_empty(), "{name}");
+    }
+    assert_eq!(
+        thread("waiter-1").stack[0].locks[0].action,
+        LockAction::WaitingToRelock
+    );
+    assert_eq!(
+        thread("waiter-2").stack[0].locks[0].action,
+        LockAction::WaitingOn
+    );
+
+    // The notified waiter and the blocked thread wait for the owner, not for the other waiter
+    let chains: Vec<Vec<String>> = dump
+        .lock_chains(2)
+        .into_iter()
+        .map(|c| c.threads.into_iter().map(|t| t.name).collect())
+        .collect();
+    assert_eq!(
+        chains,
+        vec![
+            vec!["waiter-1".to_string(), "owner".to_string()],
+            vec!["blocked".to_string(), "owner".to_string()],
+        ]
+    );
+}
+
+#[test]
+fn skips_monitors_released_in_wait0_jdk21() {
+    let dump = parse_thread_dump(WAIT_JDK21).value;
+    let thread = |name: &str| {
+        dump.application_threads
+            .iter()
+            .find(|t| t.name == name)
+            .unwrap()
+    };
+
+    let monitor = "0x00000000f5d32660";
+    assert_eq!(thread("owner").held_locks(), vec![monitor]);
+    for name in ["waiter-1", "waiter-2", "blocked"] {
+        assert!(thread(name).held_locks().is_empty(), "{name}");
+    }
+    let wait0 = &thread("waiter-2").stack[0];
+    assert_eq!(wait0.method, "java.lang.Object.wait0");
+    assert_eq!(wait0.location, "java.base@21.0.2/Native Method");
+    assert_eq!(wait0.locks[0].action, LockAction::WaitingOn);
+
+    let chains: Vec<Vec<String>> = dump
+        .lock_chains(2)
+        .into_iter()
+        .map(|c| c.threads.into_iter().map(|t| t.name).collect())
+        .collect();
+    assert_eq!(
+        chains,
+        vec![
+            vec!["waiter-1".to_string(), "owner".to_string()],
+            vec!["blocked".to_string(), "owner".to_string()],
+        ]
+    );
+}
+
+#[test]
+fn lists_held_synchronizers() {
+    let dump = parse_thread_dump(include_str!("fixtures/jdk17/g1/Thread.print.l.txt")).value;
+
+    let worker_1 = &dump.application_threads[4];
+    assert_eq!(
+        worker_1.held_locks(),
+        vec![
+            "0x0000000712a4b6d8",
+            "0x0000000712a4c010",
+            "0x0000000712b01a88"
+        ]
+    );
+}
@@ -0,0 +1,91 @@
+use jcmd_parse::{
+    parse_set_flag, parse_system_properties, parse_vm_flags, JcmdError, SystemProperty, VmFlag,
+    VmFlagOrigin,
+};
+
+#[test]
+fn parses_flags_with_origin_jdk17() {
+    let parsed = parse_vm_flags(include_str!("fixtures/jdk17/g1/VM.flags.txt"));
+
+    assert_eq!(parsed.warnings, vec![]);
+    let flags = parsed.value;
+    assert_eq!(flags.len(), 25);
+    let flag = |name: &str| flags.iter().find(|f| f.name == name).unwrap();
+
+    assert_eq!(
+        flag("G1HeapRegionSize"),
+        &VmFlag {
+            name: "G1HeapRegionSize".to_string(),
+            flag_type: "uintx".to_string(),
+            value: "2097152".to_string(),
+            kinds: vec!["product".to_string()],
+            origin: VmFlagOrigin::Ergonomic,
+        }
+    );
+    assert_eq!(flag("MaxHeapSize").origin, VmFlagOrigin::CommandLine);
+    assert_eq!(flag("MaxDirectMemorySize").value, "0");
+    assert_eq!(
+        flag("PrintConcurrentLocks").origin,
+        VmFlagOrigin::Management
+    );
+    assert!(flag("PrintConcurrentLocks").is_manageable());
+    assert!(!flag("UseG1GC").is_manageable());
+    // Empty values and kinds consisting of several words
+    assert_eq!(flag("HeapDumpPath").value, "");
+    assert_eq!(flag("ThreadStackSize").kinds, vec!["pd", "product"]);
+}
+
+#[test]
+fn reports_flag_without_origin_as_warning() {
+    let output = "[Global flags]\n     bool UseG1GC                                  = true\n";
+    let parsed = parse_vm_flags(output);
+
+    assert_eq!(parsed.value, vec![]);
+    assert_eq!(parsed.warnings.len(), 1);
+}
+
+#[test]
+fn reports_rejected_flag_change() {
+    assert_eq!(parse_set_flag("48213:\n"), Ok(()));
+    assert_eq!(
+        parse_set_flag("48213:\nonly 'writeable' flags can be set\n"),
+        Err(JcmdError::CommandFailed {
+            command: "VM.set_flag".to_string(),
+            message: "only 'writeable' flags can be set".to_string(),
+        })
+    );
+}
+
+#[test]
+fn parses_system_properties_jdk17() {
+    let parsed =
+        parse_system_properties(include_str!("fixtures/jdk17/g1/VM.system_properties.txt"));
+
+    assert_eq!(parsed.warnings, vec![]);
+    let properties = parsed.value;
+    assert_eq!(properties.len(), 30);
+    assert_eq!(
+        properties[0],
+        SystemProperty {
+            key: "java.specification.version".to_string(),
+            value: "17".to_string(),
+        }
+    );
+    let property = |key: &str| {
+        properties
+            .iter()
+            .find(|p| p.key == key)
+            .map(|p| p.value.as_str())
+            .unwrap()
+    };
+    // Escaped separators, line breaks and unicode characters
+    assert_eq!(property("java.vendor.url"), "https://adoptium.net/");
+    assert_eq!(
+        property("sun.java.command"),
+        "com.example.Application --spring.profiles.active=prod"
+    );
+    assert_eq!(property("line.separator"), "\n");
+    assert_eq!(property("path.separator"), ":");
+    assert_eq!(property("app.greeting"), "Grüß Gott");
+    assert_eq!(property("java.vm.compressedOopsMode"), "Zero based");
+}
@@ -0,0 +1,266 @@
+use jcmd_parse::{parse_vm_info, Cpus, VmResources};
+
+const MB: u64 = 1024 * 1024;
+
+#[test]
+fn parses_vm_arguments_and_resources_g1() {
+    let vm_info = parse_vm_info(include_str!("fixtures/jdk17/g1/VM.info.txt")).value;
+
+    let arguments = vm_info.vm_arguments.unwrap();
+    assert_eq!(
+        arguments.jvm_args.as_deref(),
+        Some("-XX:NativeMemoryTracking=summary -Xms256m -Xmx4g")
+    );
+    assert_eq!(
+        arguments.java_command.as_deref(),
+        Some("com.example.Application")
+    );
+
+    let resources = vm_info.vm_resources.unwrap();
+    assert_eq!(
+        resources.cpus,
+        Some(Cpus {
+            total: 8,
+            available: 8
+        })
+    );
+    assert_eq!(resources.memory, Some(15803 * MB));
+    assert_eq!(resources.heap_size_min, Some(256 * MB));
+    assert_eq!(resources.heap_size_init, Some(256 * MB));
+    assert_eq!(resources.heap_size_max, Some(4 * 1024 * MB));
+    // memory_limit_in_bytes: unlimited
+    assert_eq!(resources.container_memory_limit, None);
+}
+
+#[test]
+fn parses_vm_resources_zgc() {
+    let vm_info = parse_vm_info(include_str!("fixtures/jdk17/zgc/VM.info.txt")).value;
+
+    let resources = vm_info.vm_resources.unwrap();
+    assert_eq!(
+        resources.cpus,
+        Some(Cpus {
+            total: 1,
+            available: 1
+        })
+    );
+    assert_eq!(resources.memory, Some(1024 * MB));
+    assert_eq!(resources.heap_size_min, Some(256 * MB));
+    assert_eq!(resources.heap_size_init, Some(256 * MB));
+    assert_eq!(resources.heap_size_max, Some(256 * MB));
+    // memory_limit_in_bytes: 1048576 k
+    assert_eq!(resources.container_memory_limit, Some(1024 * MB));
+}
+
+#[test]
+fn reads_vm_resources_of_old_sessions() {
+    let resources: VmResources = serde_json::from_str(
+        r#"{"cpus":"8 total, 2 available","memory":"15803M","heapSizeMin":"256M",
+        "heapSizeInit":"256M","heapSizeMax":"4G"}"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        resources.cpus,
+        Some(Cpus {
+            total: 8,
+            available: 2
+        })
+    );
+    assert_eq!(resources.memory, Some(15803 * MB));
+    assert_eq!(resources.heap_size_max, Some(4 * 1024 * MB));
+    assert_eq!(resources.container_memory_limit, None);
+}
+
+#[test]
+fn parses_report_summary() {
+    let parsed = parse_vm_info(include_str!("fixtures/jdk17/g1/VM.info.txt"));
+    assert!(parsed.warnings.is_empty());
+    let report = parsed.value;
+
+    assert_eq!(
+        report.jre_version.as_deref(),
+        Some("OpenJDK Runtime Environment Temurin-17.0.9+9 (17.0.9+9) (build 17.0.9+9)")
+    );
+    assert!(report.vm_version.unwrap().contains("g1 gc"));
+    assert_eq!(
+        report.command_line.as_deref(),
+        Some("-XX:NativeMemoryTracking=summary -Xms256m -Xmx4g com.example.Application")
+    );
+    assert_eq!(
+        report.host.as_deref(),
+        Some("Intel(R) Core(TM) i7-8550U CPU @ 1.80GHz, 8 cores, 15G, Ubuntu 22.04.3 LTS")
+    );
+    assert_eq!(report.time.as_deref(), Some("Mon Jan 15 10:42:17 2024 CET"));
+    assert_eq!(report.elapsed_seconds, Some(62.412345));
+    assert!(report
+        .cpu
+        .unwrap()
+        .starts_with("total 8 (initial active 8)"));
+    assert_eq!(
+        report.memory.as_deref(),
+        Some("4k page, physical 16182684k(5234560k free), swap 2097148k(2097148k free)")
+    );
+    assert!(report
+        .build
+        .unwrap()
+        .starts_with("OpenJDK 64-Bit Server VM (17.0.9+9)"));
+}
+
+#[test]
+fn parses_heap_regions_g1() {
+    let report = parse_vm_info(include_str!("fixtures/jdk17/g1/VM.info.txt")).value;
+
+    assert_eq!(report.heap_regions.len(), 5);
+    let region = &report.heap_regions[1];
+    assert_eq!(region.index, 1);
+    assert_eq!(region.bottom, "0x0000000700200000");
+    assert_eq!(region.top, "0x00000007002d1a40");
+    assert_eq!(region.end, "0x0000000700400000");
+    assert_eq!(region.used_percent, 40);
+    assert_eq!(region.region_type, "O");
+    assert!(!region.collection_set);
+
+    let survivor = &report.heap_regions[3];
+    assert_eq!(survivor.index, 114);
+    assert_eq!(survivor.region_type, "S");
+    assert!(survivor.collection_set);
+}
+
+#[test]
+fn parses_event_logs() {
+    let report = parse_vm_info(include_str!("fixtures/jdk17/g1/VM.info.txt")).value;
+
+    let names: Vec<&str> = report.event_logs.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "Compilation events",
+            "GC Heap History",
+            "Dll operation events",
+            "Deoptimization events",
+            "Classes unloaded",
+            "Classes redefined",
+            "Internal exceptions",
+            "VM Operations",
+            "Events"
+        ]
+    );
+
+    let gc_history = &report.event_logs[1];
+    assert_eq!(gc_history.count, Some(2));
+    assert_eq!(gc_history.events.len(), 2);
+    assert_eq!(gc_history.events[0].time, 21.377);
+    assert!(gc_history.events[0]
+        .description
+        .starts_with("GC heap before\n{Heap before GC invocations=0 (full 0):"));
+    assert!(gc_history.events[1].description.ends_with('}'));
+
+    let unloaded = &report.event_logs[4];
+    assert_eq!(unloaded.count, Some(0));
+    assert!(unloaded.events.is_empty());
+
+    let exceptions = &report.event_logs[6];
+    assert_eq!(exceptions.events.len(), 1);
+    assert!(exceptions.events[0]
+        .description
+        .ends_with("\nthrown [src/hotspot/share/interpreter/linkResolver.cpp, line 759]"));
+}
+
+#[test]
+fn parses_system_sections() {
+    let report = parse_vm_info(include_str!("fixtures/jdk17/g1/VM.info.txt")).value;
+
+    let java = &report.dynamic_libraries[0];
+    assert_eq!(java.address, "55d2c4a00000-55d2c4a01000");
+    assert_eq!(java.permissions, "r--p");
+    assert_eq!(java.path.as_deref(), Some("/opt/java/openjdk/bin/java"));
+
+    assert_eq!(report.environment_variables.len(), 4);
+    assert_eq!(report.environment_variables[0].name, "JAVA_HOME");
+    assert_eq!(report.environment_variables[0].value, "/opt/java/openjdk");
+
+    assert_eq!(report.signal_handlers.len(), 10);
+    let sigsegv = &report.signal_handlers[0];
+    assert_eq!(sigsegv.signal, "SIGSEGV");
+    assert_eq!(sigsegv.handler, "crash_handler in libjvm.so");
+    assert_eq!(
+        sigsegv.mask.as_deref(),
+        Some("11100100010111111101111111111110")
+    );
+    assert_eq!(sigsegv.flags.as_deref(), Some("SA_RESTART|SA_SIGINFO"));
+
+    let os = |name: &str| {
+        report
+            .os
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.value.as_str())
+    };
+    assert_eq!(os("DISTRIB_ID"), Some("Ubuntu"));
+    assert_eq!(os("OS uptime"), Some("0 days 3:12 hours"));
+
+    let container = |name: &str| {
+        report
+            .container
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.value.as_str())
+    };
+    assert_eq!(container("container_type"), Some("cgroupv2"));
+    assert_eq!(container("memory_limit_in_bytes"), Some("unlimited"));
+}
+
+#[test]
+fn parses_report_zgc() {
+    let parsed = parse_vm_info(include_str!("fixtures/jdk17/zgc/VM.info.txt"));
+    assert!(parsed.warnings.is_empty());
+    let report = parsed.value;
+
+    assert!(report.vm_version.unwrap().contains("z gc"));
+    assert_eq!(report.elapsed_seconds, Some(4.126305));
+    // Heap regions are only printed by G1
+    assert!(report.heap_regions.is_empty());
+    assert!(report
+        .event_logs
+        .iter()
+        .any(|l| l.name == "VM Operations" && l.events.len() == 8));
+    assert!(!report.dynamic_libraries.is_empty());
+    assert!(!report.signal_handlers.is_empty());
+    assert!(!report.container.is_empty());
+}
+
+#[test]
+fn parses_container_info() {
+    let report = parse_vm_info(include_str!("fixtures/jdk17/zgc/VM.info.txt")).value;
+
+    let container = report.container_info.unwrap();
+    assert_eq!(container.container_type.as_deref(), Some("cgroupv1"));
+    // The quota allows two cpus, but the host has only one
+    assert_eq!(container.active_processor_count, Some(1));
+    assert_eq!(container.cpu_quota, Some(200000));
+    assert_eq!(container.cpu_period, Some(100000));
+    assert_eq!(container.cpu_shares, None);
+    assert_eq!(container.memory_limit, Some(1024 * MB));
+    assert_eq!(container.memory_and_swap_limit, None);
+    assert_eq!(container.memory_soft_limit, None);
+    assert_eq!(container.memory_usage, Some(307744 * 1024));
+    assert_eq!(container.memory_max_usage, Some(307744 * 1024));
+    // Not reported by jdk 17
+    assert_eq!(container.rss_usage, None);
+    assert_eq!(container.cache_usage, None);
+    assert_eq!(container.current_tasks, Some(41));
+    assert_eq!(container.maximum_tasks, Some(4915));
+}
+
+#[test]
+fn parses_container_info_without_limits() {
+    let report = parse_vm_info(include_str!("fixtures/jdk17/g1/VM.info.txt")).value;
+
+    let container = report.container_info.unwrap();
+    assert_eq!(container.active_processor_count, Some(8));
+    assert_eq!(container.cpu_quota, None);
+    assert_eq!(container.cpu_period, None);
+    assert_eq!(container.memory_limit, None);
+    assert_eq!(container.memory_usage, Some(3947412 * 1024));
+}
@@ -0,0 +1,92 @@
+//! Memory limit and usage of the cgroup of a process, read from `/proc/<pid>/cgroup` and the
+//! memory files of cgroup v1 (`memory.limit_in_bytes`, `memory.usage_in_bytes`) or v2
+//! (`memory.max`, `memory.current`). The files only exist on linux.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// cgroup v1 reports the largest page aligned value instead of a missing limit.
+const UNLIMITED: u64 = 1 << 62;
+
+/// Memory of the cgroup. Sizes are in bytes, a missing limit means unlimited.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CgroupMemory {
+    /// 1 or 2
+    pub version: u8,
+    /// Path of the cgroup in the hierarchy (e.g. `/system.slice/docker-<id>.scope`)
+    pub path: String,
+    pub limit: Option<u64>,
+    pub usage: Option<u64>,
+}
+
+/// Reads the memory of the cgroup of the process. Returns `None` if the cgroup files are not
+/// readable (e.g. on macOS).
+pub(crate) fn read_cgroup_memory(pid: &str) -> Option<CgroupMemory> {
+    let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    let mut unified_path = None;
+    // Rows are `<id>:<controllers>:<path>`, e.g. `4:memory:/docker/<id>` (v1) or `0::/<path>`
+    // (v2). Hybrid setups have both, then the v1 memory controller is in use.
+    for row in cgroups.lines() {
+        let mut columns = row.splitn(3, ':');
+        let (Some(id), Some(controllers), Some(path)) =
+            (columns.next(), columns.next(), columns.next())
+        else {
+            continue;
+        };
+        if controllers.split(',').any(|c| c == "memory") {
+            return read_memory(
+                1,
+                path,
+                &Path::new(CGROUP_ROOT).join("memory"),
+                "memory.limit_in_bytes",
+                "memory.usage_in_bytes",
+            );
+        } else if id == "0" && controllers.is_empty() {
+            unified_path = Some(path);
+        }
+    }
+    read_memory(
+        2,
+        unified_path?,
+        Path::new(CGROUP_ROOT),
+        "memory.max",
+        "memory.current",
+    )
+}
+
+fn read_memory(
+    version: u8,
+    path: &str,
+    mount: &Path,
+    limit_file: &str,
+    usage_file: &str,
+) -> Option<CgroupMemory> {
+    let dir = mount.join(path.trim_start_matches('/'));
+    let usage = read_bytes(&dir.join(usage_file));
+    // The effective limit is the lowest one of the cgroup and its parents
+    let limit = dir
+        .ancestors()
+        .take_while(|d| d.starts_with(mount))
+        .filter_map(|d| read_bytes(&d.join(limit_file)))
+        .min();
+    if usage.is_none() && limit.is_none() {
+        return None;
+    }
+    Some(CgroupMemory {
+        version,
+        path: path.to_string(),
+        limit,
+        usage,
+    })
+}
+
+/// Reads a size in bytes. Missing limits are `max` (v2) or a huge value (v1).
+fn read_bytes(file: &Path) -> Option<u64> {
+    let bytes = fs::read_to_string(file).ok()?.trim().parse::<u64>().ok()?;
+    (bytes < UNLIMITED).then_some(bytes)
+}
@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use jcmd_parse::{
+    ClassHistogram, HeapDumpResult, HeapInfo, JcmdError, JfrRecording, NmtDetail, NmtDiff,
+    NmtSummary, SystemProperty, ThreadDump, VmFlag, VmInfoReport,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::cgroup::{self, CgroupMemory};
+use crate::jcmd::{run_jcmd, run_jcmd_with_timeout};
+
+/// Shortest interval between two samples of a collector. Each sample starts a jcmd process, which
+/// attaches to the jvm.
+pub(crate) const MIN_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Cpu times of the threads of a single process from the previous thread dump, keyed by
+/// [`thread_key`]. Threads not in the latest dump are removed.
+#[derive(Default)]
+pub(crate) struct ThreadCpuCache {
+    threads: HashMap<String, f32>,
+}
+
+/// Identifies a thread by its thread id (`tid`) and native id (`nid`). Names aren't unique (e.g.
+/// `pool-1-thread-1` of different pools) and can be changed by the thread.
+pub(crate) fn thread_key(thread_id: &str, os_thread_id: &str) -> String {
+    format!("{}/{}", thread_id, os_thread_id)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VmInformation {
+    #[serde(flatten)]
+    pub report: VmInfoReport,
+    pub warnings: Vec<JcmdError>,
+}
+
+/// Heap info and native memory summary of a process taken at the same time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSample {
+    #[serde(deserialize_with = "deserialize_time")]
+    pub time: u128,
+    pub heap_info: HeapInfo,
+    pub native_memory: NmtSummary,
+    /// Memory of the cgroup of the process, missing if the cgroup files aren't readable
+    pub cgroup_memory: Option<CgroupMemory>,
+    pub warnings: Vec<JcmdError>,
+}
+
+/// A thread dump of a process. Once passed to [`ThreadCpuCache::apply`], the cpu time of the
+/// threads is the time spent since the previous sample.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadsSample {
+    #[serde(deserialize_with = "deserialize_time")]
+    pub time: u128,
+    #[serde(flatten)]
+    pub thread_dump: ThreadDump,
+    pub warnings: Vec<JcmdError>,
+}
+
+/// Change of the native memory since the baseline.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NmtDiffSample {
+    #[serde(deserialize_with = "deserialize_time")]
+    pub time: u128,
+    pub diff: NmtDiff,
+    pub warnings: Vec<JcmdError>,
+}
+
+/// Native memory with the call sites allocating it. The malloc sites are sorted by size, largest
+/// first.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NmtDetailSample {
+    #[serde(deserialize_with = "deserialize_time")]
+    pub time: u128,
+    pub detail: NmtDetail,
+    pub warnings: Vec<JcmdError>,
+}
+
+/// Instances and bytes per class of a process.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassHistogramSample {
+    #[serde(deserialize_with = "deserialize_time")]
+    pub time: u128,
+    pub histogram: ClassHistogram,
+    pub warnings: Vec<JcmdError>,
+}
+
+/// All flags of the jvm, collected on request.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VmFlagsSample {
+    pub time: u128,
+    pub flags: Vec<VmFlag>,
+    pub warnings: Vec<JcmdError>,
+}
+
+/// System properties of the jvm ordered by key, collected on request.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemPropertiesSample {
+    pub time: u128,
+    pub properties: Vec<SystemProperty>,
+    pub warnings: Vec<JcmdError>,
+}
+
+/// A change of a manageable flag with `VM.set_flag`. `flag` is the flag as read again after the
+/// change, which is `verified` if it holds the requested value.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VmFlagChange {
+    pub time: u128,
+    pub name: String,
+    pub old_value: String,
+    pub value: String,
+    pub flag: Option<VmFlag>,
+    pub verified: bool,
+    pub error: Option<JcmdError>,
+}
+
+/// Heap dumps of large heaps take a while.
+const HEAP_DUMP_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeapDumpOptions {
+    /// Absolute path of the file to write
+    pub path: String,
+    /// Dump unreachable objects as well instead of triggering a full gc
+    pub all: bool,
+    /// Gzip compression level from 1 to 9 (jdk 17+)
+    pub compression: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HeapDumpStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// A heap dump taken from a process. While the dump is running, `size` is the size written so far.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeapDump {
+    #[serde(flatten)]
+    pub options: HeapDumpOptions,
+    pub started: u128,
+    pub finished: Option<u128>,
+    pub status: HeapDumpStatus,
+    pub size: Option<u64>,
+    /// Used heap of the latest metrics sample when the dump was started
+    pub heap_used: Option<u64>,
+    pub error: Option<JcmdError>,
+}
+
+/// Options of `JFR.start`. Unset options are left to the jvm's defaults.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JfrOptions {
+    /// Name of the recording, defaults to its id
+    pub name: Option<String>,
+    /// Settings profile (`default` or `profile`) or path of a `.jfc` file
+    pub settings: Option<String>,
+    /// Duration after which the recording stops (e.g. `10m`)
+    pub duration: Option<String>,
+    /// Maximum size of the recording (e.g. `500M`)
+    pub max_size: Option<String>,
+    /// File the recording is written to when it stops
+    pub filename: Option<String>,
+}
+
+impl JfrOptions {
+    fn args(&self) -> Vec<String> {
+        [
+            ("name", &self.name),
+            ("settings", &self.settings),
+            ("duration", &self.duration),
+            ("maxsize", &self.max_size),
+            ("filename", &self.filename),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.as_ref().map(|v| format!("{}={}", key, v)))
+        .collect()
+    }
+}
+
+/// A recording written to a file with `JFR.dump`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JfrDump {
+    pub time: u128,
+    pub filename: String,
+}
+
+/// A flight recording seen while monitoring the process. `started` and `stopped` are the times
+/// the recording was first seen running and stopped (or gone), to place it on the timeline of
+/// the session.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightRecording {
+    #[serde(flatten)]
+    pub recording: JfrRecording,
+    pub started: u128,
+    pub stopped: Option<u128>,
+    /// File the recording is written to when it stops, if started by jcmd-ui
+    pub filename: Option<String>,
+    pub dumps: Vec<JfrDump>,
+}
+
+#[derive(Clone, Debug)]
+pub struct JfrSample {
+    pub time: u128,
+    pub recordings: Vec<JfrRecording>,
+    pub warnings: Vec<JcmdError>,
+}
+
+pub(crate) fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+}
+
+/// Deserializes a time in milliseconds. serde can't deserialize `u128` values from buffered
+/// content (e.g. in internally tagged enums), but milliseconds since epoch fit into `u64` anyway.
+pub(crate) fn deserialize_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u128, D::Error> {
+    u64::deserialize(deserializer).map(u128::from)
+}
+
+pub(crate) fn collect_vm_information(pid: &str) -> Result<VmInformation, JcmdError> {
+    let output = run_jcmd(&[pid, "VM.info"])?;
+    let parsed = jcmd_parse::parse_vm_info(&output);
+    Ok(VmInformation {
+        report: parsed.value,
+        warnings: parsed.warnings,
+    })
+}
+
+pub(crate) fn collect_metrics(pid: &str) -> Result<MetricsSample, JcmdError> {
+    let time = now();
+
+    // Parse committed / reserved sizes from heap info as they seem to be more accurate
+    // than the values from the native memory tracking.
+    let heap_info = jcmd_parse::parse_heap_info(&run_jcmd(&[pid, "GC.heap_info"])?);
+    let summary = jcmd_parse::parse_nmt_summary(&run_jcmd(&[pid, "VM.native_memory", "scale=b"])?);
+
+    let mut warnings = heap_info.warnings;
+    warnings.extend(summary.warnings);
+
+    Ok(MetricsSample {
+        time,
+        heap_info: heap_info.value,
+        native_memory: summary.value,
+        cgroup_memory: cgroup::read_cgroup_memory(pid),
+        warnings,
+    })
+}
+
+/// Takes a thread dump. The locked ownable synchronizers (`-l`) are only listed on demand, as
+/// older jdks walk the heap to find them.
+pub(crate) fn collect_threads(
+    pid: &str,
+    locked_synchronizers: bool,
+) -> Result<ThreadsSample, JcmdError> {
+    let time = now();
+    let mut args = vec![pid, "Thread.print", "-e"];
+    if locked_synchronizers {
+        args.push("-l");
+    }
+    let parsed = jcmd_parse::parse_thread_dump(&run_jcmd(&args)?);
+    Ok(ThreadsSample {
+        time,
+        thread_dump: parsed.value,
+        warnings: parsed.warnings,
+    })
+}
+
+/// Takes a native memory baseline, which later diffs are compared to.
+pub(crate) fn set_nmt_baseline(pid: &str) -> Result<(), JcmdError> {
+    run_jcmd(&[pid, "VM.native_memory", "baseline"]).map(|_| ())
+}
+
+pub(crate) fn collect_nmt_diff(pid: &str) -> Result<NmtDiffSample, JcmdError> {
+    let time = now();
+    let output = run_jcmd(&[pid, "VM.native_memory", "summary.diff", "scale=b"])?;
+    let parsed = jcmd_parse::parse_nmt_diff(&output);
+    Ok(NmtDiffSample {
+        time,
+        diff: parsed.value,
+        warnings: parsed.warnings,
+    })
+}
+
+/// Collects the native memory details. Requires `-XX:NativeMemoryTracking=detail`.
+pub(crate) fn collect_nmt_detail(pid: &str) -> Result<NmtDetailSample, JcmdError> {
+    let time = now();
+    let output = run_jcmd(&[pid, "VM.native_memory", "detail", "scale=b"])?;
+    let mut parsed = jcmd_parse::parse_nmt_detail(&output);
+    parsed
+        .value
+        .malloc_sites
+        .sort_by_key(|site| std::cmp::Reverse(site.size));
+    Ok(NmtDetailSample {
+        time,
+        detail: parsed.value,
+        warnings: parsed.warnings,
+    })
+}
+
+/// Collects the class histogram. Without `all` only live objects are counted, which triggers a
+/// full gc. With `all` unreachable objects are counted as well, but no gc is triggered.
+pub(crate) fn collect_class_histogram(
+    pid: &str,
+    all: bool,
+) -> Result<ClassHistogramSample, JcmdError> {
+    let time = now();
+    let mut args = vec![pid, "GC.class_histogram"];
+    if all {
+        args.push("-all");
+    }
+    let parsed = jcmd_parse::parse_class_histogram(&run_jcmd(&args)?);
+    Ok(ClassHistogramSample {
+        time,
+        histogram: parsed.value,
+        warnings: parsed.warnings,
+    })
+}
+
+/// Collects all flags including the defaults with their origin.
+pub(crate) fn collect_vm_flags(pid: &str) -> Result<VmFlagsSample, JcmdError> {
+    let time = now();
+    let parsed = jcmd_parse::parse_vm_flags(&run_jcmd(&[pid, "VM.flags", "-all"])?);
+    Ok(VmFlagsSample {
+        time,
+        flags: parsed.value,
+        warnings: parsed.warnings,
+    })
+}
+
+/// Changes the value of a manageable flag.
+pub(crate) fn set_vm_flag(pid: &str, name: &str, value: &str) -> Result<(), JcmdError> {
+    jcmd_parse::parse_set_flag(&run_jcmd(&[pid, "VM.set_flag", name, value])?)
+}
+
+pub(crate) fn collect_system_properties(pid: &str) -> Result<SystemPropertiesSample, JcmdError> {
+    let time = now();
+    let mut parsed =
+        jcmd_parse::parse_system_properties(&run_jcmd(&[pid, "VM.system_properties"])?);
+    parsed.value.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(SystemPropertiesSample {
+        time,
+        properties: parsed.value,
+        warnings: parsed.warnings,
+    })
+}
+
+/// Writes a heap dump with `GC.heap_dump`. Blocks until the dump is written.
+pub(crate) fn heap_dump(pid: &str, options: &HeapDumpOptions) -> Result<HeapDumpResult, JcmdError> {
+    let compression = options.compression.map(|level| format!("-gz={}", level));
+    let mut args = vec![pid, "GC.heap_dump"];
+    if options.all {
+        args.push("-all");
+    }
+    if let Some(compression) = &compression {
+        args.push(compression);
+    }
+    args.push(&options.path);
+    jcmd_parse::parse_heap_dump(&run_jcmd_with_timeout(&args, HEAP_DUMP_TIMEOUT)?)
+}
+
+/// Starts a flight recording and returns its id.
+pub(crate) fn jfr_start(pid: &str, options: &JfrOptions) -> Result<u32, JcmdError> {
+    let options = options.args();
+    let mut args = vec![pid, "JFR.start"];
+    args.extend(options.iter().map(|o| o.as_str()));
+    jcmd_parse::parse_jfr_start(&run_jcmd(&args)?)
+}
+
+/// Lists the flight recordings of the process. Initializes the flight recorder of the jvm if it
+/// isn't in use yet.
+pub(crate) fn jfr_check(pid: &str) -> Result<JfrSample, JcmdError> {
+    let time = now();
+    let parsed = jcmd_parse::parse_jfr_check(&run_jcmd(&[pid, "JFR.check"])?);
+    Ok(JfrSample {
+        time,
+        recordings: parsed.value,
+        warnings: parsed.warnings,
+    })
+}
+
+/// Writes the data recorded so far to the file and returns its path.
+pub(crate) fn jfr_dump(pid: &str, id: u32, filename: &str) -> Result<String, JcmdError> {
+    let name = format!("name={}", id);
+    let filename = format!("filename={}", filename);
+    jcmd_parse::parse_jfr_dump(&run_jcmd(&[pid, "JFR.dump", &name, &filename])?)
+}
+
+/// Stops the recording. Its data is written to the file given at the start, otherwise it is
+/// discarded.
+pub(crate) fn jfr_stop(pid: &str, id: u32) -> Result<(), JcmdError> {
+    let name = format!("name={}", id);
+    jcmd_parse::parse_jfr_stop(&run_jcmd(&[pid, "JFR.stop", &name])?)
+}
+
+impl ThreadCpuCache {
+    /// Replaces the total cpu time of the threads with the time spent since the previous dump.
+    /// Threads seen for the first time have no cpu time yet.
+    pub(crate) fn apply(&mut self, thread_dump: &mut ThreadDump) {
+        let mut threads = HashMap::new();
+        for thread in thread_dump.application_threads.iter_mut() {
+            let key = thread_key(&thread.thread_id, &thread.os_thread_id);
+            thread.cpu = self.update(&mut threads, key, thread.cpu);
+        }
+        for thread in thread_dump.jvm_threads.iter_mut() {
+            let key = thread_key(&thread.thread_id, &thread.os_thread_id);
+            thread.cpu = self.update(&mut threads, key, thread.cpu);
+        }
+        self.threads = threads;
+    }
+
+    fn update(&self, threads: &mut HashMap<String, f32>, key: String, cpu: f32) -> f32 {
+        let previous = self.threads.get(&key).copied();
+        threads.insert(key, cpu);
+        match previous {
+            Some(previous) => (cpu - previous).abs(),
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jcmd_parse::parse_thread_dump;
+
+    use super::*;
+
+    fn cpu_times(cache: &mut ThreadCpuCache, dump: &mut ThreadDump) -> Vec<f32> {
+        cache.apply(dump);
+        dump.application_threads
+            .iter()
+            .map(|t| t.cpu)
+            .chain(dump.jvm_threads.iter().map(|t| t.cpu))
+            .collect()
+    }
+
+    #[test]
+    fn calculates_cpu_time_since_the_previous_dump() {
+        let mut dump = parse_thread_dump(include_str!(
+            "../jcmd-parse/tests/fixtures/jdk17/g1/Thread.print.txt"
+        ))
+        .value;
+        dump.application_threads.truncate(2);
+        dump.jvm_threads.truncate(1);
+        let mut cache = ThreadCpuCache::default();
+
+        let mut first = dump.clone();
+        assert_eq!(cpu_times(&mut cache, &mut first), vec![0.0, 0.0, 0.0]);
+
+        let mut second = dump.clone();
+        second.application_threads[0].cpu += 20.0;
+        second.jvm_threads[0].cpu += 5.0;
+        // A renamed thread is still the same thread
+        second.application_threads[1].name = "renamed".to_string();
+        second.application_threads[1].cpu += 1.0;
+        assert_eq!(cpu_times(&mut cache, &mut second), vec![20.0, 1.0, 5.0]);
+    }
+
+    #[test]
+    fn starts_threads_with_reused_names_without_cpu_time() {
+        let mut dump = parse_thread_dump(include_str!(
+            "../jcmd-parse/tests/fixtures/jdk17/g1/Thread.print.txt"
+        ))
+        .value;
+        dump.application_threads.truncate(1);
+        dump.jvm_threads.clear();
+        let mut cache = ThreadCpuCache::default();
+        cpu_times(&mut cache, &mut dump.clone());
+
+        // Same name, but another thread
+        let mut reused = dump.clone();
+        reused.application_threads[0].os_thread_id = "0x4711".to_string();
+        assert_eq!(cpu_times(&mut cache, &mut reused), vec![0.0]);
+
+        // The previous thread terminated, its cpu time isn't kept
+        let mut returned = dump.clone();
+        assert_eq!(cpu_times(&mut cache, &mut returned), vec![0.0]);
+    }
+}
@@ -0,0 +1,15 @@
+//! Settings from the config file `~/.config/jcmd-ui/config`.
+//!
+//! The file contains one `key=value` pair per line, e.g. `java.home=<jdk-path>`.
+
+/// Returns the value of the first line with the given key.
+pub(crate) fn value(key: &str) -> Option<String> {
+    let home_dir = std::env::var("HOME").unwrap_or("".to_string());
+    let config = format!("{}/.config/jcmd-ui/config", home_dir);
+    let content = std::fs::read_to_string(config).ok()?;
+    let prefix = format!("{}=", key);
+    content
+        .split('\n')
+        .find_map(|line| line.strip_prefix(&prefix))
+        .map(|value| value.trim_end_matches('\r').to_string())
+}
@@ -0,0 +1,282 @@
+//! Lock contention of a session aggregated by monitor from the lock records of the thread dumps.
+
+use std::collections::{HashMap, HashSet};
+
+use jcmd_parse::LockAction;
+use serde::Serialize;
+
+use crate::collector::ThreadsSample;
+
+/// Longest time a thread dump is weighted with. Larger gaps between dumps (e.g. of a suspended
+/// process) would outweigh all other dumps.
+const MAX_WEIGHT_MS: u128 = 60_000;
+
+/// Number of owners and frames returned per monitor.
+const TOP: usize = 5;
+
+/// Packages of the frames acquiring `java.util.concurrent` locks, which are skipped to find the
+/// frame the thread is blocked at.
+const LOCK_PACKAGES: [&str; 2] = ["jdk.internal.misc.", "java.util.concurrent.locks."];
+
+/// Threads blocked on monitors (or `java.util.concurrent` locks) in the thread dumps of a
+/// session, keyed by the address of the lock.
+///
+/// Objects are moved by the gc, so a lock may show up with more than one address.
+#[derive(Default)]
+pub(crate) struct LockContention {
+    monitors: HashMap<String, MonitorStats>,
+    previous_time: Option<u128>,
+}
+
+#[derive(Default)]
+struct MonitorStats {
+    class_name: Option<String>,
+    blocked_count: u32,
+    max_blocked: u32,
+    contention_ms: u128,
+    threads: HashSet<String>,
+    owners: HashMap<String, u32>,
+    frames: HashMap<String, u32>,
+    last_seen: u128,
+}
+
+/// Contention of a single monitor over the session.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorContention {
+    address: String,
+    class_name: Option<String>,
+    /// Number of times a thread was blocked on the monitor, summed up over all dumps
+    blocked_count: u32,
+    /// Largest number of threads blocked on the monitor in a single dump
+    max_blocked: u32,
+    /// Number of threads ever blocked on the monitor
+    thread_count: usize,
+    /// Blocked threads weighted with the time since the previous dump, i.e. the estimated time
+    /// threads spent waiting for the monitor
+    contention_ms: u128,
+    /// Threads holding the monitor while others were blocked on it, most often first
+    owners: Vec<Occurrences>,
+    /// Frames the threads were blocked at, most often first
+    frames: Vec<Occurrences>,
+    last_seen: u128,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Occurrences {
+    name: String,
+    count: u32,
+}
+
+impl LockContention {
+    /// Adds the blocked threads of a thread dump. Threads parking for a `java.util.concurrent`
+    /// lock only count if the lock has an owner (`Thread.print -l`), otherwise they might wait
+    /// for a condition (e.g. an empty queue).
+    pub(crate) fn add(&mut self, sample: &ThreadsSample) {
+        let weight = self
+            .previous_time
+            .map_or(0, |t| sample.time.saturating_sub(t).min(MAX_WEIGHT_MS));
+        self.previous_time = Some(sample.time);
+
+        let threads = &sample.thread_dump.application_threads;
+        let mut owners = HashMap::new();
+        for thread in threads {
+            for address in thread.held_locks() {
+                owners.insert(address, thread.name.as_str());
+            }
+        }
+
+        let mut blocked: HashMap<&str, u32> = HashMap::new();
+        for thread in threads {
+            let contention = thread.stack.iter().enumerate().find_map(|(index, frame)| {
+                frame
+                    .locks
+                    .iter()
+                    .filter(|l| match l.action {
+                        LockAction::WaitingToLock | LockAction::WaitingToRelock => true,
+                        LockAction::ParkingToWaitFor => {
+                            l.address.as_deref().is_some_and(|a| owners.contains_key(a))
+                        }
+                        _ => false,
+                    })
+                    .find_map(|l| Some((index, l, l.address.as_deref()?)))
+            });
+            let Some((index, lock, address)) = contention else {
+                continue;
+            };
+            // The caller of `ReentrantLock.lock()` instead of `Unsafe.park()`
+            let frame = thread.stack[index..]
+                .iter()
+                .find(|f| !LOCK_PACKAGES.iter().any(|p| f.method.starts_with(p)))
+                .unwrap_or(&thread.stack[index]);
+            let stats = self.monitors.entry(address.to_string()).or_default();
+            if lock.class_name.is_some() {
+                stats.class_name = lock.class_name.clone();
+            }
+            stats.blocked_count += 1;
+            stats.contention_ms += weight;
+            stats.threads.insert(thread.name.clone());
+            if let Some(owner) = owners.get(address).filter(|o| **o != thread.name) {
+                *stats.owners.entry(owner.to_string()).or_default() += 1;
+            }
+            let frame = format!("{}({})", frame.method, frame.location);
+            *stats.frames.entry(frame).or_default() += 1;
+            stats.last_seen = sample.time;
+            *blocked.entry(address).or_default() += 1;
+        }
+        for (address, count) in blocked {
+            let stats = self.monitors.get_mut(address).unwrap();
+            stats.max_blocked = stats.max_blocked.max(count);
+        }
+    }
+
+    /// Returns the contended monitors, the longest contended first.
+    pub(crate) fn monitors(&self) -> Vec<MonitorContention> {
+        let mut monitors: Vec<MonitorContention> = self
+            .monitors
+            .iter()
+            .map(|(address, stats)| MonitorContention {
+                address: address.clone(),
+                class_name: stats.class_name.clone(),
+                blocked_count: stats.blocked_count,
+                max_blocked: stats.max_blocked,
+                thread_count: stats.threads.len(),
+                contention_ms: stats.contention_ms,
+                owners: top(&stats.owners),
+                frames: top(&stats.frames),
+                last_seen: stats.last_seen,
+            })
+            .collect();
+        monitors.sort_by(|a, b| {
+            b.contention_ms
+                .cmp(&a.contention_ms)
+                .then_with(|| b.blocked_count.cmp(&a.blocked_count))
+                .then_with(|| a.address.cmp(&b.address))
+        });
+        monitors
+    }
+}
+
+fn top(counts: &HashMap<String, u32>) -> Vec<Occurrences> {
+    let mut occurrences: Vec<Occurrences> = counts
+        .iter()
+        .map(|(name, count)| Occurrences {
+            name: name.clone(),
+            count: *count,
+        })
+        .collect();
+    occurrences.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    occurrences.truncate(TOP);
+    occurrences
+}
+
+#[cfg(test)]
+mod tests {
+    use jcmd_parse::parse_thread_dump;
+
+    use super::*;
+
+    fn sample(time: u128, output: &str) -> ThreadsSample {
+        ThreadsSample {
+            time,
+            thread_dump: parse_thread_dump(output).value,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn names(occurrences: &[Occurrences]) -> Vec<(&str, u32)> {
+        occurrences
+            .iter()
+            .map(|o| (o.name.as_str(), o.count))
+            .collect()
+    }
+
+    #[test]
+    fn aggregates_blocked_threads_by_monitor() {
+        let output = include_str!("../jcmd-parse/tests/fixtures/jdk17/g1/Thread.print.l.txt");
+        let mut contention = LockContention::default();
+        contention.add(&sample(1000, output));
+        contention.add(&sample(3000, output));
+
+        let monitors = contention.monitors();
+        assert_eq!(monitors.len(), 2);
+        let monitor = &monitors[0];
+        assert_eq!(monitor.address, "0x0000000712a4b6d8");
+        assert_eq!(monitor.class_name.as_deref(), Some("java.lang.Object"));
+        assert_eq!(monitor.blocked_count, 2);
+        assert_eq!(monitor.max_blocked, 1);
+        assert_eq!(monitor.thread_count, 1);
+        // The first dump has no previous one to weight it with
+        assert_eq!(monitor.contention_ms, 2000);
+        assert_eq!(names(&monitor.owners), vec![("worker-1", 2)]);
+        assert_eq!(
+            names(&monitor.frames),
+            vec![("com.example.Worker.update(Worker.java:58)", 2)]
+        );
+        assert_eq!(monitor.last_seen, 3000);
+
+        // The frame calling `ReentrantLock.lock()` is the one the thread is blocked at
+        let lock = &monitors[1];
+        assert_eq!(lock.address, "0x0000000712b01a88");
+        assert_eq!(names(&lock.owners), vec![("worker-1", 2)]);
+        assert_eq!(
+            names(&lock.frames),
+            vec![("com.example.Cache.put(Cache.java:33)", 2)]
+        );
+    }
+
+    #[test]
+    fn skips_parking_threads_without_owner() {
+        // Without `-l` the owners of `java.util.concurrent` locks are unknown
+        let mut sample = sample(
+            0,
+            include_str!("../jcmd-parse/tests/fixtures/jdk17/g1/Thread.print.l.txt"),
+        );
+        for thread in &mut sample.thread_dump.application_threads {
+            thread.locked_synchronizers.clear();
+        }
+        let mut contention = LockContention::default();
+        contention.add(&sample);
+
+        let monitors = contention.monitors();
+        assert_eq!(monitors.len(), 1);
+        assert_eq!(monitors[0].address, "0x0000000712a4b6d8");
+        assert_eq!(names(&monitors[0].owners), vec![("worker-1", 1)]);
+    }
+
+    #[test]
+    fn takes_owner_instead_of_waiters_of_monitor() {
+        let mut contention = LockContention::default();
+        contention.add(&sample(
+            0,
+            include_str!("../jcmd-parse/tests/fixtures/jdk17/g1/Thread.print.wait.txt"),
+        ));
+
+        let monitors = contention.monitors();
+        assert_eq!(monitors.len(), 1);
+        let monitor = &monitors[0];
+        // The notified waiter re-locking the monitor and the blocked thread, but not the waiter
+        // still in `Object.wait()`
+        assert_eq!(monitor.blocked_count, 2);
+        assert_eq!(monitor.max_blocked, 2);
+        assert_eq!(names(&monitor.owners), vec![("owner", 2)]);
+        assert_eq!(
+            names(&monitor.frames),
+            vec![
+                ("Wait.lambda$main$2(Wait.java:32)", 1),
+                ("java.lang.Object.wait(java.base@17.0.15/Native Method)", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_weight_of_dumps_after_a_gap() {
+        let output = include_str!("../jcmd-parse/tests/fixtures/jdk17/g1/Thread.print.txt");
+        let mut contention = LockContention::default();
+        contention.add(&sample(0, output));
+        contention.add(&sample(10 * 60_000, output));
+
+        assert_eq!(contention.monitors()[0].contention_ms, MAX_WEIGHT_MS);
+    }
+}
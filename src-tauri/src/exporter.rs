@@ -0,0 +1,360 @@
+//! Exposes the latest samples of the monitored processes in the OpenMetrics text format, e.g. to
+//! scrape them with Prometheus.
+//!
+//! The exporter is disabled by default. It is enabled with `exporter.listen=<address>` in the
+//! config file (see [`crate::config`]) or with `--exporter <address>` of the `record`
+//! subcommand. A port without a host listens on localhost only.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use jcmd_parse::ThreadStateCounts;
+use once_cell::sync::Lazy;
+
+use crate::collector::{MetricsSample, ThreadsSample, VmInformation};
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Samples are only kept once the exporter is started.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Latest samples of the monitored processes keyed by pid.
+static PROCESSES: Lazy<Mutex<HashMap<String, ProcessMetrics>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Default)]
+struct ProcessMetrics {
+    main_class: Option<String>,
+    metrics: Option<MetricsSample>,
+    application_threads: Option<ThreadStateCounts>,
+    jvm_threads: Option<ThreadStateCounts>,
+}
+
+pub(crate) fn update_vm_information(pid: &str, vm_information: &VmInformation) {
+    let main_class = vm_information
+        .report
+        .vm_arguments
+        .as_ref()
+        .and_then(|a| a.java_command.as_ref())
+        .and_then(|c| c.split_whitespace().next())
+        .map(|c| c.to_string());
+    update(pid, |p| p.main_class = main_class);
+}
+
+pub(crate) fn update_metrics(pid: &str, sample: &MetricsSample) {
+    update(pid, |p| p.metrics = Some(sample.clone()));
+}
+
+pub(crate) fn update_threads(pid: &str, sample: &ThreadsSample) {
+    update(pid, |p| {
+        p.application_threads = Some(sample.thread_dump.application_thread_states());
+        p.jvm_threads = Some(sample.thread_dump.jvm_thread_states());
+    });
+}
+
+/// Stops exporting the samples of the process.
+pub(crate) fn remove(pid: &str) {
+    PROCESSES.lock().unwrap().remove(pid);
+}
+
+fn update(pid: &str, f: impl FnOnce(&mut ProcessMetrics)) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    f(PROCESSES
+        .lock()
+        .unwrap()
+        .entry(pid.to_string())
+        .or_default());
+}
+
+/// Starts to serve the metrics on `GET /metrics` in a background thread.
+pub(crate) fn start(address: &str) -> std::io::Result<()> {
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("127.0.0.1:{}", address)
+    };
+    let listener = TcpListener::bind(&address)?;
+    ENABLED.store(true, Ordering::Relaxed);
+    eprintln!("Exporting metrics on http://{}/metrics", address);
+    std::thread::Builder::new()
+        .name("exporter".to_string())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(e) = handle(stream) {
+                    eprintln!("Couldn't serve metrics: {}", e);
+                }
+            }
+        })?;
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers aren't needed, but have to be read before responding
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", CONTENT_TYPE, render()),
+        _ => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// A metric family with the samples of all processes.
+struct Family {
+    name: &'static str,
+    unit: Option<&'static str>,
+    help: &'static str,
+    samples: Vec<(String, u64)>,
+}
+
+impl Family {
+    fn new(name: &'static str, unit: Option<&'static str>, help: &'static str) -> Family {
+        Family {
+            name,
+            unit,
+            help,
+            samples: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, labels: String, value: Option<u64>) {
+        if let Some(value) = value {
+            self.samples.push((labels, value));
+        }
+    }
+}
+
+fn render() -> String {
+    let bytes = Some("bytes");
+    let mut nmt_reserved = Family::new(
+        "jvm_nmt_reserved_bytes",
+        bytes,
+        "Memory reserved per native memory tracking category.",
+    );
+    let mut nmt_committed = Family::new(
+        "jvm_nmt_committed_bytes",
+        bytes,
+        "Memory committed per native memory tracking category.",
+    );
+    let mut nmt_total_reserved = Family::new(
+        "jvm_nmt_total_reserved_bytes",
+        bytes,
+        "Memory reserved in total by the jvm.",
+    );
+    let mut nmt_total_committed = Family::new(
+        "jvm_nmt_total_committed_bytes",
+        bytes,
+        "Memory committed in total by the jvm.",
+    );
+    let mut heap_used = Family::new("jvm_heap_used_bytes", bytes, "Used java heap.");
+    let mut space_used = Family::new(
+        "jvm_space_used_bytes",
+        bytes,
+        "Used metaspace and class space.",
+    );
+    let mut space_committed = Family::new(
+        "jvm_space_committed_bytes",
+        bytes,
+        "Committed metaspace and class space.",
+    );
+    let mut space_reserved = Family::new(
+        "jvm_space_reserved_bytes",
+        bytes,
+        "Reserved metaspace and class space.",
+    );
+    let mut threads = Family::new("jvm_threads", None, "Number of threads per state.");
+
+    let processes = PROCESSES.lock().unwrap();
+    let mut pids: Vec<&String> = processes.keys().collect();
+    pids.sort();
+    for pid in pids {
+        let process = &processes[pid];
+        let labels = match &process.main_class {
+            Some(main_class) => format!(
+                "pid=\"{}\",main_class=\"{}\"",
+                escape(pid),
+                escape(main_class)
+            ),
+            None => format!("pid=\"{}\"", escape(pid)),
+        };
+
+        if let Some(sample) = &process.metrics {
+            let nmt = &sample.native_memory;
+            for category in &nmt.categories {
+                let labels = format!("{},category=\"{}\"", labels, escape(&category.name));
+                nmt_reserved.add(labels.clone(), category.reserved);
+                nmt_committed.add(labels, category.committed);
+            }
+            if let Some(total) = &nmt.total {
+                nmt_total_reserved.add(labels.clone(), total.reserved);
+                nmt_total_committed.add(labels.clone(), total.committed);
+            }
+
+            let heap_info = &sample.heap_info;
+            heap_used.add(labels.clone(), heap_info.used);
+            for (space, info) in [
+                ("metaspace", &heap_info.metaspace),
+                ("class_space", &heap_info.class_space),
+            ] {
+                let labels = format!("{},space=\"{}\"", labels, space);
+                space_used.add(labels.clone(), info.used);
+                space_committed.add(labels.clone(), info.committed);
+                space_reserved.add(labels, info.reserved);
+            }
+        }
+
+        for (kind, counts) in [
+            ("application", &process.application_threads),
+            ("jvm", &process.jvm_threads),
+        ] {
+            let Some(counts) = counts else {
+                continue;
+            };
+            for (state, count) in [
+                ("new", counts.new_count),
+                ("runnable", counts.runnable_count),
+                ("waiting", counts.waiting_count),
+                ("timed_waiting", counts.timed_waiting_count),
+                ("blocked", counts.blocked_count),
+            ] {
+                let labels = format!("{},kind=\"{}\",state=\"{}\"", labels, kind, state);
+                threads.add(labels, Some(u64::from(count)));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for family in [
+        nmt_reserved,
+        nmt_committed,
+        nmt_total_reserved,
+        nmt_total_committed,
+        heap_used,
+        space_used,
+        space_committed,
+        space_reserved,
+        threads,
+    ] {
+        let _ = writeln!(out, "# TYPE {} gauge", family.name);
+        if let Some(unit) = family.unit {
+            let _ = writeln!(out, "# UNIT {} {}", family.name, unit);
+        }
+        let _ = writeln!(out, "# HELP {} {}", family.name, family.help);
+        for (labels, value) in family.samples {
+            let _ = writeln!(out, "{}{{{}}} {}", family.name, labels, value);
+        }
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use jcmd_parse::{parse_heap_info, parse_nmt_summary};
+
+    use super::*;
+
+    #[test]
+    fn renders_openmetrics_text() {
+        let metrics = MetricsSample {
+            time: 0,
+            heap_info: parse_heap_info(include_str!(
+                "../jcmd-parse/tests/fixtures/jdk17/g1/GC.heap_info.txt"
+            ))
+            .value,
+            native_memory: parse_nmt_summary(include_str!(
+                "../jcmd-parse/tests/fixtures/jdk17/g1/VM.native_memory.txt"
+            ))
+            .value,
+            cgroup_memory: None,
+            warnings: Vec::new(),
+        };
+        PROCESSES.lock().unwrap().insert(
+            "4711".to_string(),
+            ProcessMetrics {
+                main_class: Some("com.example.\"Main\"\\\n".to_string()),
+                metrics: Some(metrics),
+                application_threads: Some(ThreadStateCounts {
+                    new_count: 0,
+                    runnable_count: 5,
+                    waiting_count: 1,
+                    timed_waiting_count: 2,
+                    blocked_count: 1,
+                }),
+                jvm_threads: None,
+            },
+        );
+
+        let out = render();
+        remove("4711");
+
+        let labels = r#"pid="4711",main_class="com.example.\"Main\"\\\n""#;
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.last(), Some(&"# EOF"));
+        assert!(out.ends_with("# EOF\n"));
+        assert_eq!(
+            lines[..4],
+            [
+                "# TYPE jvm_nmt_reserved_bytes gauge",
+                "# UNIT jvm_nmt_reserved_bytes bytes",
+                "# HELP jvm_nmt_reserved_bytes Memory reserved per native memory tracking category.",
+                &format!("jvm_nmt_reserved_bytes{{{},category=\"Java Heap\"}} 4143972352", labels),
+            ]
+        );
+        for line in [
+            format!("jvm_nmt_total_committed_bytes{{{}}} 402010328", labels),
+            format!("jvm_heap_used_bytes{{{}}} 34601984", labels),
+            format!(
+                "jvm_space_used_bytes{{{},space=\"class_space\"}} 2837504",
+                labels
+            ),
+            format!(
+                "jvm_threads{{{},kind=\"application\",state=\"blocked\"}} 1",
+                labels
+            ),
+        ] {
+            assert!(lines.contains(&line.as_str()), "{line} missing in {out}");
+        }
+        // Thread counts have no unit, missing counts aren't exported
+        assert!(!out.contains("# UNIT jvm_threads"));
+        assert!(!out.contains("kind=\"jvm\""));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape("com.example.Main"), "com.example.Main");
+        assert_eq!(escape(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape("a\nb"), r"a\nb");
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{ErrorKind, Read};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use jcmd_parse::JcmdError;
+use once_cell::sync::Lazy;
+
+use crate::config;
+
+static JCMD: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("".to_string()));
+
+/// jcmd commands taking longer are killed. Attaching to an unresponsive jvm can otherwise block
+/// for a long time.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn jcmd() -> Command {
+    let mut cmd = JCMD.lock().unwrap();
+    let mut path = (*cmd).clone();
+    if (*cmd).is_empty() {
+        let p = match std::env::var("JAVA_HOME") {
+            Ok(p) => format!("{}/bin/jcmd", p),
+            Err(_) => match config::value("java.home") {
+                Some(java_home) => format!("{}/bin/jcmd", java_home),
+                None => "jcmd".to_string(),
+            },
+        };
+        *cmd = p.clone();
+        path = p
+    }
+    Command::new(path)
+}
+
+/// Runs jcmd with the given arguments and returns its output.
+///
+/// Outputs signaling that the process is gone, attaching failed or native memory tracking is
+/// disabled are returned as errors. Commands not completing within [`TIMEOUT`] are killed.
+pub(crate) fn run_jcmd(args: &[&str]) -> Result<String, JcmdError> {
+    run_jcmd_with_timeout(args, TIMEOUT)
+}
+
+/// Runs jcmd like [`run_jcmd`], but with another timeout for commands known to take long (e.g.
+/// heap dumps).
+pub(crate) fn run_jcmd_with_timeout(args: &[&str], timeout: Duration) -> Result<String, JcmdError> {
+    // The first argument is the pid, the second one the diagnostic command
+    let command_name = args.get(1).unwrap_or(&"jcmd").to_string();
+    let mut command = jcmd();
+    let command_failed = |message: String| JcmdError::CommandFailed {
+        command: command_name.clone(),
+        message,
+    };
+    let mut child = match command
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(JcmdError::JcmdMissing {
+                path: command.get_program().to_string_lossy().to_string(),
+            })
+        }
+        Err(e) => return Err(command_failed(e.to_string())),
+    };
+
+    // Read the output in the background, as jcmd blocks once the pipe buffers are full
+    let stdout = read_to_end(child.stdout.take());
+    let stderr = read_to_end(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(JcmdError::Timeout {
+                    command: command_name,
+                });
+            }
+            Err(e) => {
+                // Don't leave a stray jcmd behind
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(command_failed(e.to_string()));
+            }
+        }
+    };
+
+    let output = String::from_utf8_lossy(&stdout.join().unwrap_or_default()).to_string();
+    jcmd_parse::check_output(&output)?;
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr.join().unwrap_or_default()).to_string();
+        let message = format!("{}\n{}", output, stderr)
+            .split('\n')
+            .map(|l| l.trim())
+            .rev()
+            .find(|l| !l.is_empty())
+            .map(|l| l.to_string())
+            .unwrap_or_else(|| status.to_string());
+        return Err(command_failed(message));
+    }
+    Ok(output)
+}
+
+fn read_to_end<R: Read + Send + 'static>(reader: Option<R>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut reader) = reader {
+            let _ = reader.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
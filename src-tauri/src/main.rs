@@ -3,984 +3,1280 @@
     windows_subsystem = "windows"
 )]
 
+mod cgroup;
+mod collector;
+mod config;
+mod contention;
+mod exporter;
+mod jcmd;
+mod metrics;
+mod profiler;
+mod record;
+mod scheduler;
+mod session;
+mod thread_history;
+
 use std::collections::HashMap;
-use std::process::Command;
-use std::string::ToString;
+use std::path::Path;
 use std::sync::Mutex;
-use std::time::SystemTime;
+use std::time::{Duration, Instant};
 
+use jcmd_parse::{
+    ApplicationThread, ClassHistogramDiff, JcmdError, JvmProcessRef, JvmThread, LockChain,
+};
 use once_cell::sync::Lazy;
-use serde::Serialize;
-
-static CACHE: Lazy<Mutex<MetricsCache>> = Lazy::new(|| {
-    Mutex::new(MetricsCache {
-        total_memory: NamedMetric::new("Total".to_string()),
-        class_metrics: NamedMetric::new("Class".to_string()),
-        heap_metrics: NamedMetric::new("Heap".to_string()),
-        metaspace_metrics: NamedMetric::new("Metaspace".to_string()),
-        thread_metrics: NamedMetric::new("Thread".to_string()),
-        thread_count_metrics_application: NamedMetric::new("ThreadCountApplication".to_string()),
-        thread_count_metrics_jvm: NamedMetric::new("ThreadCountJvm".to_string()),
-        other_metrics: HashMap::new(),
-    })
-});
-
-static THREAD_CACHE: Lazy<Mutex<HashMap<String, ThreadCacheEntry>>> =
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::collector::{
+    ClassHistogramSample, FlightRecording, HeapDump, HeapDumpOptions, HeapDumpStatus, JfrDump,
+    JfrOptions, JfrSample, NmtDetailSample, NmtDiffSample, SystemPropertiesSample, ThreadsSample,
+    VmFlagChange, VmFlagsSample, VmInformation,
+};
+use crate::contention::MonitorContention;
+use crate::jcmd::run_jcmd;
+use crate::metrics::{
+    ClassHistogramMetricValue, ClassMemoryMetricValue, ContainerMemoryMetricValue,
+    GenericMemoryMetric, HeapMemoryMetricValue, MetaspaceMemoryMetricValue, MetricsCache,
+    NamedMetric, NativeMemoryMetricValue, Selection, ThreadChurnMetricValue,
+    ThreadCountMetricValue, ThreadMemoryMetricValue, TotalMemoryMetricValue,
+};
+use crate::profiler::{CollapsedStack, Profile, ProfileOptions, ProfileStatus};
+use crate::scheduler::{Scheduler, Task};
+use crate::session::{ClassHistogramOptions, Session, SessionError};
+use crate::thread_history::ThreadHistory;
+
+type Pid = String;
+
+/// Sessions of the monitored processes. Several processes can be monitored at the same time.
+static SESSIONS: Lazy<Mutex<HashMap<Pid, Session>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Schedulers collecting the samples of the monitored processes.
+static SCHEDULERS: Lazy<Mutex<HashMap<Pid, (Intervals, Scheduler)>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-static JCMD: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("".to_string()));
+/// Number of values per series returned by [`get_history`] by default.
+const HISTORY_POINTS: usize = 500;
+
+/// Number of classes returned by [`diff_class_histograms`] by default.
+const CLASS_HISTOGRAM_DIFF_TOP: usize = 100;
+
+/// Minimum number of threads of a lock chain reported along with the deadlocks of a thread dump.
+const LOCK_CHAIN_MIN_LENGTH: usize = 3;
+
+/// Interval of the `heap-dump` events while a heap dump is written.
+const HEAP_DUMP_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Interval of the `profile` events while a profile is running.
+const PROFILE_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest interval between two thread dumps of a profile in milliseconds. Attaching `jcmd` and
+/// dumping the threads often takes longer, the ticks passed meanwhile are skipped and reported.
+const MIN_PROFILE_INTERVAL: u64 = 100;
+
+/// Longest duration of a profile in seconds.
+const MAX_PROFILE_DURATION: u64 = 600;
+
+/// Session opened from a file for replay.
+static REPLAY: Lazy<Mutex<Option<Session>>> = Lazy::new(|| Mutex::new(None));
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|a| a.as_str()) == Some("record") {
+        std::process::exit(record::run(&args[1..]));
+    }
+    if let Some(address) = config::value("exporter.listen") {
+        if let Err(e) = exporter::start(&address) {
+            eprintln!("Couldn't start exporter on {}: {}", address, e);
+        }
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_window_state::Builder::default().build())
         .invoke_handler(tauri::generate_handler![
             get_jvm_processes,
             get_jvm_metrics,
             get_threads,
+            get_thread_history,
+            dump_threads_with_locks,
+            set_thread_dump_locks,
+            get_lock_contention,
             get_vm_information,
-            reset
+            get_history,
+            get_nmt_diffs,
+            get_nmt_detail,
+            get_vm_flags,
+            get_system_properties,
+            set_vm_flag,
+            get_vm_flag_changes,
+            get_class_histograms,
+            diff_class_histograms,
+            set_class_histogram,
+            start_heap_dump,
+            get_heap_dumps,
+            start_profiling,
+            stop_profiling,
+            get_profile,
+            export_profile,
+            start_jfr_recording,
+            dump_jfr_recording,
+            stop_jfr_recording,
+            check_jfr_recordings,
+            get_jfr_recordings,
+            set_nmt_baseline,
+            start_monitoring,
+            stop_monitoring,
+            save_session,
+            open_session,
+            get_replay_threads,
+            get_replay_lock_contention,
+            get_replay_thread_history
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
+/// Sampling intervals of the collectors in milliseconds.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Intervals {
+    metrics: u64,
+    threads: u64,
+    vm_information: u64,
+    class_histogram: u64,
+    jfr: u64,
+}
+
+impl Default for Intervals {
+    fn default() -> Self {
+        Intervals {
+            metrics: 5000,
+            threads: 2000,
+            vm_information: 10000,
+            class_histogram: 60000,
+            jfr: 10000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SampleEvent<T> {
+    pid: String,
+    sample: T,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectorErrorEvent {
+    pid: String,
+    collector: &'static str,
+    error: JcmdError,
+}
+
+/// Starts collecting samples of the process in the background.
+///
+/// New samples are emitted as `jvm-metrics`, `jvm-threads`, `vm-information`, `nmt-diff` (once a
+/// native memory baseline is set), `class-histogram` (once enabled) and `jfr-recordings` (once the
+/// flight recorder is in use) events holding only the new values. Failed collections are emitted
+/// as `collector-error` events. Collecting stops when the process is gone.
 #[tauri::command]
-fn reset() {
-    let mut c = CACHE.lock().unwrap();
-    c.class_metrics.values.clear();
-    c.heap_metrics.values.clear();
-    c.metaspace_metrics.values.clear();
-    c.other_metrics.clear();
-    c.total_memory.values.clear();
-    c.thread_count_metrics_application.values.clear();
-    c.thread_count_metrics_jvm.values.clear();
-    c.thread_metrics.values.clear();
-}
-
-fn jcmd() -> Command {
-    let mut cmd = JCMD.lock().unwrap();
-    let mut path = (*cmd).clone();
-    if (*cmd).is_empty() {
-        let jcmd: String = "jcmd".to_string();
-        let home_dir = std::env::var("HOME").unwrap_or("".to_string());
-        let jcmd_ui_config = format!("{}/.config/jcmd-ui/config", home_dir);
-        let jcmd_ui_config_path = std::path::Path::new(&jcmd_ui_config);
-        let p = match std::env::var("JAVA_HOME") {
-            Ok(p) => format!("{}/bin/jcmd", p),
-            Err(_) => {
-                if jcmd_ui_config_path.exists() {
-                    if let Ok(content) = std::fs::read_to_string(jcmd_ui_config_path) {
-                        let lines: Vec<&str> = content.split('\n').collect();
-                        let java_home_paths: Vec<&str> = lines
-                            .into_iter()
-                            .filter(|s| s.starts_with("java.home="))
-                            .collect();
-                        if !java_home_paths.is_empty() {
-                            let jcmd_dir = java_home_paths.first().unwrap().to_string().clone()
-                                [10..]
-                                .to_string();
-                            format!("{}/bin/jcmd", jcmd_dir)
-                        } else {
-                            jcmd
-                        }
-                    } else {
-                        jcmd
-                    }
-                } else {
-                    jcmd
-                }
-            }
-        };
-        *cmd = p.clone();
-        path = p
-    }
-    Command::new(path)
-}
-
-#[tauri::command]
-fn get_jvm_processes() -> Result<JvmProcesses, String> {
-    match jcmd().output() {
-        Ok(o) => {
-            if o.status.success() {
-                let output = String::from_utf8_lossy(o.stdout.as_slice()).to_string();
-                let lines: Vec<&str> = output.split('\n').collect();
-                let mut processes: Vec<JvmProcessRef> = Vec::new();
-                for line in lines {
-                    if (line).contains("jdk.jcmd") {
-                        continue;
-                    }
-                    let parts: Vec<&str> = line.split(' ').collect();
-                    if parts.len() < 2 {
-                        continue;
-                    }
-                    if parts.len() >= 3 {
-                        let mut path: Vec<String> = Vec::new();
-                        for p in 2..(parts.len()) {
-                            let value = parts.get(p);
-                            if let Some(v) = value {
-                                path.push(v.to_string());
-                            }
-                        }
-                        processes.push(JvmProcessRef {
-                            id: parts.first().unwrap().to_string(),
-                            name: parts.get(1).unwrap().to_string(),
-
-                            path: Some(path.join(" ")),
-                        })
-                    } else {
-                        processes.push(JvmProcessRef {
-                            id: parts.first().unwrap().to_string(),
-                            name: parts.get(1).unwrap().to_string(),
-                            path: None,
-                        })
-                    }
-                }
-
-                return Ok(JvmProcesses { processes });
-            }
-            Err("Data couldn't be read successfully".to_string())
+fn start_monitoring(
+    app: AppHandle,
+    pid: String,
+    intervals: Option<Intervals>,
+) -> Result<(), JcmdError> {
+    let intervals = intervals.unwrap_or_default();
+    for (interval, command) in [
+        (intervals.metrics, "VM.native_memory"),
+        (intervals.threads, "Thread.print"),
+        (intervals.vm_information, "VM.info"),
+        (intervals.class_histogram, "GC.class_histogram"),
+        (intervals.jfr, "JFR.check"),
+    ] {
+        if Duration::from_millis(interval) < collector::MIN_INTERVAL {
+            return Err(JcmdError::CommandFailed {
+                command: command.to_string(),
+                message: format!(
+                    "The interval must be at least {} ms",
+                    collector::MIN_INTERVAL.as_millis()
+                ),
+            });
         }
-        Err(e) => Err(e.to_string()),
-    }
-}
-
-#[tauri::command]
-fn get_vm_information(pid: &str) -> Result<VmInformation, String> {
-    match jcmd().arg(pid).arg("VM.info").output() {
-        Ok(o) => {
-            if o.status.success() {
-                let output = String::from_utf8_lossy(o.stdout.as_slice()).to_string();
-                let blocks: Vec<&str> = output.split("\n\n").collect();
-
-                let mut vm_arguments = None;
-                let mut vm_resources = None;
-                for block in blocks {
-                    let rows: Vec<&str> = block.split('\n').collect();
-                    let row1 = rows.first();
-                    if let Some(first_row) = row1 {
-                        if first_row.starts_with("VM Arguments:") {
-                            let mut jvm_args = None;
-                            let mut java_command = None;
-                            for row in rows {
-                                if row.starts_with("jvm_args:") {
-                                    jvm_args = Some(row.replace("jvm_args:", "").trim().to_string())
-                                } else if row.starts_with("java_command:") {
-                                    java_command =
-                                        Some(row.replace("java_command:", "").trim().to_string())
-                                }
-                            }
-                            vm_arguments = Some(VmArguments {
-                                jvm_args,
-                                java_command,
-                            });
-                        } else if first_row.starts_with("GC Precious Log:") {
-                            let mut cpus = None;
-                            let mut memory = None;
-                            let mut heap_size_min = None;
-                            let mut heap_size_init = None;
-                            let mut heap_size_max = None;
-                            for row in rows {
-                                if row.starts_with(" CPUs:") {
-                                    cpus = Some(row.replace(" CPUs:", "").trim().to_string())
-                                } else if row.starts_with(" Memory:") {
-                                    memory = Some(row.replace(" Memory:", "").trim().to_string())
-                                } else if row.starts_with(" Heap Min Capacity:") {
-                                    heap_size_min = Some(
-                                        row.replace(" Heap Min Capacity:", "").trim().to_string(),
-                                    )
-                                } else if row.starts_with(" Min Capacity:") {
-                                    // zgc
-                                    heap_size_min =
-                                        Some(row.replace(" Min Capacity:", "").trim().to_string())
-                                } else if row.starts_with(" Heap Initial Capacity:") {
-                                    heap_size_init = Some(
-                                        row.replace(" Heap Initial Capacity:", "")
-                                            .trim()
-                                            .to_string(),
-                                    )
-                                } else if row.starts_with(" Initial Capacity:") {
-                                    // zgc
-                                    heap_size_init = Some(
-                                        row.replace(" Initial Capacity:", "").trim().to_string(),
-                                    )
-                                } else if row.starts_with(" Heap Max Capacity:") {
-                                    heap_size_max = Some(
-                                        row.replace(" Heap Max Capacity:", "").trim().to_string(),
-                                    )
-                                } else if row.starts_with(" Max Capacity:") {
-                                    // zgc
-                                    heap_size_max =
-                                        Some(row.replace(" Max Capacity:", "").trim().to_string())
-                                }
-                            }
-                            vm_resources = Some(VmResources {
-                                cpus,
-                                memory,
-                                heap_size_min,
-                                heap_size_init,
-                                heap_size_max,
-                            })
-                        }
-                    }
-                }
-
-                return Ok(VmInformation {
-                    vm_arguments,
-                    vm_resources,
-                });
-            }
-            Err("Data couldn't be read successfully".to_string())
+    }
+    let mut schedulers = SCHEDULERS.lock().unwrap();
+    if let Some((running_intervals, scheduler)) = schedulers.get(&pid) {
+        if running_intervals == &intervals && scheduler.is_running() {
+            return Ok(());
         }
-        Err(e) => Err(e.to_string()),
     }
+
+    SESSIONS
+        .lock()
+        .unwrap()
+        .entry(pid.clone())
+        .or_insert_with(|| Session::new(Some(pid.clone()), collector::now()));
+
+    let tasks = vec![
+        collector_task(
+            &app,
+            &pid,
+            intervals.vm_information,
+            "vmInformation",
+            emit_vm_information,
+        ),
+        collector_task(&app, &pid, intervals.threads, "threads", emit_threads),
+        collector_task(&app, &pid, intervals.metrics, "metrics", emit_metrics),
+        collector_task(&app, &pid, intervals.metrics, "nmtDiff", emit_nmt_diff),
+        collector_task(
+            &app,
+            &pid,
+            intervals.class_histogram,
+            "classHistogram",
+            emit_class_histogram,
+        ),
+        collector_task(&app, &pid, intervals.jfr, "jfr", emit_jfr_recordings),
+    ];
+    let scheduler = Scheduler::start(format!("monitor-{}", pid), tasks);
+    // Replacing a scheduler stops the previous one
+    schedulers.insert(pid, (intervals, scheduler));
+    Ok(())
 }
 
+/// Stops collecting samples of the process and discards the collected samples.
 #[tauri::command]
-fn get_jvm_metrics(pid: &str) -> Result<JvmMetrics, String> {
-    let time = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
-    let mut heap_size = None;
-    let mut metaspace_size = None;
-    let mut class_space_size = None;
-
-    // Parse committed / reserved sizes from heap info as they seem to be more accurate
-    // than the values from the native memory tracking.
-    let mut metaspace_committed = None;
-    let mut metaspace_reserved = None;
-    let mut class_space_committed = None;
-    let mut class_space_reserved = None;
-
-    match jcmd().arg(pid).arg("GC.heap_info").output() {
-        Ok(o) => {
-            let output = String::from_utf8_lossy(o.stdout.as_slice()).to_string();
-            if output.contains("IOException: No such process") {
-                return Err("No such process".to_string());
-            }
+fn stop_monitoring(pid: &str) {
+    SCHEDULERS.lock().unwrap().remove(pid);
+    SESSIONS.lock().unwrap().remove(pid);
+    exporter::remove(pid);
+}
 
-            let rows: Vec<&str> = output.split('\n').collect();
-            let mut is_shenandoah = false;
-            for row in rows {
-                if row.starts_with("Shenandoah") {
-                    is_shenandoah = true;
-                } else if is_shenandoah && heap_size.is_none() {
-                    // parse used size from second line
-                    heap_size = parse_memory_from_heap_info(row, "used", true);
-                } else if row.starts_with(" ZHeap")
-                    || row.starts_with(" garbage-first")
-                    // def new generation is serial - new gen
-                    || row.starts_with(" def new generation")
-                {
-                    heap_size = parse_memory_from_heap_info(row, "used", false);
-                } else if row.starts_with(" tenured generation") && heap_size.is_some() {
-                    // serial - old gen
-                    let old_gen_size = parse_memory_from_heap_info(row, "used", false);
-                    if let Some(old_gen) = old_gen_size {
-                        heap_size = Some(heap_size.unwrap() + old_gen);
-                    }
-                } else if row.starts_with(" PSYoungGen") {
-                    // parallel - new gen
-                    heap_size = parse_memory_from_heap_info(row, "used", false);
-                } else if row.starts_with(" ParOldGen") && heap_size.is_some() {
-                    // parallel - old gen
-                    let old_gen_size = parse_memory_from_heap_info(row, "used", false);
-                    if let Some(old_gen) = old_gen_size {
-                        heap_size = Some(heap_size.unwrap() + old_gen);
-                    }
-                } else if row.starts_with(" Metaspace") {
-                    metaspace_size = parse_memory_from_heap_info(row, "used", false);
-                    metaspace_committed = parse_memory_from_heap_info(row, "committed", false);
-                    metaspace_reserved = parse_memory_from_heap_info(row, "reserved", false);
-                } else if row.starts_with("  class space") {
-                    class_space_size = parse_memory_from_heap_info(row, "used", false);
-                    class_space_committed = parse_memory_from_heap_info(row, "committed", false);
-                    class_space_reserved = parse_memory_from_heap_info(row, "reserved", false);
-                }
+fn collector_task(
+    app: &AppHandle,
+    pid: &str,
+    interval: u64,
+    collector: &'static str,
+    collect: fn(&AppHandle, &str) -> Result<bool, JcmdError>,
+) -> Task {
+    let app = app.clone();
+    let pid = pid.to_string();
+    Task::new(Duration::from_millis(interval), move || {
+        let monitored = match collect(&app, &pid) {
+            Ok(monitored) => monitored,
+            Err(error) => {
+                let process_gone = error == JcmdError::ProcessGone;
+                let event = CollectorErrorEvent {
+                    pid: pid.clone(),
+                    collector,
+                    error,
+                };
+                let _ = app.emit_all("collector-error", event);
+                !process_gone
             }
+        };
+        // The scheduler stops, don't export the last samples forever
+        if !monitored {
+            exporter::remove(&pid);
+        }
+        monitored
+    })
+}
+
+/// Runs `f` with the session of the process. Returns `None` if the process isn't monitored
+/// (anymore).
+fn update_session<T>(pid: &str, f: impl FnOnce(&mut Session) -> T) -> Option<T> {
+    SESSIONS.lock().unwrap().get_mut(pid).map(f)
+}
+
+fn emit<T: Serialize + Clone>(app: &AppHandle, event: &str, pid: &str, sample: T) {
+    let event_payload = SampleEvent {
+        pid: pid.to_string(),
+        sample,
+    };
+    let _ = app.emit_all(event, event_payload);
+}
+
+fn emit_vm_information(app: &AppHandle, pid: &str) -> Result<bool, JcmdError> {
+    let vm_information = collector::collect_vm_information(pid)?;
+    let update = update_session(pid, |session| {
+        exporter::update_vm_information(pid, &vm_information);
+        session.vm_information = Some(vm_information.clone());
+    });
+    if update.is_some() {
+        emit(app, "vm-information", pid, vm_information);
+    }
+    Ok(update.is_some())
+}
+
+fn emit_metrics(app: &AppHandle, pid: &str) -> Result<bool, JcmdError> {
+    let sample = collector::collect_metrics(pid)?;
+
+    let update = update_session(pid, |session| {
+        // The event only contains the values of the new sample and the rollups created with it
+        let since = session.metrics_samples.last().map_or(0, |s| s.time);
+        let warnings = sample.warnings.clone();
+        exporter::update_metrics(pid, &sample);
+        session.add_metrics(sample);
+        JvmMetrics::new(&session.metrics, warnings, Selection::Since(since))
+    });
+    let monitored = update.is_some();
+    if let Some(update) = update {
+        emit(app, "jvm-metrics", pid, update);
+    }
+    Ok(monitored)
+}
+
+fn emit_threads(app: &AppHandle, pid: &str) -> Result<bool, JcmdError> {
+    let Some(locks) = update_session(pid, |session| session.thread_dump_locks) else {
+        return Ok(false);
+    };
+    let sample = collector::collect_threads(pid, locks)?;
+    Ok(add_threads(app, pid, sample))
+}
+
+/// Adds the thread dump to the session of the process and emits it. Returns `false` if the
+/// process isn't monitored (anymore).
+fn add_threads(app: &AppHandle, pid: &str, mut sample: ThreadsSample) -> bool {
+    let update = update_session(pid, |session| {
+        // The event only contains the thread counts of the new sample and the rollups created
+        // with it
+        let since = session.thread_samples.last().map_or(0, |s| s.time);
+        session.thread_cpu.apply(&mut sample.thread_dump);
+        exporter::update_threads(pid, &sample);
+        session.add_threads(sample.clone());
+        Threads::new(&session.metrics, sample, Selection::Since(since))
+    });
+    let monitored = update.is_some();
+    if let Some(update) = update {
+        emit(app, "jvm-threads", pid, update);
+    }
+    monitored
+}
+
+fn emit_nmt_diff(app: &AppHandle, pid: &str) -> Result<bool, JcmdError> {
+    match update_session(pid, |session| session.nmt_baseline.is_some()) {
+        Some(true) => {}
+        // Nothing to compare to until a baseline is set
+        Some(false) => return Ok(true),
+        None => return Ok(false),
+    }
+    let sample = collector::collect_nmt_diff(pid)?;
+    let update = update_session(pid, |session| session.add_nmt_diff(sample.clone()));
+    if update.is_some() {
+        emit(app, "nmt-diff", pid, sample);
+    }
+    Ok(update.is_some())
+}
+
+fn emit_class_histogram(app: &AppHandle, pid: &str) -> Result<bool, JcmdError> {
+    let options = match update_session(pid, |session| session.class_histogram_options) {
+        Some(Some(options)) => options,
+        // Not enabled
+        Some(None) => return Ok(true),
+        None => return Ok(false),
+    };
+    let sample = collector::collect_class_histogram(pid, options.all)?;
+    let update = update_session(pid, |session| {
+        // The event only contains the values of the new histogram and the rollups created with it
+        let since = session.class_histograms.last().map_or(0, |s| s.time);
+        let total = ClassHistogramTotal::new(&sample);
+        let warnings = sample.warnings.clone();
+        session.add_class_histogram(sample);
+        ClassHistogramUpdate {
+            total,
+            classes: class_histogram_metrics(&session.metrics, Selection::Since(since)),
+            warnings,
+        }
+    });
+    let monitored = update.is_some();
+    if let Some(update) = update {
+        emit(app, "class-histogram", pid, update);
+    }
+    Ok(monitored)
+}
+
+fn emit_jfr_recordings(app: &AppHandle, pid: &str) -> Result<bool, JcmdError> {
+    match update_session(pid, |session| session.jfr_in_use()) {
+        Some(true) => {}
+        // Not polled until a recording is started
+        Some(false) => return Ok(true),
+        None => return Ok(false),
+    }
+    let sample = collector::jfr_check(pid)?;
+    Ok(update_jfr_recordings(app, pid, sample, |_| {}).is_some())
+}
+
+/// Updates the recordings of the session with the output of `JFR.check` and emits them as
+/// `jfr-recordings` event. `f` is applied to the session before emitting.
+fn update_jfr_recordings(
+    app: &AppHandle,
+    pid: &str,
+    sample: JfrSample,
+    f: impl FnOnce(&mut Session),
+) -> Option<FlightRecordings> {
+    let update = update_session(pid, |session| {
+        session.update_jfr_recordings(sample.time, sample.recordings);
+        f(session);
+        FlightRecordings {
+            recordings: session.jfr_recordings.clone(),
+            warnings: sample.warnings,
         }
-        Err(e) => return Err(e.to_string()),
+    });
+    if let Some(update) = &update {
+        emit(app, "jfr-recordings", pid, update.clone());
+    }
+    update
+}
+
+#[tauri::command]
+fn get_jvm_processes() -> Result<JvmProcesses, JcmdError> {
+    let output = run_jcmd(&[])?;
+    let processes = jcmd_parse::parse_jvm_processes(&output);
+    Ok(JvmProcesses { processes })
+}
+
+/// Returns the latest vm information of the monitored process.
+#[tauri::command]
+fn get_vm_information(pid: &str) -> Option<VmInformation> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions.get(pid).and_then(|s| s.vm_information.clone())
+}
+
+/// Returns the memory metrics collected from the monitored process after `since` (all of them
+/// if not given).
+#[tauri::command]
+fn get_jvm_metrics(pid: &str, since: Option<u64>) -> Option<JvmMetrics> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions
+        .get(pid)
+        .map(|s| jvm_metrics(s, since_selection(since)))
+}
+
+/// Returns the latest thread dump and the thread counts collected from the monitored process
+/// after `since` (all of them if not given).
+#[tauri::command]
+fn get_threads(pid: &str, since: Option<u64>) -> Option<Threads> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions
+        .get(pid)
+        .and_then(|s| threads(s, since_selection(since)))
+}
+
+/// Returns the samples collected from the monitored process so far with at most `max_points`
+/// values per series, e.g. to render the charts of a process monitored for a long time.
+#[tauri::command]
+fn get_history(pid: &str, max_points: Option<usize>) -> Option<History> {
+    let selection = Selection::Downsampled(max_points.unwrap_or(HISTORY_POINTS));
+    let sessions = SESSIONS.lock().unwrap();
+    sessions.get(pid).map(|s| History {
+        vm_information: s.vm_information.clone(),
+        metrics: jvm_metrics(s, selection),
+        threads: threads(s, selection),
+    })
+}
+
+fn since_selection(since: Option<u64>) -> Selection {
+    match since {
+        Some(since) => Selection::Since(u128::from(since)),
+        None => Selection::All,
+    }
+}
+
+fn jvm_metrics(session: &Session, selection: Selection) -> JvmMetrics {
+    let warnings = session.metrics_samples.last().map(|m| m.warnings.clone());
+    JvmMetrics::new(&session.metrics, warnings.unwrap_or_default(), selection)
+}
+
+fn threads(session: &Session, selection: Selection) -> Option<Threads> {
+    session
+        .thread_samples
+        .last()
+        .map(|sample| Threads::new(&session.metrics, sample.clone(), selection))
+}
+
+/// Returns the threads seen in the thread dumps of the monitored process, the alive ones first.
+#[tauri::command]
+fn get_thread_history(pid: &str) -> Option<Vec<ThreadHistory>> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions.get(pid).map(|s| s.thread_histories.threads())
+}
+
+/// Takes a thread dump of the monitored process including the locked ownable synchronizers
+/// (`Thread.print -l`). The dump is added to the session like the periodic ones.
+#[tauri::command]
+fn dump_threads_with_locks(app: AppHandle, pid: &str) -> Result<(), JcmdError> {
+    let sample = collector::collect_threads(pid, true)?;
+    if !add_threads(&app, pid, sample) {
+        return Err(JcmdError::CommandFailed {
+            command: "Thread.print".to_string(),
+            message: "The process is not monitored".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Enables (or disables) the locked ownable synchronizers (`Thread.print -l`) in the periodic
+/// thread dumps of the monitored process.
+#[tauri::command]
+fn set_thread_dump_locks(pid: &str, enabled: bool) {
+    update_session(pid, |session| session.thread_dump_locks = enabled);
+}
+
+/// Returns the monitors the threads of the monitored process were blocked on.
+#[tauri::command]
+fn get_lock_contention(pid: &str) -> Option<LockContentionReport> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions.get(pid).map(|s| LockContentionReport {
+        thread_dump_locks: s.thread_dump_locks,
+        monitors: s.lock_contention.monitors(),
+    })
+}
+
+/// Takes a native memory baseline of the monitored process. The native memory diffs collected
+/// before are discarded. Returns the time of the baseline.
+#[tauri::command]
+fn set_nmt_baseline(pid: &str) -> Result<u128, JcmdError> {
+    collector::set_nmt_baseline(pid)?;
+    let time = collector::now();
+    update_session(pid, |session| session.set_nmt_baseline(time));
+    Ok(time)
+}
+
+/// Returns the native memory diffs to the baseline collected from the monitored process.
+#[tauri::command]
+fn get_nmt_diffs(pid: &str) -> Option<NmtDiffs> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions.get(pid).map(|s| NmtDiffs {
+        baseline: s.nmt_baseline,
+        samples: s.nmt_diff_samples.clone(),
+    })
+}
+
+/// Starts a heap dump of the monitored process in the background. The size written so far and the
+/// result are emitted as `heap-dump` events. Only one heap dump runs at a time.
+#[tauri::command]
+fn start_heap_dump(
+    app: AppHandle,
+    pid: String,
+    options: HeapDumpOptions,
+) -> Result<HeapDump, JcmdError> {
+    let invalid = |message: String| JcmdError::CommandFailed {
+        command: "GC.heap_dump".to_string(),
+        message,
     };
-    match jcmd()
-        .arg(pid)
-        .arg("VM.native_memory")
-        .arg("scale=b")
-        .output()
+    if !Path::new(&options.path).is_absolute() {
+        return Err(invalid(
+            "The path of the heap dump must be absolute".to_string(),
+        ));
+    }
+    if options
+        .compression
+        .is_some_and(|level| !(1..=9).contains(&level))
     {
-        Ok(o) => {
-            if o.status.success() {
-                let output = String::from_utf8_lossy(o.stdout.as_slice()).to_string();
-                if output.contains("IOException: No such process") {
-                    return Err("No such process".to_string());
-                } else if output.contains("Native memory tracking is not enabled") {
-                    return Err(
-                        "Native memory tracking not activated. Start application with java \
-                    -XX:NativeMemoryTracking=summary -jar ..."
-                            .to_string(),
-                    );
-                }
-
-                let rows: Vec<&str> = output.split('\n').collect();
-                let mut buffer: Vec<String> = Vec::new();
-
-                for row in rows {
-                    if row.trim() == "" {
-                        for buffer_row in buffer {
-                            if buffer_row.starts_with("Total:") {
-                                let total_memory_parts: Vec<&str> = buffer_row.split(' ').collect();
-                                let (reserved, committed) =
-                                    parse_reserved_committed(total_memory_parts);
-                                let total_memory_metric = TotalMemoryMetricValue {
-                                    time,
-                                    reserved,
-                                    committed,
-                                };
-                                CACHE
-                                    .lock()
-                                    .unwrap()
-                                    .total_memory
-                                    .values
-                                    .push(total_memory_metric)
-                            } else if buffer_row.starts_with('-') && buffer_row.contains("Class") {
-                                let class_memory_metric = ClassMemoryMetricValue {
-                                    time,
-                                    reserved: class_space_reserved,
-                                    committed: class_space_committed,
-                                    class_count: 0,
-                                    used: class_space_size,
-                                };
-                                CACHE
-                                    .lock()
-                                    .unwrap()
-                                    .class_metrics
-                                    .values
-                                    .push(class_memory_metric)
-                            } else if buffer_row.starts_with('-') && buffer_row.contains("Thread") {
-                                let cleaned_row = buffer_row
-                                    .replace(['(', ')', ','], "")
-                                    .replace("Thread", "");
-                                let values: Vec<&str> = cleaned_row.split(' ').collect();
-                                let (reserved, committed) = parse_reserved_committed(values);
-                                let thread_memory_metric = ThreadMemoryMetricValue {
-                                    time,
-                                    reserved,
-                                    committed,
-                                    thread_count: 0,
-                                };
-                                CACHE
-                                    .lock()
-                                    .unwrap()
-                                    .thread_metrics
-                                    .values
-                                    .push(thread_memory_metric);
-                            } else if buffer_row.starts_with('-')
-                                && buffer_row.contains("Java Heap")
-                            {
-                                let cleaned_row = buffer_row
-                                    .replace(['(', ')', ','], "")
-                                    .replace("Java Heap", "");
-                                let values: Vec<&str> = cleaned_row.split(' ').collect();
-
-                                let (reserved, committed) = parse_reserved_committed(values);
-                                let heap_memory_metric = HeapMemoryMetricValue {
-                                    time,
-                                    reserved,
-                                    committed,
-                                    used: heap_size,
-                                };
-                                CACHE
-                                    .lock()
-                                    .unwrap()
-                                    .heap_metrics
-                                    .values
-                                    .push(heap_memory_metric);
-                            } else if buffer_row.starts_with('-')
-                                && buffer_row.contains("Metaspace")
-                            {
-                                let metaspace_memory_metric = MetaspaceMemoryMetricValue {
-                                    time,
-                                    reserved: metaspace_reserved,
-                                    committed: metaspace_committed,
-                                    used: metaspace_size,
-                                };
-                                CACHE
-                                    .lock()
-                                    .unwrap()
-                                    .metaspace_metrics
-                                    .values
-                                    .push(metaspace_memory_metric);
-                            } else if buffer_row.starts_with('-') {
-                                let cleaned_row = buffer_row.replace(['-', '(', ')', ','], "");
-                                let values: Vec<&str> = cleaned_row.split(' ').collect();
-
-                                let (name, reserved, committed) =
-                                    parse_name_reserved_committed(values);
-                                let metric = GenericMemoryMetricValue {
-                                    time,
-                                    reserved,
-                                    committed,
-                                };
-                                let n = name.unwrap();
-                                let mut c = CACHE.lock().unwrap();
-                                if !c.other_metrics.contains_key(&n) {
-                                    c.other_metrics.insert(
-                                        n.clone(),
-                                        GenericMemoryMetric {
-                                            name: n,
-                                            values: vec![metric],
-                                        },
-                                    );
-                                } else {
-                                    c.other_metrics.get_mut(&n).unwrap().values.push(metric);
-                                }
-                            } // else ignore
-                        }
-
-                        // reset buffer
-                        buffer = Vec::new();
-                    } else {
-                        buffer.push(row.to_string());
-                    }
-                }
-
-                let c = CACHE.lock().unwrap();
-                let jvm_metrics = JvmMetrics {
-                    total_memory: c.total_memory.clone(),
-                    class: c.class_metrics.clone(),
-                    heap: c.heap_metrics.clone(),
-                    metaspace: c.metaspace_metrics.clone(),
-                    thread: c.thread_metrics.clone(),
-                    other: c.other_metrics.values().cloned().collect(),
-                };
-                return Ok(jvm_metrics);
-            }
-            Err("Data couldn't be read successfully".to_string())
+        return Err(invalid(
+            "The compression level must be between 1 and 9".to_string(),
+        ));
+    }
+    let heap_dump = update_session(&pid, |session| {
+        if session
+            .heap_dumps
+            .iter()
+            .any(|d| d.status == HeapDumpStatus::Running)
+        {
+            return Err(invalid("Another heap dump is running".to_string()));
+        }
+        let heap_dump = HeapDump {
+            options,
+            started: collector::now(),
+            finished: None,
+            status: HeapDumpStatus::Running,
+            size: None,
+            heap_used: session.metrics.heap_metrics.last().and_then(|v| v.used()),
+            error: None,
+        };
+        session.update_heap_dump(heap_dump.clone());
+        Ok(heap_dump)
+    })
+    .unwrap_or_else(|| Err(invalid("The process is not monitored".to_string())))?;
+
+    let running = heap_dump.clone();
+    std::thread::Builder::new()
+        .name(format!("heap-dump-{}", pid))
+        .spawn(move || run_heap_dump(&app, &pid, running))
+        .map_err(|e| invalid(e.to_string()))?;
+    Ok(heap_dump)
+}
+
+fn run_heap_dump(app: &AppHandle, pid: &str, mut heap_dump: HeapDump) {
+    let dump = {
+        let pid = pid.to_string();
+        let options = heap_dump.options.clone();
+        std::thread::spawn(move || collector::heap_dump(&pid, &options))
+    };
+    // jcmd doesn't report any progress, so the size of the file written so far is emitted instead
+    while !dump.is_finished() {
+        std::thread::sleep(HEAP_DUMP_PROGRESS_INTERVAL);
+        let size = file_size(&heap_dump.options.path);
+        if size.is_some() && size != heap_dump.size {
+            heap_dump.size = size;
+            update_heap_dump(app, pid, &heap_dump);
+        }
+    }
+    let result = dump.join().unwrap_or_else(|_| {
+        Err(JcmdError::CommandFailed {
+            command: "GC.heap_dump".to_string(),
+            message: "The heap dump panicked".to_string(),
+        })
+    });
+    heap_dump.finished = Some(collector::now());
+    match result {
+        Ok(result) => {
+            heap_dump.status = HeapDumpStatus::Completed;
+            heap_dump.size = result.bytes.or_else(|| file_size(&heap_dump.options.path));
+        }
+        Err(error) => {
+            heap_dump.status = HeapDumpStatus::Failed;
+            heap_dump.error = Some(error);
         }
-        Err(e) => Err(e.to_string()),
     }
+    update_heap_dump(app, pid, &heap_dump);
 }
 
-// Intro to thread dumps: https://dzone.com/articles/how-to-read-a-thread-dump
+fn file_size(path: &str) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|m| m.len())
+}
+
+fn update_heap_dump(app: &AppHandle, pid: &str, heap_dump: &HeapDump) {
+    let update = update_session(pid, |session| session.update_heap_dump(heap_dump.clone()));
+    if update.is_some() {
+        emit(app, "heap-dump", pid, heap_dump.clone());
+    }
+}
+
+/// Returns the heap dumps taken from the monitored process.
 #[tauri::command]
-fn get_threads(pid: &str) -> Result<Threads, String> {
-    let time = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
-    match jcmd().arg(pid).arg("Thread.print").arg("-e").output() {
-        Ok(o) => {
-            let output = String::from_utf8_lossy(o.stdout.as_slice()).to_string();
-            let blocks: Vec<&str> = output.split("\n\n").collect();
-
-            let mut new_thread_count_application = 0;
-            let mut new_thread_count_jvm = 0;
-            let mut runnable_thread_count_application = 0;
-            let mut runnable_thread_count_jvm = 0;
-            let mut timed_waiting_thread_count_application = 0;
-            let mut waiting_thread_count_application = 0;
-            let mut waiting_thread_count_jvm = 0;
-            let mut blocked_thread_count_application = 0;
-            let mut blocked_thread_count_jvm = 0;
-
-            let mut application_threads = Vec::new();
-            let mut jvm_threads = Vec::new();
-            for block in blocks {
-                let rows: Vec<&str> = block.split('\n').collect();
-                let row1 = rows.first();
-                if let Some(first_row) = row1 {
-                    if let Some(first_row_without_first_quote) = first_row.strip_prefix('\"') {
-                        let title_quote_indexes = first_row
-                            .chars()
-                            .enumerate()
-                            .filter(|(_, c)| *c == '\"')
-                            .map(|(i, _)| i)
-                            .collect::<Vec<_>>();
-
-                        let thread_name_end_quote = title_quote_indexes.last().unwrap();
-                        let name = first_row_without_first_quote
-                            .chars()
-                            .take(thread_name_end_quote - 1)
-                            .collect::<String>();
-                        let rest = first_row[thread_name_end_quote + 2..]
-                            .chars()
-                            .take(first_row.len() - thread_name_end_quote + 2)
-                            .collect::<String>();
-
-                        let mut status = "".to_string();
-                        if rows.len() > 1 {
-                            let row2 = rows[1];
-                            let thread_state_input = row2.replace("java.lang.Thread.State:", "");
-                            let thread_state_parts: Vec<&str> =
-                                thread_state_input.split(' ').collect();
-                            let thread_state_parts: Vec<&str> = thread_state_parts
-                                .into_iter()
-                                .filter(|p| p != &"")
-                                .collect();
-                            status = thread_state_parts.join(" ");
-                        }
-
-                        if rest.contains('#') {
-                            let elements: Vec<&str> = rest.split(' ').collect();
-                            let mut id = 0;
-                            let mut daemon = false;
-                            let mut prio = 0;
-                            let mut os_thread_prio = 0;
-                            let mut cpu = 0.0;
-                            let mut elapsed = 0.0;
-                            let mut allocated = "".to_string();
-                            let mut defined_classes = 0;
-                            let mut tid = "".to_string();
-                            let mut nid = "".to_string();
-                            //let mut status: Vec<&str> = Vec::new();
-                            let mut last_known_java_stack_pointer = "".to_string();
-                            for e in elements {
-                                if e.starts_with('#') {
-                                    id = e.replace('#', "").parse::<u32>().unwrap();
-                                } else if e == "daemon" {
-                                    daemon = true;
-                                } else if e.starts_with("prio=") {
-                                    prio = e.replace("prio=", "").parse::<u16>().unwrap();
-                                } else if e.starts_with("cpu=") {
-                                    cpu = calculate_thread_time(e, "cpu=")
-                                } else if e.starts_with("elapsed=") {
-                                    elapsed = calculate_thread_time(e, "elapsed=")
-                                } else if e.starts_with("allocated=") {
-                                    allocated = e.replace("allocated=", "");
-                                } else if e.starts_with("defined_classes=") {
-                                    defined_classes =
-                                        e.replace("defined_classes=", "").parse::<u16>().unwrap();
-                                } else if e.starts_with("os_prio=") {
-                                    os_thread_prio =
-                                        e.replace("os_prio=", "").parse::<u16>().unwrap();
-                                } else if e.starts_with("tid=") {
-                                    tid = e.replace("tid=", "");
-                                } else if e.starts_with("nid=") {
-                                    nid = e.replace("nid=", "");
-                                } else if !nid.is_empty() && !e.starts_with('[') {
-                                    //status.push(e);
-                                } else if e.starts_with('[') {
-                                    last_known_java_stack_pointer = e.replace(['[', ']'], "");
-                                }
-                            }
-
-                            cpu = update_thread_cache(cpu, name.clone(), false);
-
-                            let thread = ApplicationThread {
-                                name,
-                                id,
-                                daemon,
-                                prio,
-                                os_thread_prio,
-                                cpu,
-                                elapsed,
-                                allocated,
-                                defined_classes,
-                                thread_id: tid.to_string(),
-                                os_thread_id: nid.to_string(),
-                                status: status.clone(), //status.join(" ").to_string(),
-                                last_known_java_stack_pointer: last_known_java_stack_pointer
-                                    .to_string(),
-                            };
-                            application_threads.push(thread);
-
-                            // The java.lang.Thread class contains a static State enum
-                            if status.starts_with("NEW") {
-                                new_thread_count_application += 1;
-                            } else if status.starts_with("RUNNABLE") {
-                                runnable_thread_count_application += 1;
-                            } else if status.starts_with("TIMED_WAITING") {
-                                timed_waiting_thread_count_application += 1;
-                            } else if status.starts_with("WAITING") {
-                                waiting_thread_count_application += 1;
-                            } else if status.starts_with("BLOCKED") {
-                                blocked_thread_count_application += 1;
-                            }
-                        } else {
-                            let elements: Vec<&str> = rest.split(' ').collect();
-                            let mut os_thread_prio = 0;
-                            let mut cpu = 0.0;
-                            let mut elapsed = 0.0;
-                            let mut tid = "".to_string();
-                            let mut nid = "".to_string();
-                            let mut status: Vec<&str> = Vec::new();
-                            for e in elements {
-                                if e.starts_with("os_prio=") {
-                                    os_thread_prio =
-                                        e.replace("os_prio=", "").parse::<u16>().unwrap();
-                                } else if e.starts_with("cpu=") {
-                                    cpu = calculate_thread_time(e, "cpu=");
-                                } else if e.starts_with("elapsed=") {
-                                    elapsed = calculate_thread_time(e, "elapsed=")
-                                } else if e.starts_with("tid=") {
-                                    tid = e.replace("tid=", "");
-                                } else if e.starts_with("nid=") {
-                                    nid = e.replace("nid=", "");
-                                } else if !nid.is_empty() && !e.starts_with('[') {
-                                    status.push(e);
-                                }
-                            }
-
-                            cpu = update_thread_cache(cpu, name.clone(), true);
-                            let status_name = status.join(" ").to_string();
-
-                            let thread = JvmThread {
-                                name,
-                                os_thread_prio,
-                                cpu,
-                                elapsed,
-                                thread_id: tid,
-                                os_thread_id: nid,
-                                status: status_name.clone(),
-                            };
-                            jvm_threads.push(thread);
-
-                            // The java.lang.Thread class contains a static State enum
-                            if status_name.starts_with("new") {
-                                new_thread_count_jvm += 1;
-                            } else if status_name.starts_with("runnable") {
-                                runnable_thread_count_jvm += 1;
-                            } else if status_name.starts_with("waiting on condition") {
-                                // There's no separation of thread state waiting and timed waiting for jvm threads
-                                waiting_thread_count_jvm += 1;
-                            } else if status_name.starts_with("blocked") {
-                                blocked_thread_count_jvm += 1;
-                            }
-                        }
-                    }
-                }
-            }
+fn get_heap_dumps(pid: &str) -> Vec<HeapDump> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions
+        .get(pid)
+        .map(|s| s.heap_dumps.clone())
+        .unwrap_or_default()
+}
 
-            let thread_count_application = ThreadCountMetricValue {
-                time,
-                new_count: new_thread_count_application,
-                runnable_count: runnable_thread_count_application,
-                waiting_count: waiting_thread_count_application,
-                timed_waiting_count: timed_waiting_thread_count_application,
-                blocked_count: blocked_thread_count_application,
-            };
+/// Starts profiling the monitored process in the background by taking thread dumps at the
+/// interval of the options. The progress and the result are emitted as `profile` events. Only one
+/// profile runs at a time.
+#[tauri::command]
+fn start_profiling(
+    app: AppHandle,
+    pid: String,
+    options: ProfileOptions,
+) -> Result<Profile, JcmdError> {
+    let invalid = |message: String| JcmdError::CommandFailed {
+        command: "Thread.print".to_string(),
+        message,
+    };
+    if options.interval < MIN_PROFILE_INTERVAL {
+        return Err(invalid(format!(
+            "The interval must be at least {} ms",
+            MIN_PROFILE_INTERVAL
+        )));
+    }
+    if !(1..=MAX_PROFILE_DURATION).contains(&options.duration) {
+        return Err(invalid(format!(
+            "The duration must be between 1 and {} seconds",
+            MAX_PROFILE_DURATION
+        )));
+    }
+    let profile = update_session(&pid, |session| {
+        if session
+            .profile
+            .as_ref()
+            .is_some_and(|p| p.status == ProfileStatus::Running)
+        {
+            return Err(invalid("Another profile is running".to_string()));
+        }
+        let profile = Profile::new(options, collector::now());
+        session.profile = Some(profile.clone());
+        Ok(profile)
+    })
+    .unwrap_or_else(|| Err(invalid("The process is not monitored".to_string())))?;
+
+    let started = profile.started;
+    std::thread::Builder::new()
+        .name(format!("profile-{}", pid))
+        .spawn(move || run_profile(&app, &pid, started, options))
+        .map_err(|e| invalid(e.to_string()))?;
+    Ok(profile)
+}
 
-            let mut c = CACHE.lock().unwrap();
-            c.thread_count_metrics_application
-                .values
-                .push(thread_count_application);
-
-            let thread_count_jvm = ThreadCountMetricValue {
-                time,
-                new_count: new_thread_count_jvm,
-                runnable_count: runnable_thread_count_jvm,
-                waiting_count: waiting_thread_count_jvm,
-                timed_waiting_count: 0,
-                blocked_count: blocked_thread_count_jvm,
-            };
-            c.thread_count_metrics_jvm.values.push(thread_count_jvm);
-
-            Ok(Threads {
-                application_threads,
-                jvm_threads,
-                thread_count_application: c.thread_count_metrics_application.clone(),
-                thread_count_jvm: c.thread_count_metrics_jvm.clone(),
-            })
+fn run_profile(app: &AppHandle, pid: &str, started: u128, options: ProfileOptions) {
+    let interval = Duration::from_millis(options.interval);
+    let start = Instant::now();
+    let ticks = options.duration * 1000 / options.interval;
+    let mut tick = 0;
+    let mut emitted = Instant::now();
+    let status = loop {
+        if tick >= ticks {
+            break ProfileStatus::Completed;
+        }
+        let sample = collector::collect_threads(pid, false);
+        let next_tick = profiler::next_tick(start.elapsed(), interval).min(ticks);
+        let update = update_session(pid, |session| {
+            let profile = session
+                .profile
+                .as_mut()
+                .filter(|p| p.started == started && p.status == ProfileStatus::Running)?;
+            match &sample {
+                Ok(sample) => profile.add(&sample.thread_dump),
+                Err(e) => profile.error = Some(e.clone()),
+            }
+            profile.skipped += next_tick - tick - 1;
+            Some(profile.clone())
+        });
+        tick = next_tick;
+        let Some(Some(profile)) = update else {
+            // Stopped or not monitored anymore
+            return;
+        };
+        if sample.is_err() {
+            break ProfileStatus::Failed;
+        }
+        if emitted.elapsed() >= PROFILE_PROGRESS_INTERVAL {
+            emit(app, "profile", pid, profile);
+            emitted = Instant::now();
         }
-        Err(e) => Err(e.to_string()),
+        let next = start + interval * u32::try_from(tick).unwrap_or(u32::MAX);
+        std::thread::sleep(next.saturating_duration_since(Instant::now()));
+    };
+    finish_profile(app, pid, started, status);
+}
+
+/// Finishes the profile started at `started` if it is still running and emits it.
+fn finish_profile(
+    app: &AppHandle,
+    pid: &str,
+    started: u128,
+    status: ProfileStatus,
+) -> Option<Profile> {
+    let update = update_session(pid, |session| {
+        let profile = session
+            .profile
+            .as_mut()
+            .filter(|p| p.started == started && p.status == ProfileStatus::Running)?;
+        profile.finish(collector::now(), status);
+        Some(profile.clone())
+    });
+    let profile = update.flatten();
+    if let Some(profile) = &profile {
+        emit(app, "profile", pid, profile.clone());
     }
+    profile
+}
+
+/// Stops the running profile of the monitored process. The stacks sampled so far are kept.
+#[tauri::command]
+fn stop_profiling(app: AppHandle, pid: &str) -> Option<Profile> {
+    let started = update_session(pid, |session| session.profile.as_ref().map(|p| p.started))??;
+    finish_profile(&app, pid, started, ProfileStatus::Stopped)
 }
 
-fn calculate_thread_time(e: &str, column: &str) -> f32 {
-    let factor = if e.contains("ms") {
-        1
-    } else if e.contains('m') {
-        60 * 1000
-    } else if e.contains('s') {
-        1000
-    } else {
-        1
+/// Returns the latest profile of the monitored process with its stacks.
+#[tauri::command]
+fn get_profile(pid: &str) -> Option<ProfileResult> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions
+        .get(pid)
+        .and_then(|s| s.profile.as_ref())
+        .map(|profile| ProfileResult {
+            stacks: profile.stacks(),
+            profile: profile.clone(),
+        })
+}
+
+/// Writes the stacks of the latest profile of the monitored process in the collapsed format
+/// (e.g. for `flamegraph.pl`) to the given file.
+#[tauri::command]
+fn export_profile(pid: &str, path: &str) -> Result<(), SessionError> {
+    let collapsed = {
+        let sessions = SESSIONS.lock().unwrap();
+        match sessions.get(pid).and_then(|s| s.profile.as_ref()) {
+            Some(profile) => profile.collapsed(),
+            None => return Err(SessionError::NotFound),
+        }
     };
-    e.replace(column, "")
-        .replace("ms", "")
-        .replace('s', "")
-        // On linux it seem to be required to replace the comma
-        // with a dot to be able to parse the number
-        .replace(',', ".")
-        .parse::<f32>()
-        .unwrap()
-        * factor as f32
-}
-
-fn parse_reserved_committed(parts: Vec<&str>) -> (Option<u64>, Option<u64>) {
-    let mut reserved = None;
-    let mut committed = None;
-    for part in parts {
-        if part.starts_with("reserved=") {
-            reserved = Some(
-                part.replace("reserved=", "")
-                    .replace(',', "")
-                    .trim()
-                    .parse::<u64>()
-                    .unwrap(),
-            )
-        } else if part.starts_with("committed=") {
-            committed = Some(
-                part.replace("committed=", "")
-                    .replace(',', "")
-                    .trim()
-                    .parse::<u64>()
-                    .unwrap(),
-            )
+    std::fs::write(path, collapsed).map_err(|e| SessionError::Io {
+        path: path.to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// Starts a flight recording of the monitored process.
+#[tauri::command]
+fn start_jfr_recording(
+    app: AppHandle,
+    pid: &str,
+    options: JfrOptions,
+) -> Result<FlightRecordings, JcmdError> {
+    let invalid = |message: &str| JcmdError::CommandFailed {
+        command: "JFR.start".to_string(),
+        message: message.to_string(),
+    };
+    // jcmd passes the options as a single line, which the jvm splits at spaces
+    let values = [
+        &options.name,
+        &options.settings,
+        &options.duration,
+        &options.max_size,
+        &options.filename,
+    ];
+    if values
+        .into_iter()
+        .flatten()
+        .any(|v| v.is_empty() || v.contains(char::is_whitespace))
+    {
+        return Err(invalid("The options must not be empty or contain spaces"));
+    }
+    if options
+        .filename
+        .as_ref()
+        .is_some_and(|f| !Path::new(f).is_absolute())
+    {
+        return Err(invalid("The path of the recording must be absolute"));
+    }
+    if update_session(pid, |_| ()).is_none() {
+        return Err(invalid("The process is not monitored"));
+    }
+    let id = collector::jfr_start(pid, &options)?;
+    let sample = collector::jfr_check(pid)?;
+    update_jfr_recordings(&app, pid, sample, |session| {
+        if let Some(recording) = session
+            .jfr_recordings
+            .iter_mut()
+            .find(|r| r.recording.id == id)
+        {
+            recording.filename = options.filename.clone();
         }
+    })
+    .ok_or_else(|| invalid("The process is not monitored"))
+}
+
+/// Writes the data recorded so far to the file.
+#[tauri::command]
+fn dump_jfr_recording(
+    app: AppHandle,
+    pid: &str,
+    id: u32,
+    filename: &str,
+) -> Result<FlightRecordings, JcmdError> {
+    let invalid = |message: &str| JcmdError::CommandFailed {
+        command: "JFR.dump".to_string(),
+        message: message.to_string(),
+    };
+    if !Path::new(filename).is_absolute() || filename.contains(char::is_whitespace) {
+        return Err(invalid(
+            "The path of the recording must be absolute and must not contain spaces",
+        ));
     }
-    (reserved, committed)
+    let dump = JfrDump {
+        time: collector::now(),
+        filename: collector::jfr_dump(pid, id, filename)?,
+    };
+    let sample = collector::jfr_check(pid)?;
+    update_jfr_recordings(&app, pid, sample, |session| session.add_jfr_dump(id, dump))
+        .ok_or_else(|| invalid("The process is not monitored"))
+}
+
+/// Stops the flight recording. Its data is written to the file given at the start, otherwise it
+/// is discarded.
+#[tauri::command]
+fn stop_jfr_recording(app: AppHandle, pid: &str, id: u32) -> Result<FlightRecordings, JcmdError> {
+    collector::jfr_stop(pid, id)?;
+    check_jfr_recordings(app, pid)
+}
+
+/// Lists the flight recordings of the monitored process with `JFR.check`. Once a recording was
+/// seen, the recordings are polled in the background.
+#[tauri::command]
+fn check_jfr_recordings(app: AppHandle, pid: &str) -> Result<FlightRecordings, JcmdError> {
+    let sample = collector::jfr_check(pid)?;
+    update_jfr_recordings(&app, pid, sample, |_| {}).ok_or_else(|| JcmdError::CommandFailed {
+        command: "JFR.check".to_string(),
+        message: "The process is not monitored".to_string(),
+    })
+}
+
+/// Returns the flight recordings seen while monitoring the process.
+#[tauri::command]
+fn get_jfr_recordings(pid: &str) -> Vec<FlightRecording> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions
+        .get(pid)
+        .map(|s| s.jfr_recordings.clone())
+        .unwrap_or_default()
+}
+
+/// Enables (or with `None` disables) collecting class histograms of the monitored process.
+#[tauri::command]
+fn set_class_histogram(pid: &str, options: Option<ClassHistogramOptions>) {
+    update_session(pid, |session| session.class_histogram_options = options);
 }
 
-fn parse_name_reserved_committed(parts: Vec<&str>) -> (Option<String>, Option<u64>, Option<u64>) {
-    let mut name_buffer = Vec::new();
-    for p in &parts {
-        if p.starts_with("reserved") {
-            break;
-        } else if !p.is_empty() {
-            name_buffer.push(p.trim().to_string());
+/// Returns the class histograms collected from the monitored process and the series of the
+/// classes with the largest growth.
+#[tauri::command]
+fn get_class_histograms(pid: &str, max_points: Option<usize>) -> Option<ClassHistograms> {
+    let selection = Selection::Downsampled(max_points.unwrap_or(HISTORY_POINTS));
+    let sessions = SESSIONS.lock().unwrap();
+    sessions.get(pid).map(|s| ClassHistograms {
+        options: s.class_histogram_options,
+        samples: s
+            .class_histograms
+            .iter()
+            .map(ClassHistogramTotal::new)
+            .collect(),
+        classes: class_histogram_metrics(&s.metrics, selection),
+    })
+}
+
+/// Compares the class histograms collected at `from` and `to` and returns the `top` classes with
+/// the largest growth.
+#[tauri::command]
+fn diff_class_histograms(
+    pid: &str,
+    from: u64,
+    to: u64,
+    top: Option<usize>,
+) -> Result<Vec<ClassHistogramDiff>, SessionError> {
+    let sessions = SESSIONS.lock().unwrap();
+    let session = sessions.get(pid).ok_or(SessionError::NotFound)?;
+    match (
+        session.class_histogram(u128::from(from)),
+        session.class_histogram(u128::from(to)),
+    ) {
+        (Some(from), Some(to)) => {
+            let mut diff = to.diff(from);
+            diff.truncate(top.unwrap_or(CLASS_HISTOGRAM_DIFF_TOP));
+            Ok(diff)
         }
+        _ => Err(SessionError::NotFound),
     }
-    let name = Some(name_buffer.join(" "));
-    let (reserved, committed) = parse_reserved_committed(parts);
-    (name, reserved, committed)
-}
-
-fn parse_memory_from_heap_info(row: &str, memory_type: &str, reversed: bool) -> Option<u64> {
-    let parts: Vec<&str> = row.split(' ').collect();
-    for (i, p) in parts.iter().enumerate() {
-        if p == &memory_type {
-            let size_index = if reversed { i - 1 } else { i + 1 };
-            let size_string = parts.get(size_index).unwrap();
-            let size_unit_factor = if size_string.contains('K') {
-                1024
-            } else if size_string.contains('M') {
-                1024 * 1024
-            } else if size_string.contains('G') {
-                1024 * 1024 * 1024
-            } else {
-                1
-            };
+}
+
+fn class_histogram_metrics(
+    c: &MetricsCache,
+    selection: Selection,
+) -> Vec<NamedMetric<ClassHistogramMetricValue>> {
+    c.class_histogram_metrics
+        .values()
+        .map(|m| m.select(selection))
+        .collect()
+}
 
-            let size = size_string
-                .replace(['K', 'M', 'G', ','], "")
-                .trim()
-                .parse::<u64>()
-                .unwrap();
-            return Some(size * size_unit_factor);
+/// Collects the native memory details of the process with the call sites of the malloc
+/// allocations and virtual memory regions. The output can be large, so it is only collected on
+/// request and not kept in the session.
+#[tauri::command]
+fn get_nmt_detail(pid: &str) -> Result<NmtDetailSample, JcmdError> {
+    collector::collect_nmt_detail(pid)
+}
+
+/// Collects the flags of the process with `VM.flags -all`. Collected on request and not kept in
+/// the session.
+#[tauri::command]
+fn get_vm_flags(pid: &str) -> Result<VmFlagsSample, JcmdError> {
+    collector::collect_vm_flags(pid)
+}
+
+/// Changes the value of a manageable flag of the monitored process with `VM.set_flag`. The flag is
+/// read again afterwards to verify the change. All attempts are logged in the session.
+#[tauri::command]
+fn set_vm_flag(pid: &str, name: &str, value: &str) -> Result<VmFlagChange, JcmdError> {
+    let invalid = |message: String| JcmdError::CommandFailed {
+        command: "VM.set_flag".to_string(),
+        message,
+    };
+    // jcmd passes the arguments as a single line, which the jvm splits at spaces
+    if value.is_empty() || value.contains(char::is_whitespace) {
+        return Err(invalid(
+            "The value must not be empty or contain spaces".to_string(),
+        ));
+    }
+    if update_session(pid, |_| ()).is_none() {
+        return Err(invalid("The process is not monitored".to_string()));
+    }
+    let find = |flags: VmFlagsSample| flags.flags.into_iter().find(|f| f.name == name);
+    let Some(before) = find(collector::collect_vm_flags(pid)?) else {
+        return Err(invalid(format!("Unknown flag {}", name)));
+    };
+    if !before.is_manageable() {
+        return Err(invalid(format!("{} is not manageable", name)));
+    }
+
+    let mut change = VmFlagChange {
+        time: collector::now(),
+        name: name.to_string(),
+        old_value: before.value,
+        value: value.to_string(),
+        flag: None,
+        verified: false,
+        error: None,
+    };
+    match collector::set_vm_flag(pid, name, value).and_then(|_| collector::collect_vm_flags(pid)) {
+        Ok(flags) => {
+            change.flag = find(flags);
+            // Boolean flags can be set with 1 and 0 as well, but are printed as true and false
+            let expected = match value {
+                "1" if before.flag_type == "bool" => "true",
+                "0" if before.flag_type == "bool" => "false",
+                value => value,
+            };
+            change.verified = change.flag.as_ref().is_some_and(|f| f.value == expected);
         }
+        Err(error) => change.error = Some(error),
+    }
+    update_session(pid, |session| session.vm_flag_changes.push(change.clone()));
+    match change.error {
+        Some(error) => Err(error),
+        None => Ok(change),
     }
-    None
 }
 
-fn update_thread_cache(cpu: f32, name: String, jvm_tread: bool) -> f32 {
-    let mut thread_cpu = 0.0f32;
-    let mut mutex_guard = THREAD_CACHE.lock().unwrap();
-    let existing_thread = mutex_guard.get_mut(&name);
-    if let Some(thread_entry) = existing_thread {
-        let existing_cpu = thread_entry.cpu;
-        thread_cpu = (cpu - existing_cpu).abs();
-        thread_entry.cpu = cpu;
-    } else {
-        mutex_guard.insert(
-            name.clone(),
-            ThreadCacheEntry {
-                name: name.clone(),
-                jvm_thread: jvm_tread,
-                cpu,
-            },
-        );
+/// Returns the changes of manageable flags made while monitoring the process.
+#[tauri::command]
+fn get_vm_flag_changes(pid: &str) -> Vec<VmFlagChange> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions
+        .get(pid)
+        .map(|s| s.vm_flag_changes.clone())
+        .unwrap_or_default()
+}
+
+/// Collects the system properties of the process. Collected on request and not kept in the
+/// session.
+#[tauri::command]
+fn get_system_properties(pid: &str) -> Result<SystemPropertiesSample, JcmdError> {
+    collector::collect_system_properties(pid)
+}
+
+/// Saves the samples collected from the process to the given file.
+#[tauri::command]
+fn save_session(pid: &str, path: &str) -> Result<(), SessionError> {
+    match SESSIONS.lock().unwrap().get(pid) {
+        Some(session) => session.save(path),
+        None => Err(SessionError::NotFound),
     }
-    thread_cpu
 }
 
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct VmInformation {
-    vm_arguments: Option<VmArguments>,
-    vm_resources: Option<VmResources>,
+/// Opens a session file (or a recording of the `record` subcommand) for replay.
+#[tauri::command]
+fn open_session(path: &str) -> Result<SessionReplay, SessionError> {
+    let session = Session::open(path)?;
+    let replay = SessionReplay {
+        pid: session.pid.clone(),
+        started: session.started,
+        thread_snapshots: session.thread_samples.iter().map(|t| t.time).collect(),
+        vm_information: session.vm_information.clone(),
+        metrics: JvmMetrics::new(&session.metrics, Vec::new(), Selection::All),
+    };
+    *REPLAY.lock().unwrap() = Some(session);
+    Ok(replay)
 }
 
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct VmArguments {
-    jvm_args: Option<String>,
-    java_command: Option<String>,
+/// Returns the thread snapshot with the given index of the replayed session.
+#[tauri::command]
+fn get_replay_threads(index: usize) -> Result<Threads, SessionError> {
+    let replay = REPLAY.lock().unwrap();
+    match replay.as_ref() {
+        Some(session) => match session.thread_samples.get(index) {
+            Some(sample) => Ok(Threads::new(
+                &session.metrics,
+                sample.clone(),
+                Selection::All,
+            )),
+            None => Err(SessionError::NotFound),
+        },
+        None => Err(SessionError::NotFound),
+    }
 }
 
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct VmResources {
-    cpus: Option<String>,
-    memory: Option<String>,
-    heap_size_min: Option<String>,
-    heap_size_init: Option<String>,
-    heap_size_max: Option<String>,
+/// Returns the threads seen in the thread dumps of the replayed session, the alive ones first.
+#[tauri::command]
+fn get_replay_thread_history() -> Result<Vec<ThreadHistory>, SessionError> {
+    let replay = REPLAY.lock().unwrap();
+    match replay.as_ref() {
+        Some(session) => Ok(session.thread_histories.threads()),
+        None => Err(SessionError::NotFound),
+    }
+}
+
+/// Returns the monitors the threads of the replayed session were blocked on.
+#[tauri::command]
+fn get_replay_lock_contention() -> Result<LockContentionReport, SessionError> {
+    let replay = REPLAY.lock().unwrap();
+    match replay.as_ref() {
+        Some(session) => Ok(LockContentionReport {
+            thread_dump_locks: false,
+            monitors: session.lock_contention.monitors(),
+        }),
+        None => Err(SessionError::NotFound),
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct JvmMetrics {
     total_memory: NamedMetric<TotalMemoryMetricValue>,
+    container_memory: NamedMetric<ContainerMemoryMetricValue>,
     class: NamedMetric<ClassMemoryMetricValue>,
     heap: NamedMetric<HeapMemoryMetricValue>,
     metaspace: NamedMetric<MetaspaceMemoryMetricValue>,
     thread: NamedMetric<ThreadMemoryMetricValue>,
     other: Vec<GenericMemoryMetric>,
+    /// Breakdown of all native memory categories
+    native_memory: Vec<NamedMetric<NativeMemoryMetricValue>>,
+    warnings: Vec<JcmdError>,
 }
 
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct NamedMetric<T> {
-    name: String,
-    values: Vec<T>,
-}
-
-impl<T> NamedMetric<T> {
-    fn new(name: String) -> NamedMetric<T> {
-        NamedMetric {
-            name,
-            values: Vec::new(),
+impl JvmMetrics {
+    fn new(c: &MetricsCache, warnings: Vec<JcmdError>, selection: Selection) -> JvmMetrics {
+        JvmMetrics {
+            total_memory: c.total_memory.select(selection),
+            container_memory: c.container_memory.select(selection),
+            class: c.class_metrics.select(selection),
+            heap: c.heap_metrics.select(selection),
+            metaspace: c.metaspace_metrics.select(selection),
+            thread: c.thread_metrics.select(selection),
+            other: c
+                .other_metrics
+                .values()
+                .map(|m| m.select(selection))
+                .collect(),
+            native_memory: c
+                .native_memory_metrics
+                .values()
+                .map(|m| m.select(selection))
+                .collect(),
+            warnings,
         }
     }
 }
 
 #[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GenericMemoryMetric {
-    name: String,
-    values: Vec<GenericMemoryMetricValue>,
+pub struct JvmProcesses {
+    processes: Vec<JvmProcessRef>,
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct GenericMemoryMetricValue {
+pub struct Threads {
     time: u128,
-    reserved: Option<u64>,
-    committed: Option<u64>,
+    application_threads: Vec<ApplicationThread>,
+    jvm_threads: Vec<JvmThread>,
+    /// Deadlocks and long chains of threads waiting for locks
+    lock_chains: Vec<LockChain>,
+    thread_count_application: NamedMetric<ThreadCountMetricValue>,
+    thread_count_jvm: NamedMetric<ThreadCountMetricValue>,
+    thread_churn: NamedMetric<ThreadChurnMetricValue>,
+    warnings: Vec<JcmdError>,
 }
 
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ClassMemoryMetricValue {
-    time: u128,
-    class_count: u32,
-    reserved: Option<u64>,
-    committed: Option<u64>,
-    used: Option<u64>,
+impl Threads {
+    fn new(c: &MetricsCache, sample: ThreadsSample, selection: Selection) -> Threads {
+        Threads {
+            time: sample.time,
+            lock_chains: sample.thread_dump.lock_chains(LOCK_CHAIN_MIN_LENGTH),
+            application_threads: sample.thread_dump.application_threads,
+            jvm_threads: sample.thread_dump.jvm_threads,
+            thread_count_application: c.thread_count_metrics_application.select(selection),
+            thread_count_jvm: c.thread_count_metrics_jvm.select(selection),
+            thread_churn: c.thread_churn.select(selection),
+            warnings: sample.warnings,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct MetaspaceMemoryMetricValue {
-    time: u128,
-    reserved: Option<u64>,
-    committed: Option<u64>,
-    used: Option<u64>,
+pub struct LockContentionReport {
+    thread_dump_locks: bool,
+    monitors: Vec<MonitorContention>,
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct HeapMemoryMetricValue {
-    time: u128,
-    reserved: Option<u64>,
-    committed: Option<u64>,
-    used: Option<u64>,
+pub struct ProfileResult {
+    profile: Profile,
+    stacks: Vec<CollapsedStack>,
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ThreadCountMetricValue {
-    time: u128,
-    new_count: u32,
-    runnable_count: u32,
-    waiting_count: u32,
-    timed_waiting_count: u32,
-    blocked_count: u32,
+pub struct History {
+    vm_information: Option<VmInformation>,
+    metrics: JvmMetrics,
+    threads: Option<Threads>,
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ThreadMemoryMetricValue {
-    time: u128,
-    thread_count: u32,
-    reserved: Option<u64>,
-    committed: Option<u64>,
+pub struct NmtDiffs {
+    /// Time the baseline was taken
+    baseline: Option<u128>,
+    samples: Vec<NmtDiffSample>,
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct TotalMemoryMetricValue {
-    time: u128,
-    reserved: Option<u64>,
-    committed: Option<u64>,
+pub struct ClassHistograms {
+    /// `None` while collecting class histograms is disabled
+    options: Option<ClassHistogramOptions>,
+    samples: Vec<ClassHistogramTotal>,
+    classes: Vec<NamedMetric<ClassHistogramMetricValue>>,
 }
 
+/// Time and totals of a class histogram.
 #[derive(Clone, Debug, Serialize)]
-pub struct JvmProcesses {
-    processes: Vec<JvmProcessRef>,
-}
-
-#[derive(Clone, Debug, Serialize)]
-pub struct JvmProcessRef {
-    id: String,
-    name: String,
-    path: Option<String>,
+#[serde(rename_all = "camelCase")]
+pub struct ClassHistogramTotal {
+    time: u128,
+    total_instances: Option<u64>,
+    total_bytes: Option<u64>,
 }
 
-struct MetricsCache {
-    total_memory: NamedMetric<TotalMemoryMetricValue>,
-    class_metrics: NamedMetric<ClassMemoryMetricValue>,
-    heap_metrics: NamedMetric<HeapMemoryMetricValue>,
-    metaspace_metrics: NamedMetric<MetaspaceMemoryMetricValue>,
-    thread_metrics: NamedMetric<ThreadMemoryMetricValue>,
-    thread_count_metrics_application: NamedMetric<ThreadCountMetricValue>,
-    thread_count_metrics_jvm: NamedMetric<ThreadCountMetricValue>,
-    other_metrics: HashMap<String, GenericMemoryMetric>,
+impl ClassHistogramTotal {
+    fn new(sample: &ClassHistogramSample) -> ClassHistogramTotal {
+        ClassHistogramTotal {
+            time: sample.time,
+            total_instances: sample.histogram.total_instances,
+            total_bytes: sample.histogram.total_bytes,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ApplicationThread {
-    name: String,
-    id: u32,
-    daemon: bool,
-    prio: u16,
-    os_thread_prio: u16,
-    //https://www.linkedin.com/pulse/getting-java-thread-cpu-utilization-vishalendu-pandey
-    cpu: f32,
-    elapsed: f32,
-    allocated: String,
-    defined_classes: u16,
-    thread_id: String,
-    os_thread_id: String,
-    status: String,
-    last_known_java_stack_pointer: String,
+pub struct ClassHistogramUpdate {
+    #[serde(flatten)]
+    total: ClassHistogramTotal,
+    classes: Vec<NamedMetric<ClassHistogramMetricValue>>,
+    warnings: Vec<JcmdError>,
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct JvmThread {
-    name: String,
-    os_thread_prio: u16,
-    cpu: f32,
-    elapsed: f32,
-    thread_id: String,
-    os_thread_id: String,
-    status: String,
+pub struct FlightRecordings {
+    recordings: Vec<FlightRecording>,
+    warnings: Vec<JcmdError>,
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Threads {
-    application_threads: Vec<ApplicationThread>,
-    jvm_threads: Vec<JvmThread>,
-    thread_count_application: NamedMetric<ThreadCountMetricValue>,
-    thread_count_jvm: NamedMetric<ThreadCountMetricValue>,
-}
-
-#[derive(Clone, Debug, Serialize)]
-struct ThreadCacheEntry {
-    name: String,
-    jvm_thread: bool,
-    cpu: f32,
+pub struct SessionReplay {
+    pid: Option<String>,
+    started: u128,
+    /// Times of the recorded thread dumps
+    thread_snapshots: Vec<u128>,
+    vm_information: Option<VmInformation>,
+    metrics: JvmMetrics,
 }
@@ -0,0 +1,913 @@
+use std::collections::HashMap;
+
+use jcmd_parse::{NmtCategory, ThreadStateCounts};
+use serde::{Deserialize, Serialize};
+
+use crate::collector::{deserialize_time, ClassHistogramSample, MetricsSample, ThreadsSample};
+use crate::thread_history::ThreadChurn;
+
+/// Time series built from the collected samples of a process.
+pub(crate) struct MetricsCache {
+    pub(crate) total_memory: NamedMetric<TotalMemoryMetricValue>,
+    /// Limit and usage of the cgroup, only sampled if the cgroup files are readable
+    pub(crate) container_memory: NamedMetric<ContainerMemoryMetricValue>,
+    pub(crate) class_metrics: NamedMetric<ClassMemoryMetricValue>,
+    pub(crate) heap_metrics: NamedMetric<HeapMemoryMetricValue>,
+    pub(crate) metaspace_metrics: NamedMetric<MetaspaceMemoryMetricValue>,
+    pub(crate) thread_metrics: NamedMetric<ThreadMemoryMetricValue>,
+    pub(crate) thread_count_metrics_application: NamedMetric<ThreadCountMetricValue>,
+    pub(crate) thread_count_metrics_jvm: NamedMetric<ThreadCountMetricValue>,
+    /// Threads created and terminated within the minute before each thread dump
+    pub(crate) thread_churn: NamedMetric<ThreadChurnMetricValue>,
+    pub(crate) other_metrics: HashMap<String, GenericMemoryMetric>,
+    /// Malloc, mmap, arena and stack memory of the native memory categories keyed by category
+    pub(crate) native_memory_metrics: HashMap<String, NamedMetric<NativeMemoryMetricValue>>,
+    /// Instances and bytes of the classes tracked from the class histograms keyed by class name
+    pub(crate) class_histogram_metrics: HashMap<String, NamedMetric<ClassHistogramMetricValue>>,
+    retention: Retention,
+}
+
+impl MetricsCache {
+    pub(crate) fn new() -> MetricsCache {
+        let retention = Retention::default();
+        MetricsCache {
+            total_memory: NamedMetric::new("Total".to_string(), retention),
+            container_memory: NamedMetric::new("Container".to_string(), retention),
+            class_metrics: NamedMetric::new("Class".to_string(), retention),
+            heap_metrics: NamedMetric::new("Heap".to_string(), retention),
+            metaspace_metrics: NamedMetric::new("Metaspace".to_string(), retention),
+            thread_metrics: NamedMetric::new("Thread".to_string(), retention),
+            thread_count_metrics_application: NamedMetric::new(
+                "ThreadCountApplication".to_string(),
+                retention,
+            ),
+            thread_count_metrics_jvm: NamedMetric::new("ThreadCountJvm".to_string(), retention),
+            thread_churn: NamedMetric::new("ThreadChurn".to_string(), retention),
+            other_metrics: HashMap::new(),
+            native_memory_metrics: HashMap::new(),
+            class_histogram_metrics: HashMap::new(),
+            retention,
+        }
+    }
+
+    /// Returns the time before which all values are rolled up once a value of the given time was
+    /// added, i.e. the start of the bucket the full resolution period starts in.
+    pub(crate) fn rolled_up_before(&self, time: u128) -> u128 {
+        let cutoff = time.saturating_sub(self.retention.full_resolution);
+        cutoff - cutoff % self.retention.bucket
+    }
+
+    /// Returns the rollups of all series, e.g. to save them to a session file.
+    pub(crate) fn rollups(&self) -> MetricsRollups {
+        fn rollups<T: Clone>(metrics: &HashMap<String, NamedMetric<T>>) -> RollupsByName<T> {
+            metrics
+                .iter()
+                .filter(|(_, m)| !m.rollups.is_empty())
+                .map(|(name, m)| (name.clone(), m.rollups.clone()))
+                .collect()
+        }
+        MetricsRollups {
+            total_memory: self.total_memory.rollups.clone(),
+            container_memory: self.container_memory.rollups.clone(),
+            class_metrics: self.class_metrics.rollups.clone(),
+            heap_metrics: self.heap_metrics.rollups.clone(),
+            metaspace_metrics: self.metaspace_metrics.rollups.clone(),
+            thread_metrics: self.thread_metrics.rollups.clone(),
+            thread_count_metrics_application: self.thread_count_metrics_application.rollups.clone(),
+            thread_count_metrics_jvm: self.thread_count_metrics_jvm.rollups.clone(),
+            thread_churn: self.thread_churn.rollups.clone(),
+            other_metrics: rollups(&self.other_metrics),
+            native_memory_metrics: rollups(&self.native_memory_metrics),
+            class_histogram_metrics: rollups(&self.class_histogram_metrics),
+        }
+    }
+
+    /// Restores the rollups of a session file. The rollups are older than the values added
+    /// afterwards.
+    pub(crate) fn restore_rollups(&mut self, rollups: MetricsRollups) {
+        fn restore<T: SeriesValue>(
+            metrics: &mut HashMap<String, NamedMetric<T>>,
+            rollups: RollupsByName<T>,
+            retention: Retention,
+        ) {
+            for (name, rollups) in rollups {
+                metrics
+                    .entry(name.clone())
+                    .or_insert_with(|| NamedMetric::new(name, retention))
+                    .restore(rollups);
+            }
+        }
+        self.total_memory.restore(rollups.total_memory);
+        self.container_memory.restore(rollups.container_memory);
+        self.class_metrics.restore(rollups.class_metrics);
+        self.heap_metrics.restore(rollups.heap_metrics);
+        self.metaspace_metrics.restore(rollups.metaspace_metrics);
+        self.thread_metrics.restore(rollups.thread_metrics);
+        self.thread_count_metrics_application
+            .restore(rollups.thread_count_metrics_application);
+        self.thread_count_metrics_jvm
+            .restore(rollups.thread_count_metrics_jvm);
+        self.thread_churn.restore(rollups.thread_churn);
+        let retention = self.retention;
+        restore(&mut self.other_metrics, rollups.other_metrics, retention);
+        restore(
+            &mut self.native_memory_metrics,
+            rollups.native_memory_metrics,
+            retention,
+        );
+        restore(
+            &mut self.class_histogram_metrics,
+            rollups.class_histogram_metrics,
+            retention,
+        );
+    }
+
+    pub(crate) fn add_metrics(&mut self, sample: &MetricsSample) {
+        let time = sample.time;
+        let heap_info = &sample.heap_info;
+        if let Some(total) = &sample.native_memory.total {
+            self.total_memory.push(TotalMemoryMetricValue {
+                time,
+                reserved: total.reserved,
+                committed: total.committed,
+            });
+        }
+        if let Some(cgroup) = &sample.cgroup_memory {
+            let committed = sample
+                .native_memory
+                .total
+                .as_ref()
+                .and_then(|t| t.committed);
+            self.container_memory.push(ContainerMemoryMetricValue {
+                time,
+                limit: cgroup.limit,
+                usage: cgroup.usage,
+                headroom: cgroup
+                    .limit
+                    .zip(committed)
+                    .map(|(limit, committed)| limit.saturating_sub(committed)),
+            });
+        }
+        for category in &sample.native_memory.categories {
+            match category.name.as_str() {
+                "Class" => self.class_metrics.push(ClassMemoryMetricValue {
+                    time,
+                    reserved: heap_info.class_space.reserved,
+                    committed: heap_info.class_space.committed,
+                    class_count: count(category.class_count),
+                    used: heap_info.class_space.used,
+                }),
+                "Thread" => self.thread_metrics.push(ThreadMemoryMetricValue {
+                    time,
+                    reserved: category.reserved,
+                    committed: category.committed,
+                    thread_count: count(category.thread_count),
+                }),
+                "Java Heap" => self.heap_metrics.push(HeapMemoryMetricValue {
+                    time,
+                    reserved: category.reserved,
+                    committed: category.committed,
+                    used: heap_info.used,
+                }),
+                "Metaspace" => self.metaspace_metrics.push(MetaspaceMemoryMetricValue {
+                    time,
+                    reserved: heap_info.metaspace.reserved,
+                    committed: heap_info.metaspace.committed,
+                    used: heap_info.metaspace.used,
+                }),
+                _ => {
+                    let metric = GenericMemoryMetricValue {
+                        time,
+                        reserved: category.reserved,
+                        committed: category.committed,
+                    };
+                    let retention = self.retention;
+                    self.other_metrics
+                        .entry(category.name.clone())
+                        .or_insert_with(|| NamedMetric::new(category.name.clone(), retention))
+                        .push(metric);
+                }
+            }
+
+            let retention = self.retention;
+            self.native_memory_metrics
+                .entry(category.name.clone())
+                .or_insert_with(|| NamedMetric::new(category.name.clone(), retention))
+                .push(NativeMemoryMetricValue::new(time, category));
+        }
+    }
+
+    /// Starts to track the given classes and adds the values of all tracked classes. Classes
+    /// missing in the histogram have no instances.
+    pub(crate) fn add_class_histogram(&mut self, sample: &ClassHistogramSample, track: &[&str]) {
+        let retention = self.retention;
+        for class_name in track {
+            if self.class_histogram_metrics.len() >= MAX_TRACKED_CLASSES {
+                break;
+            }
+            self.class_histogram_metrics
+                .entry(class_name.to_string())
+                .or_insert_with(|| NamedMetric::new(class_name.to_string(), retention));
+        }
+        let mut values: HashMap<&str, (u64, u64)> = HashMap::new();
+        for entry in &sample.histogram.entries {
+            let (instances, bytes) = values.entry(&entry.class_name).or_default();
+            *instances += entry.instances;
+            *bytes += entry.bytes;
+        }
+        for (class_name, metric) in self.class_histogram_metrics.iter_mut() {
+            let (instances, bytes) = values.get(class_name.as_str()).copied().unwrap_or_default();
+            metric.push(ClassHistogramMetricValue {
+                time: sample.time,
+                instances: Some(instances),
+                bytes: Some(bytes),
+            });
+        }
+    }
+
+    pub(crate) fn add_threads(&mut self, sample: &ThreadsSample, churn: ThreadChurn) {
+        self.thread_count_metrics_application
+            .push(ThreadCountMetricValue::new(
+                sample.time,
+                sample.thread_dump.application_thread_states(),
+            ));
+        self.thread_count_metrics_jvm
+            .push(ThreadCountMetricValue::new(
+                sample.time,
+                sample.thread_dump.jvm_thread_states(),
+            ));
+        self.thread_churn.push(ThreadChurnMetricValue {
+            time: sample.time,
+            created: churn.created,
+            terminated: churn.terminated,
+        });
+    }
+}
+
+type RollupsByName<T> = HashMap<String, Vec<Rollup<T>>>;
+
+/// The rollups of all series of a [`MetricsCache`], saved to session files. The values in full
+/// resolution are restored from the samples of the session.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct MetricsRollups {
+    total_memory: Vec<Rollup<TotalMemoryMetricValue>>,
+    container_memory: Vec<Rollup<ContainerMemoryMetricValue>>,
+    class_metrics: Vec<Rollup<ClassMemoryMetricValue>>,
+    heap_metrics: Vec<Rollup<HeapMemoryMetricValue>>,
+    metaspace_metrics: Vec<Rollup<MetaspaceMemoryMetricValue>>,
+    thread_metrics: Vec<Rollup<ThreadMemoryMetricValue>>,
+    thread_count_metrics_application: Vec<Rollup<ThreadCountMetricValue>>,
+    thread_count_metrics_jvm: Vec<Rollup<ThreadCountMetricValue>>,
+    thread_churn: Vec<Rollup<ThreadChurnMetricValue>>,
+    other_metrics: RollupsByName<GenericMemoryMetricValue>,
+    native_memory_metrics: RollupsByName<NativeMemoryMetricValue>,
+    class_histogram_metrics: RollupsByName<ClassHistogramMetricValue>,
+}
+
+/// Number of classes tracked from the class histograms at most.
+const MAX_TRACKED_CLASSES: usize = 50;
+
+/// How long the values of a series are kept.
+///
+/// Values are kept in full resolution for the given duration (relative to the latest value).
+/// Older values are rolled up into buckets holding their minimum, maximum and average. The oldest
+/// rollups are dropped once there are more than `max_rollups` of them.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Retention {
+    /// Milliseconds
+    full_resolution: u128,
+    /// Milliseconds
+    bucket: u128,
+    max_rollups: usize,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        // 30 minutes in full resolution, one rollup per minute for 24 hours
+        Retention {
+            full_resolution: 30 * 60 * 1000,
+            bucket: 60 * 1000,
+            max_rollups: 24 * 60,
+        }
+    }
+}
+
+/// Values of a time series returned to the ui.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Selection {
+    All,
+    /// Values collected and rollups created after the given time
+    Since(u128),
+    /// At most the given number of values and rollups spread evenly over the series
+    Downsampled(usize),
+}
+
+/// A value of a time series.
+pub(crate) trait SeriesValue: Clone {
+    fn time(&self) -> u128;
+
+    fn with_time(&self, time: u128) -> Self;
+
+    /// Combines the numbers of both values with `f`. Numbers missing in one of the values are
+    /// taken from the other one.
+    fn zip_with(&self, other: &Self, f: impl Fn(u64, u64) -> u64) -> Self;
+
+    fn map(&self, f: impl Fn(u64) -> u64) -> Self;
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedMetric<T> {
+    name: String,
+    /// Rolled up values older than the values in full resolution
+    rollups: Vec<Rollup<T>>,
+    values: Vec<T>,
+    #[serde(skip)]
+    retention: Retention,
+}
+
+impl<T: SeriesValue> NamedMetric<T> {
+    fn new(name: String, retention: Retention) -> NamedMetric<T> {
+        NamedMetric {
+            name,
+            rollups: Vec::new(),
+            values: Vec::new(),
+            retention,
+        }
+    }
+
+    /// Adds a value collected after the existing ones and rolls up the values having left the
+    /// full resolution period.
+    fn push(&mut self, value: T) {
+        let now = value.time();
+        self.values.push(value);
+
+        let retention = self.retention;
+        let cutoff = now.saturating_sub(retention.full_resolution);
+        while let Some(first) = self.values.first() {
+            let start = first.time() - first.time() % retention.bucket;
+            let end = start + retention.bucket;
+            if end > cutoff {
+                break;
+            }
+            let count = self.values.partition_point(|v| v.time() < end);
+            let rollup = Rollup::new(start, end, now, &self.values[..count]);
+            self.values.drain(..count);
+            match self.rollups.last_mut() {
+                Some(last) if last.time == start => *last = last.merge(&rollup),
+                _ => self.rollups.push(rollup),
+            }
+        }
+        if self.rollups.len() > retention.max_rollups {
+            let dropped = self.rollups.len() - retention.max_rollups;
+            self.rollups.drain(..dropped);
+        }
+    }
+
+    /// Restores rollups saved before. Restored rollups count as created at their end.
+    fn restore(&mut self, rollups: Vec<Rollup<T>>) {
+        self.rollups = rollups
+            .into_iter()
+            .map(|r| Rollup {
+                created: r.end,
+                ..r
+            })
+            .collect();
+    }
+
+    /// Returns the latest value in full resolution.
+    pub(crate) fn last(&self) -> Option<&T> {
+        self.values.last()
+    }
+
+    /// Copies the selected values and rollups. The values are expected to be ordered by time.
+    pub(crate) fn select(&self, selection: Selection) -> NamedMetric<T> {
+        let (rollups, values) = match selection {
+            Selection::All => (self.rollups.clone(), self.values.clone()),
+            Selection::Since(since) => {
+                let first = self.values.partition_point(|v| v.time() <= since);
+                let rollups = self.rollups.iter().filter(|r| r.created > since);
+                (rollups.cloned().collect(), self.values[first..].to_vec())
+            }
+            Selection::Downsampled(max_points) => (
+                downsample_rollups(&self.rollups, max_points),
+                downsample(&self.values, max_points),
+            ),
+        };
+        NamedMetric {
+            name: self.name.clone(),
+            rollups,
+            values,
+            retention: self.retention,
+        }
+    }
+}
+
+/// Picks every n-th value, so that at most `max_points` values remain. The latest value is always
+/// kept, so that values collected later can be appended.
+fn downsample<T: Clone>(values: &[T], max_points: usize) -> Vec<T> {
+    if values.len() <= max_points || max_points < 2 {
+        return values
+            .iter()
+            .rev()
+            .take(max_points)
+            .rev()
+            .cloned()
+            .collect();
+    }
+    let last = values.len() - 1;
+    let step = (last - 1) / (max_points - 1) + 1;
+    let mut picked: Vec<T> = (0..last).step_by(step).map(|i| values[i].clone()).collect();
+    picked.push(values[last].clone());
+    picked
+}
+
+/// Merges adjacent rollups, so that at most `max_points` rollups remain.
+fn downsample_rollups<T: SeriesValue>(rollups: &[Rollup<T>], max_points: usize) -> Vec<Rollup<T>> {
+    if rollups.len() <= max_points || max_points == 0 {
+        return rollups.iter().take(max_points).cloned().collect();
+    }
+    let chunk_size = (rollups.len() - 1) / max_points + 1;
+    rollups
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let (first, rest) = chunk.split_first().unwrap();
+            rest.iter().fold(first.clone(), |merged, r| merged.merge(r))
+        })
+        .collect()
+}
+
+/// Minimum, maximum and average of the values collected within a time bucket.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rollup<T> {
+    /// Start of the bucket
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    /// End of the bucket (exclusive)
+    #[serde(deserialize_with = "deserialize_time")]
+    end: u128,
+    count: u64,
+    min: T,
+    max: T,
+    avg: T,
+    /// Sum of the values, to merge rollups
+    sum: T,
+    /// Time of the value which caused the rollup
+    #[serde(skip)]
+    created: u128,
+}
+
+impl<T: SeriesValue> Rollup<T> {
+    /// Rolls up the given values. `values` must not be empty.
+    fn new(start: u128, end: u128, created: u128, values: &[T]) -> Rollup<T> {
+        let first = values[0].with_time(start);
+        let (mut min, mut max, mut sum) = (first.clone(), first.clone(), first);
+        for value in &values[1..] {
+            min = min.zip_with(value, u64::min);
+            max = max.zip_with(value, u64::max);
+            sum = sum.zip_with(value, u64::saturating_add);
+        }
+        let count = values.len() as u64;
+        Rollup {
+            time: start,
+            end,
+            count,
+            avg: sum.map(|v| v / count),
+            min,
+            max,
+            sum,
+            created,
+        }
+    }
+
+    fn merge(&self, other: &Rollup<T>) -> Rollup<T> {
+        let count = self.count + other.count;
+        let sum = self.sum.zip_with(&other.sum, u64::saturating_add);
+        Rollup {
+            time: self.time,
+            end: self.end.max(other.end),
+            count,
+            min: self.min.zip_with(&other.min, u64::min),
+            max: self.max.zip_with(&other.max, u64::max),
+            avg: sum.map(|v| v / count),
+            sum,
+            created: self.created.max(other.created),
+        }
+    }
+}
+
+pub type GenericMemoryMetric = NamedMetric<GenericMemoryMetricValue>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenericMemoryMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    reserved: Option<u64>,
+    committed: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassMemoryMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    class_count: u32,
+    reserved: Option<u64>,
+    committed: Option<u64>,
+    used: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetaspaceMemoryMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    reserved: Option<u64>,
+    committed: Option<u64>,
+    used: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeapMemoryMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    reserved: Option<u64>,
+    committed: Option<u64>,
+    used: Option<u64>,
+}
+
+impl HeapMemoryMetricValue {
+    pub(crate) fn used(&self) -> Option<u64> {
+        self.used
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadCountMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    new_count: u32,
+    runnable_count: u32,
+    waiting_count: u32,
+    timed_waiting_count: u32,
+    blocked_count: u32,
+}
+
+impl ThreadCountMetricValue {
+    fn new(time: u128, counts: ThreadStateCounts) -> ThreadCountMetricValue {
+        ThreadCountMetricValue {
+            time,
+            new_count: counts.new_count,
+            runnable_count: counts.runnable_count,
+            waiting_count: counts.waiting_count,
+            timed_waiting_count: counts.timed_waiting_count,
+            blocked_count: counts.blocked_count,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadChurnMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    created: u32,
+    terminated: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadMemoryMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    thread_count: u32,
+    reserved: Option<u64>,
+    committed: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassHistogramMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    instances: Option<u64>,
+    bytes: Option<u64>,
+}
+
+/// Memory of a native memory category by the way it is allocated.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeMemoryMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    malloc: Option<u64>,
+    malloc_count: u32,
+    mmap_reserved: Option<u64>,
+    mmap_committed: Option<u64>,
+    arena: Option<u64>,
+    arena_count: u32,
+    stack_reserved: Option<u64>,
+    stack_committed: Option<u64>,
+}
+
+impl NativeMemoryMetricValue {
+    fn new(time: u128, category: &NmtCategory) -> NativeMemoryMetricValue {
+        let malloc = category.malloc.as_ref();
+        let mmap = category.mmap.as_ref();
+        let arena = category.arena.as_ref();
+        let stack = category.stack.as_ref();
+        NativeMemoryMetricValue {
+            time,
+            malloc: malloc.and_then(|m| m.size),
+            malloc_count: count(malloc.and_then(|m| m.count)),
+            mmap_reserved: mmap.and_then(|m| m.reserved),
+            mmap_committed: mmap.and_then(|m| m.committed),
+            arena: arena.and_then(|a| a.size),
+            arena_count: count(arena.and_then(|a| a.count)),
+            stack_reserved: stack.and_then(|s| s.reserved),
+            stack_committed: stack.and_then(|s| s.committed),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotalMemoryMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    reserved: Option<u64>,
+    committed: Option<u64>,
+}
+
+/// Memory of the cgroup of a process. The headroom is the limit minus the total committed memory
+/// of the native memory tracking.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerMemoryMetricValue {
+    #[serde(deserialize_with = "deserialize_time")]
+    time: u128,
+    limit: Option<u64>,
+    usage: Option<u64>,
+    headroom: Option<u64>,
+}
+
+/// Counts missing in the output are 0.
+fn count(value: Option<u64>) -> u32 {
+    value.map_or(0, |v| u32::try_from(v).unwrap_or(u32::MAX))
+}
+
+fn zip_bytes(a: Option<u64>, b: Option<u64>, f: &impl Fn(u64, u64) -> u64) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(f(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn zip_count(a: u32, b: u32, f: &impl Fn(u64, u64) -> u64) -> u32 {
+    u32::try_from(f(u64::from(a), u64::from(b))).unwrap_or(u32::MAX)
+}
+
+fn map_count(a: u32, f: &impl Fn(u64) -> u64) -> u32 {
+    u32::try_from(f(u64::from(a))).unwrap_or(u32::MAX)
+}
+
+/// Implements [`SeriesValue`] for a value with the given optional byte fields and count fields.
+macro_rules! impl_series_value {
+    ($t:ident { bytes: [$($bytes:ident),*], counts: [$($counts:ident),*] }) => {
+        impl SeriesValue for $t {
+            fn time(&self) -> u128 {
+                self.time
+            }
+
+            fn with_time(&self, time: u128) -> Self {
+                $t {
+                    time,
+                    ..self.clone()
+                }
+            }
+
+            fn zip_with(&self, other: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+                $t {
+                    time: self.time,
+                    $($bytes: zip_bytes(self.$bytes, other.$bytes, &f),)*
+                    $($counts: zip_count(self.$counts, other.$counts, &f),)*
+                }
+            }
+
+            fn map(&self, f: impl Fn(u64) -> u64) -> Self {
+                $t {
+                    time: self.time,
+                    $($bytes: self.$bytes.map(&f),)*
+                    $($counts: map_count(self.$counts, &f),)*
+                }
+            }
+        }
+    };
+}
+
+impl_series_value!(GenericMemoryMetricValue {
+    bytes: [reserved, committed],
+    counts: []
+});
+impl_series_value!(ClassMemoryMetricValue {
+    bytes: [reserved, committed, used],
+    counts: [class_count]
+});
+impl_series_value!(MetaspaceMemoryMetricValue {
+    bytes: [reserved, committed, used],
+    counts: []
+});
+impl_series_value!(HeapMemoryMetricValue {
+    bytes: [reserved, committed, used],
+    counts: []
+});
+impl_series_value!(ThreadCountMetricValue {
+    bytes: [],
+    counts: [
+        new_count,
+        runnable_count,
+        waiting_count,
+        timed_waiting_count,
+        blocked_count
+    ]
+});
+impl_series_value!(ThreadChurnMetricValue {
+    bytes: [],
+    counts: [created, terminated]
+});
+impl_series_value!(ThreadMemoryMetricValue {
+    bytes: [reserved, committed],
+    counts: [thread_count]
+});
+impl_series_value!(NativeMemoryMetricValue {
+    bytes: [
+        malloc,
+        mmap_reserved,
+        mmap_committed,
+        arena,
+        stack_reserved,
+        stack_committed
+    ],
+    counts: [malloc_count, arena_count]
+});
+impl_series_value!(ClassHistogramMetricValue {
+    bytes: [instances, bytes],
+    counts: []
+});
+impl_series_value!(TotalMemoryMetricValue {
+    bytes: [reserved, committed],
+    counts: []
+});
+impl_series_value!(ContainerMemoryMetricValue {
+    bytes: [limit, usage, headroom],
+    counts: []
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(time: u128, committed: u64) -> GenericMemoryMetricValue {
+        GenericMemoryMetricValue {
+            time,
+            reserved: None,
+            committed: Some(committed),
+        }
+    }
+
+    fn metric(max_rollups: usize) -> GenericMemoryMetric {
+        NamedMetric::new(
+            "Test".to_string(),
+            Retention {
+                full_resolution: 1000,
+                bucket: 100,
+                max_rollups,
+            },
+        )
+    }
+
+    fn committed(values: &[GenericMemoryMetricValue]) -> Vec<u64> {
+        values.iter().map(|v| v.committed.unwrap()).collect()
+    }
+
+    #[test]
+    fn keeps_values_of_the_full_resolution_period() {
+        let mut metric = metric(10);
+        for time in (0..=1100).step_by(50) {
+            metric.push(value(time, 1));
+        }
+        // The first bucket ends at the cutoff and is rolled up
+        assert_eq!(metric.rollups.len(), 1);
+        assert_eq!(metric.rollups[0].count, 2);
+        assert_eq!(metric.values.len(), 21);
+        assert_eq!(metric.values[0].time, 100);
+        assert_eq!(metric.last().unwrap().time, 1100);
+    }
+
+    #[test]
+    fn rolls_up_values_into_buckets() {
+        let mut metric = metric(10);
+        metric.push(value(110, 4));
+        metric.push(value(150, 2));
+        metric.push(value(190, 9));
+        metric.push(value(1200, 1));
+
+        assert_eq!(committed(&metric.values), vec![1]);
+        assert_eq!(metric.rollups.len(), 1);
+        let rollup = &metric.rollups[0];
+        assert_eq!((rollup.time, rollup.end), (100, 200));
+        assert_eq!(rollup.count, 3);
+        assert_eq!(rollup.min.committed, Some(2));
+        assert_eq!(rollup.max.committed, Some(9));
+        assert_eq!(rollup.avg.committed, Some(5));
+        assert_eq!(rollup.avg.time, 100);
+        assert_eq!(rollup.avg.reserved, None);
+        assert_eq!(rollup.created, 1200);
+    }
+
+    #[test]
+    fn merges_values_rolled_up_later_into_the_same_bucket() {
+        let mut metric = metric(10);
+        metric.push(value(110, 4));
+        metric.push(value(1150, 1));
+        // Pushed out of order, e.g. by a sample collected while the previous one was pending
+        metric.values.insert(0, value(180, 10));
+        metric.push(value(1200, 1));
+
+        assert_eq!(metric.rollups.len(), 1);
+        let rollup = &metric.rollups[0];
+        assert_eq!(rollup.count, 2);
+        assert_eq!(rollup.min.committed, Some(4));
+        assert_eq!(rollup.max.committed, Some(10));
+        assert_eq!(rollup.avg.committed, Some(7));
+        assert_eq!(rollup.created, 1200);
+    }
+
+    #[test]
+    fn drops_the_oldest_rollups() {
+        let mut metric = metric(3);
+        for time in (0..=2000).step_by(100) {
+            metric.push(value(time, time as u64));
+        }
+        let rollups: Vec<u128> = metric.rollups.iter().map(|r| r.time).collect();
+        assert_eq!(rollups, vec![700, 800, 900]);
+        assert_eq!(metric.values.first().unwrap().time, 1000);
+    }
+
+    #[test]
+    fn selects_values_and_rollups_since() {
+        let mut metric = metric(10);
+        for time in (0..=1500).step_by(100) {
+            metric.push(value(time, 1));
+        }
+        let selected = metric.select(Selection::Since(1400));
+        assert_eq!(selected.values.len(), 1);
+        // The rollup created by the value at 1500
+        assert_eq!(selected.rollups.len(), 1);
+        assert_eq!(selected.rollups[0].time, 400);
+    }
+
+    #[test]
+    fn downsamples_values() {
+        let values: Vec<u32> = (0..10).collect();
+        assert_eq!(downsample(&values, 20), values);
+        assert_eq!(downsample(&values, 10), values);
+        assert_eq!(downsample(&values, 4), vec![0, 3, 6, 9]);
+        assert_eq!(downsample(&values, 3), vec![0, 5, 9]);
+        assert_eq!(downsample(&values, 2), vec![0, 9]);
+        // The latest value is always kept
+        assert_eq!(downsample(&values, 1), vec![9]);
+        assert_eq!(downsample(&values, 0), Vec::<u32>::new());
+        assert_eq!(downsample(&[] as &[u32], 5), Vec::<u32>::new());
+
+        for max_points in 2..10 {
+            let picked = downsample(&values, max_points);
+            assert!(picked.len() <= max_points, "{picked:?}");
+            assert_eq!(picked.last(), Some(&9));
+        }
+    }
+
+    #[test]
+    fn downsamples_rollups() {
+        let mut metric = metric(10);
+        for time in (0..=2000).step_by(100) {
+            metric.push(value(time, time as u64));
+        }
+        assert_eq!(metric.rollups.len(), 10);
+
+        let merged = downsample_rollups(&metric.rollups, 4);
+        assert_eq!(merged.len(), 4);
+        assert_eq!((merged[0].time, merged[0].end), (0, 300));
+        assert_eq!(merged[0].count, 3);
+        assert_eq!(merged[0].min.committed, Some(0));
+        assert_eq!(merged[0].max.committed, Some(200));
+        assert_eq!(merged[0].avg.committed, Some(100));
+        assert_eq!((merged[3].time, merged[3].end), (900, 1000));
+        assert_eq!(downsample_rollups(&metric.rollups, 0).len(), 0);
+    }
+}
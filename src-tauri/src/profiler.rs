@@ -0,0 +1,207 @@
+//! Sampling profiler built on thread dumps taken at a high rate. The stacks of the runnable
+//! threads are aggregated into collapsed stacks (`frame;frame;frame count`, the input format of
+//! Brendan Gregg's flame graph tools).
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use jcmd_parse::{JcmdError, ThreadDump};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileOptions {
+    /// Time between two thread dumps in milliseconds. Dumps taking longer delay the next one.
+    pub interval: u64,
+    /// Duration of the profile in seconds
+    pub duration: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProfileStatus {
+    Running,
+    Completed,
+    /// Stopped before the end of its duration
+    Stopped,
+    Failed,
+}
+
+/// A profile of a process. The stacks aren't serialized, as they are only requested once the
+/// profile finished.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    #[serde(flatten)]
+    pub options: ProfileOptions,
+    pub started: u128,
+    pub finished: Option<u128>,
+    pub status: ProfileStatus,
+    /// Number of thread dumps taken
+    pub dumps: u32,
+    /// Ticks of the interval without thread dump, as the previous dump was still running
+    pub skipped: u64,
+    /// Number of stacks of runnable threads
+    pub samples: u64,
+    pub error: Option<JcmdError>,
+    #[serde(skip)]
+    stacks: HashMap<String, u64>,
+}
+
+/// A stack with its frames separated by `;`, the outermost frame first, and the number of times it
+/// was sampled.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollapsedStack {
+    pub stack: String,
+    pub count: u64,
+}
+
+impl Profile {
+    pub(crate) fn new(options: ProfileOptions, started: u128) -> Profile {
+        Profile {
+            options,
+            started,
+            finished: None,
+            status: ProfileStatus::Running,
+            dumps: 0,
+            skipped: 0,
+            samples: 0,
+            error: None,
+            stacks: HashMap::new(),
+        }
+    }
+
+    /// Adds the stacks of the runnable application threads of the dump. Threads in native
+    /// methods (e.g. reading from a socket) are runnable as well.
+    pub(crate) fn add(&mut self, thread_dump: &ThreadDump) {
+        self.dumps += 1;
+        for thread in &thread_dump.application_threads {
+            if !thread.status.starts_with("RUNNABLE") || thread.stack.is_empty() {
+                continue;
+            }
+            let frames: Vec<String> = thread
+                .stack
+                .iter()
+                .rev()
+                .map(|f| frame_name(&f.method))
+                .collect();
+            *self.stacks.entry(frames.join(";")).or_default() += 1;
+            self.samples += 1;
+        }
+    }
+
+    pub(crate) fn finish(&mut self, time: u128, status: ProfileStatus) {
+        self.finished = Some(time);
+        self.status = status;
+    }
+
+    /// Returns the sampled stacks, the most frequent first.
+    pub(crate) fn stacks(&self) -> Vec<CollapsedStack> {
+        let mut stacks: Vec<CollapsedStack> = self
+            .stacks
+            .iter()
+            .map(|(stack, count)| CollapsedStack {
+                stack: stack.clone(),
+                count: *count,
+            })
+            .collect();
+        stacks.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.stack.cmp(&b.stack)));
+        stacks
+    }
+
+    /// Returns the stacks in the collapsed format, one stack per line.
+    pub(crate) fn collapsed(&self) -> String {
+        self.stacks()
+            .iter()
+            .map(|s| format!("{} {}\n", s.stack, s.count))
+            .collect()
+    }
+}
+
+/// Returns the next tick of the interval after the elapsed time. Ticks passed while a thread
+/// dump was taken are skipped instead of taking the dumps back to back to catch up.
+pub(crate) fn next_tick(elapsed: Duration, interval: Duration) -> u64 {
+    u64::try_from(elapsed.as_nanos() / interval.as_nanos()).unwrap_or(u64::MAX - 1) + 1
+}
+
+/// Removes the address of hidden classes (e.g. `Main$$Lambda$14/0x0000000800c0a000.run`), which
+/// differs between runs, and characters of the collapsed format.
+fn frame_name(method: &str) -> String {
+    let name = match method.split_once("/0x") {
+        Some((class, rest)) => match rest.find('.') {
+            Some(index) => format!("{}{}", class, &rest[index..]),
+            None => class.to_string(),
+        },
+        None => method.to_string(),
+    };
+    name.replace([';', ' '], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use jcmd_parse::parse_thread_dump;
+
+    use super::*;
+
+    #[test]
+    fn removes_addresses_of_hidden_classes() {
+        assert_eq!(
+            frame_name("com.example.Worker.run"),
+            "com.example.Worker.run"
+        );
+        assert_eq!(
+            frame_name("Main$$Lambda$14/0x0000000800c0a000.run"),
+            "Main$$Lambda$14.run"
+        );
+        assert_eq!(
+            frame_name("java.lang.invoke.LambdaForm$MH/0x0000000801004400"),
+            "java.lang.invoke.LambdaForm$MH"
+        );
+    }
+
+    #[test]
+    fn skips_ticks_passed_while_dumping() {
+        let interval = Duration::from_millis(100);
+        // The first dump took 30 ms, the next one is taken at the next tick
+        assert_eq!(next_tick(Duration::from_millis(30), interval), 1);
+        // The dump of tick 1 took 280 ms, ticks 2 and 3 are skipped
+        assert_eq!(next_tick(Duration::from_millis(380), interval), 4);
+        assert_eq!(next_tick(Duration::from_millis(400), interval), 5);
+    }
+
+    #[test]
+    fn replaces_separators_of_collapsed_format() {
+        assert_eq!(frame_name("a;b c"), "a_b_c");
+    }
+
+    #[test]
+    fn collapses_stacks_of_runnable_threads() {
+        let mut dump = parse_thread_dump(include_str!(
+            "../jcmd-parse/tests/fixtures/jdk17/g1/Thread.print.txt"
+        ))
+        .value;
+        let mut profile = Profile::new(
+            ProfileOptions {
+                interval: 500,
+                duration: 10,
+            },
+            0,
+        );
+        profile.add(&dump);
+        profile.add(&dump);
+        dump.application_threads
+            .retain(|t| t.name.starts_with("worker"));
+        profile.add(&dump);
+
+        assert_eq!(profile.dumps, 3);
+        // Sleeping, waiting and blocked threads and runnable threads without stack are skipped
+        assert_eq!(profile.samples, 5);
+        assert_eq!(
+            profile.collapsed(),
+            "java.lang.Thread.run;com.example.Worker.run;com.example.Worker.compute 3\n\
+             java.lang.ref.Reference$ReferenceHandler.run;java.lang.ref.Reference.processPendingReferences;\
+             java.lang.ref.Reference.waitForReferencePendingList 2\n"
+        );
+    }
+}
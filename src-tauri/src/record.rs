@@ -0,0 +1,330 @@
+//! Headless recording of metrics: `jcmd-ui record --pid <pid> --out run.jsonl`.
+//!
+//! The samples are written in the session file format (see [`crate::session`]), so recordings
+//! can be opened in the ui afterwards.
+//!
+//! The subcommand is part of the ui binary, so it needs the libraries of the webview (GTK and
+//! WebKitGTK on linux) even though no window is opened. On windows the binary is built for the
+//! gui subsystem, which has no console, so the output of the subcommand isn't shown.
+
+use std::time::{Duration, Instant};
+
+use jcmd_parse::JcmdError;
+
+use crate::collector::{self, ThreadCpuCache};
+use crate::exporter;
+use crate::session::{SessionEntry, SessionWriter, SESSION_VERSION};
+
+const USAGE: &str = "Usage: jcmd-ui record --pid <pid> --out <file> [--interval <duration>] \
+[--duration <duration>] [--exporter <address>]
+
+Records memory metrics and thread dumps of a jvm process as json lines.
+
+Options:
+  --pid <pid>            process to record
+  --out <file>           file to write the samples to
+  --interval <duration>  time between two samples (default: 5s, at least 500ms)
+  --duration <duration>  stop recording after this time (default: until the process exits)
+  --exporter <address>   serve the latest samples as OpenMetrics on http://<address>/metrics
+                         (a port without a host listens on localhost)
+
+Durations are given as a number with one of the units ms, s, m or h (e.g. 500ms, 5s, 30m).";
+
+struct RecordOptions {
+    pid: String,
+    out: String,
+    interval: Duration,
+    duration: Option<Duration>,
+    exporter: Option<String>,
+}
+
+/// Runs the `record` subcommand with the arguments following it and returns the exit code.
+pub(crate) fn run(args: &[String]) -> i32 {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return 0;
+    }
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return 2;
+        }
+    };
+    if let Some(address) = &options.exporter {
+        if let Err(e) = exporter::start(address) {
+            eprintln!("Couldn't start exporter on {}: {}", address, e);
+            return 1;
+        }
+    }
+    let mut recorder = match SessionWriter::create(&options.out) {
+        Ok(out) => Recorder { out },
+        Err(e) => {
+            eprintln!("Couldn't create {}", e);
+            return 1;
+        }
+    };
+    match recorder.record(&options) {
+        Ok(samples) => {
+            eprintln!("Recorded {} samples to {}", samples, options.out);
+            0
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            1
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<RecordOptions, String> {
+    let mut pid = None;
+    let mut out = None;
+    let mut interval = Duration::from_secs(5);
+    let mut duration = None;
+    let mut exporter = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--pid" => pid = Some(value()?),
+            "--out" => out = Some(value()?),
+            "--interval" => interval = parse_duration(&value()?)?,
+            "--duration" => duration = Some(parse_duration(&value()?)?),
+            "--exporter" => exporter = Some(value()?),
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    if interval < collector::MIN_INTERVAL {
+        return Err(format!(
+            "The interval must be at least {}ms",
+            collector::MIN_INTERVAL.as_millis()
+        ));
+    }
+    Ok(RecordOptions {
+        pid: pid.ok_or("Missing --pid")?,
+        out: out.ok_or("Missing --out")?,
+        interval,
+        duration,
+        exporter,
+    })
+}
+
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid duration {} (expected e.g. 500ms, 5s, 30m or 1h)",
+            value
+        )
+    };
+    let unit_index = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let amount = value[..unit_index].parse::<u64>().map_err(|_| invalid())?;
+    let seconds = |factor: u64| {
+        amount
+            .checked_mul(factor)
+            .map(Duration::from_secs)
+            .ok_or_else(invalid)
+    };
+    match &value[unit_index..] {
+        "ms" => Ok(Duration::from_millis(amount)),
+        "s" | "" => Ok(Duration::from_secs(amount)),
+        "m" => seconds(60),
+        "h" => seconds(60 * 60),
+        _ => Err(invalid()),
+    }
+}
+
+struct Recorder {
+    out: SessionWriter,
+}
+
+impl Recorder {
+    /// Collects samples until the duration is over or the process is gone and returns the
+    /// number of recorded samples.
+    fn record(&mut self, options: &RecordOptions) -> Result<u32, String> {
+        let pid = options.pid.as_str();
+        let started = Instant::now();
+        self.write(SessionEntry::Start {
+            version: SESSION_VERSION,
+            pid: Some(options.pid.clone()),
+            interval: Some(options.interval.as_millis()),
+            time: collector::now(),
+        })?;
+
+        match collector::collect_vm_information(pid) {
+            Ok(vm_information) => {
+                exporter::update_vm_information(pid, &vm_information);
+                self.write(SessionEntry::VmInformation(Box::new(vm_information)))?
+            }
+            Err(error) => self.handle_error(&error)?,
+        }
+
+        let mut thread_cpu = ThreadCpuCache::default();
+        let mut samples = 0;
+        let mut next_sample = started;
+        loop {
+            match collector::collect_metrics(pid) {
+                Ok(sample) => {
+                    exporter::update_metrics(pid, &sample);
+                    self.write(SessionEntry::Metrics(sample))?
+                }
+                Err(JcmdError::ProcessGone) => break,
+                Err(error) => self.handle_error(&error)?,
+            }
+            match collector::collect_threads(pid, false) {
+                Ok(mut sample) => {
+                    thread_cpu.apply(&mut sample.thread_dump);
+                    exporter::update_threads(pid, &sample);
+                    self.write(SessionEntry::Threads(sample))?
+                }
+                Err(JcmdError::ProcessGone) => break,
+                Err(error) => self.handle_error(&error)?,
+            }
+            samples += 1;
+
+            next_sample += options.interval;
+            if let Some(duration) = options.duration {
+                if next_sample.duration_since(started) > duration {
+                    return Ok(samples);
+                }
+            }
+            let now = Instant::now();
+            if next_sample > now {
+                std::thread::sleep(next_sample - now);
+            } else {
+                // jcmd took longer than the interval, continue with the next sample right away
+                next_sample = now;
+            }
+        }
+        eprintln!("Process {} is gone", pid);
+        Ok(samples)
+    }
+
+    /// Records the error. Errors which won't go away on the next sample abort the recording.
+    fn handle_error(&mut self, error: &JcmdError) -> Result<(), String> {
+        self.write(SessionEntry::Error {
+            time: collector::now(),
+            error: error.clone(),
+        })?;
+        match error {
+            JcmdError::JcmdMissing { .. } | JcmdError::AttachRefused { .. } => {
+                Err(error.to_string())
+            }
+            _ => {
+                eprintln!("{}", error);
+                Ok(())
+            }
+        }
+    }
+
+    fn write(&mut self, entry: SessionEntry) -> Result<(), String> {
+        self.out
+            .write(&entry)
+            .map_err(|e| format!("Couldn't write sample to {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("5s"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_duration("5"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for value in ["", "s", "5d", "-5s", "1.5s", "5 s"] {
+            assert_eq!(
+                parse_duration(value),
+                Err(format!(
+                    "Invalid duration {} (expected e.g. 500ms, 5s, 30m or 1h)",
+                    value
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_durations() {
+        assert_eq!(
+            parse_duration(&format!("{}s", u64::MAX)),
+            Ok(Duration::from_secs(u64::MAX))
+        );
+        assert!(parse_duration(&format!("{}m", u64::MAX / 60 + 1)).is_err());
+        assert!(parse_duration(&format!("{}h", u64::MAX / 3600 + 1)).is_err());
+        assert!(parse_duration("18446744073709551616s").is_err());
+    }
+
+    #[test]
+    fn parses_args() {
+        let options = parse_args(&args(&[
+            "--pid",
+            "4711",
+            "--out",
+            "run.jsonl",
+            "--interval",
+            "500ms",
+            "--duration",
+            "1h",
+            "--exporter",
+            "9464",
+        ]))
+        .unwrap();
+        assert_eq!(options.pid, "4711");
+        assert_eq!(options.out, "run.jsonl");
+        assert_eq!(options.interval, Duration::from_millis(500));
+        assert_eq!(options.duration, Some(Duration::from_secs(60 * 60)));
+        assert_eq!(options.exporter.as_deref(), Some("9464"));
+    }
+
+    #[test]
+    fn uses_default_interval_and_no_duration() {
+        let options = parse_args(&args(&["--out", "run.jsonl", "--pid", "4711"])).unwrap();
+        assert_eq!(options.interval, Duration::from_secs(5));
+        assert_eq!(options.duration, None);
+        assert_eq!(options.exporter, None);
+    }
+
+    #[test]
+    fn rejects_invalid_args() {
+        for (args, message) in [
+            (args(&["--out", "run.jsonl"]), "Missing --pid"),
+            (args(&["--pid", "4711"]), "Missing --out"),
+            (args(&["--pid"]), "Missing value for --pid"),
+            (
+                args(&["--pid", "4711", "--out", "run.jsonl", "--verbose"]),
+                "Unknown argument --verbose",
+            ),
+            (
+                args(&["--pid", "4711", "--out", "run.jsonl", "--interval", "0s"]),
+                "The interval must be at least 500ms",
+            ),
+            (
+                args(&["--pid", "4711", "--out", "run.jsonl", "--interval", "499ms"]),
+                "The interval must be at least 500ms",
+            ),
+            (
+                args(&["--pid", "4711", "--out", "run.jsonl", "--duration", "1w"]),
+                "Invalid duration 1w (expected e.g. 500ms, 5s, 30m or 1h)",
+            ),
+        ] {
+            assert_eq!(parse_args(&args).err().as_deref(), Some(message));
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// A task run repeatedly by a [`Scheduler`]. The task returns `false` to stop the scheduler.
+pub(crate) struct Task {
+    interval: Duration,
+    run: Box<dyn FnMut() -> bool + Send>,
+}
+
+impl Task {
+    pub(crate) fn new(interval: Duration, run: impl FnMut() -> bool + Send + 'static) -> Task {
+        Task {
+            interval,
+            run: Box::new(run),
+        }
+    }
+}
+
+/// Runs tasks on their intervals in background threads.
+///
+/// Each task has its own thread, so a slow task (e.g. a class histogram of a large heap) doesn't
+/// delay the other tasks. A task never overlaps with itself, taking longer than its interval
+/// delays its next runs instead of piling up. The scheduler stops when it is dropped.
+pub(crate) struct Scheduler {
+    stop: Arc<Stop>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Scheduler {
+    pub(crate) fn start(name: String, tasks: Vec<Task>) -> Scheduler {
+        let stop = Arc::new(Stop::default());
+        let mut threads = Vec::new();
+        for (index, mut task) in tasks.into_iter().enumerate() {
+            let stop = stop.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("{}-{}", name, index))
+                .spawn(move || {
+                    let mut next_run = Instant::now();
+                    while stop.wait_until(next_run) {
+                        let started = Instant::now();
+                        if !(task.run)() {
+                            stop.stop();
+                            return;
+                        }
+                        next_run = started + task.interval;
+                    }
+                });
+            match spawned {
+                Ok(thread) => threads.push(thread),
+                Err(e) => eprintln!("Couldn't start scheduler: {}", e),
+            }
+        }
+        Scheduler { stop, threads }
+    }
+
+    /// Returns `false` once a task stopped the scheduler.
+    pub(crate) fn is_running(&self) -> bool {
+        !self.threads.is_empty() && self.threads.iter().all(|t| !t.is_finished())
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        // Running tasks finish in the background, a jcmd call can take a while
+        self.stop.stop();
+    }
+}
+
+/// Stops the threads of a scheduler, also while they wait for the next run of their task.
+#[derive(Default)]
+struct Stop {
+    stopped: Mutex<bool>,
+    wake_up: Condvar,
+}
+
+impl Stop {
+    fn stop(&self) {
+        *self.stopped.lock().unwrap() = true;
+        self.wake_up.notify_all();
+    }
+
+    /// Waits until the time is reached. Returns `false` if the scheduler stopped meanwhile.
+    fn wait_until(&self, time: Instant) -> bool {
+        let timeout = time.saturating_duration_since(Instant::now());
+        let stopped = self.stopped.lock().unwrap();
+        let (stopped, _) = self
+            .wake_up
+            .wait_timeout_while(stopped, timeout, |stopped| !*stopped)
+            .unwrap();
+        !*stopped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn counting_task(interval: u64, runs: &Arc<AtomicUsize>, sleep: u64) -> Task {
+        let runs = runs.clone();
+        Task::new(Duration::from_millis(interval), move || {
+            runs.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(sleep));
+            true
+        })
+    }
+
+    #[test]
+    fn runs_fast_tasks_while_slow_tasks_are_running() {
+        let fast = Arc::new(AtomicUsize::new(0));
+        let slow = Arc::new(AtomicUsize::new(0));
+        let scheduler = Scheduler::start(
+            "test".to_string(),
+            vec![
+                counting_task(1000, &slow, 1000),
+                counting_task(10, &fast, 0),
+            ],
+        );
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(scheduler.is_running());
+        assert_eq!(slow.load(Ordering::SeqCst), 1);
+        assert!(fast.load(Ordering::SeqCst) >= 5);
+    }
+
+    #[test]
+    fn stops_all_tasks_when_a_task_returns_false() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let scheduler = Scheduler::start(
+            "test".to_string(),
+            vec![
+                counting_task(10, &runs, 0),
+                Task::new(Duration::from_millis(10), || false),
+            ],
+        );
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!scheduler.is_running());
+        let stopped = runs.load(Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(runs.load(Ordering::SeqCst), stopped);
+    }
+
+    #[test]
+    fn stops_when_dropped() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let scheduler = Scheduler::start("test".to_string(), vec![counting_task(10, &runs, 0)]);
+        std::thread::sleep(Duration::from_millis(50));
+        drop(scheduler);
+        std::thread::sleep(Duration::from_millis(20));
+        let stopped = runs.load(Ordering::SeqCst);
+        assert!(stopped > 0);
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(runs.load(Ordering::SeqCst), stopped);
+    }
+}
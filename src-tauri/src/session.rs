@@ -0,0 +1,736 @@
+//! Monitoring sessions and their file format.
+//!
+//! A session file contains one json object per line, tagged with its `type`. The first line is
+//! always a `start` entry holding the version of the format, followed by `vmInformation`,
+//! `metrics`, `threads` and `error` entries in the order they were collected. Saved sessions
+//! contain a `rollups` entry before the samples, holding the metrics of the samples which were
+//! dropped already (since version 2). Recordings of the `record` subcommand use the same format
+//! and can be opened in the ui as well.
+
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use jcmd_parse::{ClassHistogram, JcmdError, JfrRecording, JfrRecordingState};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::collector::{
+    deserialize_time, ClassHistogramSample, FlightRecording, HeapDump, JfrDump, MetricsSample,
+    NmtDiffSample, ThreadCpuCache, ThreadsSample, VmFlagChange, VmInformation,
+};
+use crate::contention::LockContention;
+use crate::metrics::{MetricsCache, MetricsRollups};
+use crate::profiler::Profile;
+use crate::thread_history::ThreadHistories;
+
+/// Number of native memory diffs kept per session. Older diffs are dropped.
+const MAX_NMT_DIFFS: usize = 1000;
+
+/// Number of class histograms kept per session. Older histograms are dropped.
+const MAX_CLASS_HISTOGRAMS: usize = 20;
+
+/// Number of classes with the largest growth, which are tracked as series with each histogram.
+const TOP_GROWING_CLASSES: usize = 10;
+
+/// Number of thread dumps per session whose stacks are kept. Only the threads of older dumps are
+/// kept, without their stacks.
+const MAX_THREAD_STACKS: usize = 150;
+
+/// Version of the session file format. Files written with a newer version are rejected.
+///
+/// Version 2 added the `rollups` entry.
+pub(crate) const SESSION_VERSION: u32 = 2;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(crate) enum SessionEntry {
+    Start {
+        version: u32,
+        pid: Option<String>,
+        /// Sampling interval in milliseconds, if the samples were collected with a fixed interval.
+        #[serde(default, deserialize_with = "deserialize_interval")]
+        interval: Option<u128>,
+        #[serde(deserialize_with = "deserialize_time")]
+        time: u128,
+    },
+    VmInformation(Box<VmInformation>),
+    /// Rolled up metrics older than the samples of the file
+    Rollups(Box<MetricsRollups>),
+    Metrics(MetricsSample),
+    Threads(ThreadsSample),
+    Error {
+        #[serde(deserialize_with = "deserialize_time")]
+        time: u128,
+        error: JcmdError,
+    },
+}
+
+fn deserialize_interval<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u128>, D::Error> {
+    Ok(Option::<u64>::deserialize(deserializer)?.map(u128::from))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SessionError {
+    Io { path: String, message: String },
+    Format { line: usize, message: String },
+    UnsupportedVersion { version: u32 },
+    NotFound,
+}
+
+impl SessionError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SessionError::Io { .. } => "io",
+            SessionError::Format { .. } => "format",
+            SessionError::UnsupportedVersion { .. } => "unsupportedVersion",
+            SessionError::NotFound => "notFound",
+        }
+    }
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Io { path, message } => write!(f, "{}: {}", path, message),
+            SessionError::Format { line, message } => {
+                write!(f, "Invalid session file (line {}): {}", line, message)
+            }
+            SessionError::UnsupportedVersion { version } => write!(
+                f,
+                "Session file version {} is not supported (latest supported version is {})",
+                version, SESSION_VERSION
+            ),
+            SessionError::NotFound => write!(f, "No session data available"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+/// Serialized as `{ kind, message }` like [`JcmdError`].
+impl Serialize for SessionError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("SessionError", 2)?;
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.end()
+    }
+}
+
+/// Writes session entries to a file. Every entry is flushed, so that an interrupted recording
+/// still leaves a readable file.
+pub(crate) struct SessionWriter {
+    path: String,
+    out: BufWriter<File>,
+}
+
+impl SessionWriter {
+    pub(crate) fn create(path: &str) -> Result<SessionWriter, SessionError> {
+        let file = File::create(path).map_err(|e| io_error(path, e))?;
+        Ok(SessionWriter {
+            path: path.to_string(),
+            out: BufWriter::new(file),
+        })
+    }
+
+    pub(crate) fn write(&mut self, entry: &SessionEntry) -> Result<(), SessionError> {
+        serde_json::to_writer(&mut self.out, entry).map_err(|e| io_error(&self.path, e))?;
+        writeln!(self.out)
+            .and_then(|_| self.out.flush())
+            .map_err(|e| io_error(&self.path, e))
+    }
+}
+
+fn io_error(path: &str, e: impl Display) -> SessionError {
+    SessionError::Io {
+        path: path.to_string(),
+        message: e.to_string(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassHistogramOptions {
+    /// Count unreachable objects as well instead of triggering a full gc
+    pub(crate) all: bool,
+}
+
+/// The samples collected from a process, either live or read from a session file (replay).
+pub(crate) struct Session {
+    pub(crate) pid: Option<String>,
+    pub(crate) started: u128,
+    pub(crate) vm_information: Option<VmInformation>,
+    pub(crate) metrics: MetricsCache,
+    /// Samples of the full resolution period of the metrics, older samples are only kept as
+    /// rollups in live sessions. Saved to session files with the rollups.
+    pub(crate) metrics_samples: Vec<MetricsSample>,
+    /// Thread dumps of the full resolution period of the metrics. Saved to session files.
+    pub(crate) thread_samples: Vec<ThreadsSample>,
+    /// Cpu times of the previous thread dump, to calculate the cpu time spent between two dumps.
+    pub(crate) thread_cpu: ThreadCpuCache,
+    /// Periodic thread dumps include the locked ownable synchronizers (`Thread.print -l`)
+    pub(crate) thread_dump_locks: bool,
+    /// Blocked threads of all thread dumps by monitor
+    pub(crate) lock_contention: LockContention,
+    /// Threads created and terminated in the session
+    pub(crate) thread_histories: ThreadHistories,
+    /// Time the native memory baseline was taken
+    pub(crate) nmt_baseline: Option<u128>,
+    /// Native memory diffs to the baseline. Not saved to session files.
+    pub(crate) nmt_diff_samples: Vec<NmtDiffSample>,
+    /// Class histograms are only collected once enabled, as they trigger a full gc by default.
+    pub(crate) class_histogram_options: Option<ClassHistogramOptions>,
+    /// Latest class histograms. Not saved to session files.
+    pub(crate) class_histograms: Vec<ClassHistogramSample>,
+    /// The growth of the classes is compared to the first histogram.
+    first_class_histogram: Option<ClassHistogram>,
+    /// Heap dumps taken in the session, identified by their start time. Not saved to session
+    /// files.
+    pub(crate) heap_dumps: Vec<HeapDump>,
+    /// Flight recordings seen in the session, oldest first. Not saved to session files.
+    pub(crate) jfr_recordings: Vec<FlightRecording>,
+    /// Changes of manageable flags made in the session, oldest first. Not saved to session files.
+    pub(crate) vm_flag_changes: Vec<VmFlagChange>,
+    /// Latest profile of the process. Not saved to session files.
+    pub(crate) profile: Option<Profile>,
+}
+
+impl Session {
+    pub(crate) fn new(pid: Option<String>, started: u128) -> Session {
+        Session {
+            pid,
+            started,
+            vm_information: None,
+            metrics: MetricsCache::new(),
+            metrics_samples: Vec::new(),
+            thread_samples: Vec::new(),
+            thread_cpu: ThreadCpuCache::default(),
+            thread_dump_locks: false,
+            lock_contention: LockContention::default(),
+            thread_histories: ThreadHistories::default(),
+            nmt_baseline: None,
+            nmt_diff_samples: Vec::new(),
+            class_histogram_options: None,
+            class_histograms: Vec::new(),
+            first_class_histogram: None,
+            heap_dumps: Vec::new(),
+            jfr_recordings: Vec::new(),
+            vm_flag_changes: Vec::new(),
+            profile: None,
+        }
+    }
+
+    /// Adds a sample of a live session. Samples rolled up by the metrics are dropped, so that a
+    /// long monitored process doesn't keep every sample in memory.
+    pub(crate) fn add_metrics(&mut self, sample: MetricsSample) {
+        let cutoff = self.metrics.rolled_up_before(sample.time);
+        self.push_metrics(sample);
+        let expired = self.metrics_samples.partition_point(|s| s.time < cutoff);
+        self.metrics_samples.drain(..expired);
+    }
+
+    /// Adds a thread dump of a live session, see [`Session::add_metrics`].
+    pub(crate) fn add_threads(&mut self, sample: ThreadsSample) {
+        let cutoff = self.metrics.rolled_up_before(sample.time);
+        self.push_threads(sample);
+        let expired = self.thread_samples.partition_point(|s| s.time < cutoff);
+        self.thread_samples.drain(..expired);
+    }
+
+    fn push_metrics(&mut self, sample: MetricsSample) {
+        self.metrics.add_metrics(&sample);
+        self.metrics_samples.push(sample);
+    }
+
+    fn push_threads(&mut self, sample: ThreadsSample) {
+        let churn = self.thread_histories.add(&sample);
+        self.metrics.add_threads(&sample, churn);
+        self.lock_contention.add(&sample);
+        self.thread_samples.push(sample);
+        if let Some(index) = self.thread_samples.len().checked_sub(MAX_THREAD_STACKS + 1) {
+            let dump = &mut self.thread_samples[index].thread_dump;
+            for thread in &mut dump.application_threads {
+                thread.stack = Vec::new();
+                thread.locked_synchronizers = Vec::new();
+            }
+        }
+    }
+
+    /// Starts a new series of native memory diffs.
+    pub(crate) fn set_nmt_baseline(&mut self, time: u128) {
+        self.nmt_baseline = Some(time);
+        self.nmt_diff_samples.clear();
+    }
+
+    pub(crate) fn add_nmt_diff(&mut self, sample: NmtDiffSample) {
+        if self.nmt_diff_samples.len() >= MAX_NMT_DIFFS {
+            self.nmt_diff_samples.remove(0);
+        }
+        self.nmt_diff_samples.push(sample);
+    }
+
+    /// Adds the histogram and tracks the classes which grew the most since the first histogram.
+    pub(crate) fn add_class_histogram(&mut self, sample: ClassHistogramSample) {
+        let first = self
+            .first_class_histogram
+            .get_or_insert_with(|| sample.histogram.clone());
+        let diff = sample.histogram.diff(first);
+        let growing: Vec<&str> = diff
+            .iter()
+            .filter(|d| d.bytes.delta > 0)
+            .take(TOP_GROWING_CLASSES)
+            .map(|d| d.class_name.as_str())
+            .collect();
+        self.metrics.add_class_histogram(&sample, &growing);
+
+        if self.class_histograms.len() >= MAX_CLASS_HISTOGRAMS {
+            self.class_histograms.remove(0);
+        }
+        self.class_histograms.push(sample);
+    }
+
+    pub(crate) fn class_histogram(&self, time: u128) -> Option<&ClassHistogram> {
+        self.class_histograms
+            .iter()
+            .find(|s| s.time == time)
+            .map(|s| &s.histogram)
+    }
+
+    /// Replaces the heap dump with the same start time.
+    pub(crate) fn update_heap_dump(&mut self, heap_dump: HeapDump) {
+        match self
+            .heap_dumps
+            .iter_mut()
+            .find(|d| d.started == heap_dump.started)
+        {
+            Some(existing) => *existing = heap_dump,
+            None => self.heap_dumps.push(heap_dump),
+        }
+    }
+
+    /// `JFR.check` initializes the flight recorder of the jvm, so recordings are only polled once
+    /// a recording was seen or the jvm was started with flight recorder options.
+    pub(crate) fn jfr_in_use(&self) -> bool {
+        !self.jfr_recordings.is_empty()
+            || self
+                .vm_information
+                .as_ref()
+                .and_then(|v| v.report.vm_arguments.as_ref())
+                .and_then(|a| a.jvm_args.as_ref())
+                .is_some_and(|args| args.contains("FlightRecord"))
+    }
+
+    /// Updates the recordings with the ones listed by `JFR.check` at the given time. Recordings
+    /// which aren't listed anymore are closed.
+    pub(crate) fn update_jfr_recordings(&mut self, time: u128, recordings: Vec<JfrRecording>) {
+        let stopped = |r: &JfrRecording| {
+            matches!(
+                r.state,
+                JfrRecordingState::Stopped | JfrRecordingState::Closed
+            )
+        };
+        for existing in self.jfr_recordings.iter_mut() {
+            if !recordings.iter().any(|r| r.id == existing.recording.id) {
+                existing.recording.state = JfrRecordingState::Closed;
+                existing.stopped.get_or_insert(time);
+            }
+        }
+        for recording in recordings {
+            let stopped_at = stopped(&recording).then_some(time);
+            match self
+                .jfr_recordings
+                .iter_mut()
+                .find(|r| r.recording.id == recording.id)
+            {
+                Some(existing) => {
+                    if existing.stopped.is_none() {
+                        existing.stopped = stopped_at;
+                    }
+                    existing.recording = recording;
+                }
+                None => self.jfr_recordings.push(FlightRecording {
+                    recording,
+                    started: time,
+                    stopped: stopped_at,
+                    filename: None,
+                    dumps: Vec::new(),
+                }),
+            }
+        }
+    }
+
+    pub(crate) fn add_jfr_dump(&mut self, id: u32, dump: JfrDump) {
+        if let Some(recording) = self
+            .jfr_recordings
+            .iter_mut()
+            .find(|r| r.recording.id == id)
+        {
+            recording.dumps.push(dump);
+        }
+    }
+
+    pub(crate) fn save(&self, path: &str) -> Result<(), SessionError> {
+        let mut writer = SessionWriter::create(path)?;
+        writer.write(&SessionEntry::Start {
+            version: SESSION_VERSION,
+            pid: self.pid.clone(),
+            interval: None,
+            time: self.started,
+        })?;
+        if let Some(vm_information) = &self.vm_information {
+            writer.write(&SessionEntry::VmInformation(Box::new(
+                vm_information.clone(),
+            )))?;
+        }
+        writer.write(&SessionEntry::Rollups(Box::new(self.metrics.rollups())))?;
+
+        // Write samples ordered by time
+        let mut metrics = self.metrics_samples.iter().peekable();
+        let mut threads = self.thread_samples.iter().peekable();
+        loop {
+            let entry = match (metrics.peek(), threads.peek()) {
+                (Some(m), Some(t)) if m.time <= t.time => {
+                    SessionEntry::Metrics(metrics.next().unwrap().clone())
+                }
+                (_, Some(_)) => SessionEntry::Threads(threads.next().unwrap().clone()),
+                (Some(_), None) => SessionEntry::Metrics(metrics.next().unwrap().clone()),
+                (None, None) => break,
+            };
+            writer.write(&entry)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a session file. Error entries are skipped.
+    pub(crate) fn open(path: &str) -> Result<Session, SessionError> {
+        let file = File::open(path).map_err(|e| io_error(path, e))?;
+        let mut lines = BufReader::new(file).lines().enumerate();
+
+        let mut session = match lines.next() {
+            Some((_, line)) => {
+                let line = line.map_err(|e| io_error(path, e))?;
+                start_session(&line)?
+            }
+            None => {
+                return Err(SessionError::Format {
+                    line: 1,
+                    message: "file is empty".to_string(),
+                })
+            }
+        };
+        for (index, line) in lines {
+            let line = line.map_err(|e| io_error(path, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: SessionEntry =
+                serde_json::from_str(&line).map_err(|e| SessionError::Format {
+                    line: index + 1,
+                    message: e.to_string(),
+                })?;
+            match entry {
+                SessionEntry::Start { .. } => {
+                    return Err(SessionError::Format {
+                        line: index + 1,
+                        message: "unexpected start entry".to_string(),
+                    })
+                }
+                SessionEntry::VmInformation(vm_information) => {
+                    session.vm_information = Some(*vm_information)
+                }
+                SessionEntry::Rollups(rollups) => session.metrics.restore_rollups(*rollups),
+                // Replays keep all samples of the file
+                SessionEntry::Metrics(sample) => session.push_metrics(sample),
+                SessionEntry::Threads(sample) => session.push_threads(sample),
+                SessionEntry::Error { .. } => {}
+            }
+        }
+        Ok(session)
+    }
+}
+
+fn start_session(line: &str) -> Result<Session, SessionError> {
+    // Read the version on its own first, to report newer files as such instead of failing on
+    // entries which are unknown to this version.
+    #[derive(Deserialize)]
+    struct Version {
+        version: u32,
+    }
+    let format_error = |e: serde_json::Error| SessionError::Format {
+        line: 1,
+        message: e.to_string(),
+    };
+    let Version { version } = serde_json::from_str(line).map_err(format_error)?;
+    if version > SESSION_VERSION {
+        return Err(SessionError::UnsupportedVersion { version });
+    }
+    match serde_json::from_str(line).map_err(format_error)? {
+        SessionEntry::Start { pid, time, .. } => Ok(Session::new(pid, time)),
+        _ => Err(SessionError::Format {
+            line: 1,
+            message: "missing start entry".to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jcmd_parse::{parse_heap_info, parse_nmt_summary, parse_thread_dump, parse_vm_info};
+
+    use super::*;
+    use crate::metrics::Selection;
+
+    /// A file in the temp directory, removed when dropped.
+    struct TempFile(String);
+
+    impl TempFile {
+        fn new(name: &str) -> TempFile {
+            let path =
+                std::env::temp_dir().join(format!("jcmd-ui-{}-{}.jsonl", std::process::id(), name));
+            TempFile(path.to_string_lossy().to_string())
+        }
+
+        fn with_lines(name: &str, lines: &[&str]) -> TempFile {
+            let file = TempFile::new(name);
+            std::fs::write(&file.0, lines.join("\n")).unwrap();
+            file
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn metrics_sample(time: u128) -> MetricsSample {
+        MetricsSample {
+            time,
+            heap_info: parse_heap_info(include_str!(
+                "../jcmd-parse/tests/fixtures/jdk17/g1/GC.heap_info.txt"
+            ))
+            .value,
+            native_memory: parse_nmt_summary(include_str!(
+                "../jcmd-parse/tests/fixtures/jdk17/g1/VM.native_memory.txt"
+            ))
+            .value,
+            cgroup_memory: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn threads_sample(time: u128) -> ThreadsSample {
+        ThreadsSample {
+            time,
+            thread_dump: parse_thread_dump(include_str!(
+                "../jcmd-parse/tests/fixtures/jdk17/g1/Thread.print.txt"
+            ))
+            .value,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn json<T: Serialize>(value: &T) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    /// Start of a file written by version 1, which has no rollups
+    const START: &str = r#"{"type":"start","version":1,"pid":"4711","time":1000}"#;
+
+    #[test]
+    fn saves_and_opens_sessions() {
+        let mut session = Session::new(Some("4711".to_string()), 1000);
+        session.vm_information = Some(VmInformation {
+            report: parse_vm_info(include_str!(
+                "../jcmd-parse/tests/fixtures/jdk17/g1/VM.info.txt"
+            ))
+            .value,
+            warnings: Vec::new(),
+        });
+        for time in [2000, 7000] {
+            session.add_metrics(metrics_sample(time));
+        }
+        for time in [3000, 5000] {
+            session.add_threads(threads_sample(time));
+        }
+        let file = TempFile::new("round-trip");
+        session.save(&file.0).unwrap();
+
+        let opened = Session::open(&file.0).unwrap();
+        assert_eq!(opened.pid.as_deref(), Some("4711"));
+        assert_eq!(opened.started, 1000);
+        assert_eq!(json(&opened.vm_information), json(&session.vm_information));
+        assert_eq!(
+            json(&opened.metrics_samples),
+            json(&session.metrics_samples)
+        );
+        assert_eq!(json(&opened.thread_samples), json(&session.thread_samples));
+        assert_eq!(
+            json(&opened.metrics.heap_metrics.select(Selection::All)),
+            json(&session.metrics.heap_metrics.select(Selection::All))
+        );
+        assert_eq!(
+            json(&opened.thread_histories.threads()),
+            json(&session.thread_histories.threads())
+        );
+
+        // Entries are written ordered by time
+        let times: Vec<u64> = std::fs::read_to_string(&file.0)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .filter_map(|v| v["time"].as_u64())
+            .collect();
+        assert_eq!(times, vec![1000, 2000, 3000, 5000, 7000]);
+    }
+
+    #[test]
+    fn saves_rollups_of_sessions_longer_than_the_full_resolution_period() {
+        // Three hours of samples every minute
+        let mut session = Session::new(Some("4711".to_string()), 0);
+        let (metrics, threads) = (metrics_sample(0), threads_sample(0));
+        for minute in 0..180 {
+            let time = minute * 60 * 1000;
+            session.add_metrics(MetricsSample {
+                time,
+                ..metrics.clone()
+            });
+            session.add_threads(ThreadsSample {
+                time: time + 1000,
+                ..threads.clone()
+            });
+        }
+        // Only the samples which aren't rolled up yet are kept
+        assert_eq!(session.metrics_samples.len(), 31);
+        assert_eq!(session.metrics_samples[0].time, 149 * 60 * 1000);
+        assert_eq!(session.thread_samples.len(), 31);
+
+        let file = TempFile::new("rollups");
+        session.save(&file.0).unwrap();
+        let opened = Session::open(&file.0).unwrap();
+
+        let heap = json(&opened.metrics.heap_metrics.select(Selection::All));
+        assert_eq!(heap["rollups"].as_array().unwrap().len(), 149);
+        assert_eq!(heap["values"].as_array().unwrap().len(), 31);
+        assert_eq!(
+            heap,
+            json(&session.metrics.heap_metrics.select(Selection::All))
+        );
+        assert_eq!(
+            json(&opened.metrics.thread_churn.select(Selection::All)),
+            json(&session.metrics.thread_churn.select(Selection::All))
+        );
+        for category in ["GC", "Java Heap", "Thread"] {
+            assert_eq!(
+                json(&opened.metrics.native_memory_metrics[category].select(Selection::All)),
+                json(&session.metrics.native_memory_metrics[category].select(Selection::All))
+            );
+        }
+        assert_eq!(
+            json(&opened.metrics.other_metrics["GC"].select(Selection::All)),
+            json(&session.metrics.other_metrics["GC"].select(Selection::All))
+        );
+    }
+
+    #[test]
+    fn opens_sessions_of_version_1() {
+        let metrics = serde_json::to_string(&SessionEntry::Metrics(metrics_sample(2000))).unwrap();
+        let threads = serde_json::to_string(&SessionEntry::Threads(threads_sample(3000))).unwrap();
+        let file = TempFile::with_lines("version-1", &[START, &metrics, &threads]);
+
+        let session = Session::open(&file.0).unwrap();
+        assert_eq!(session.started, 1000);
+        assert_eq!(session.metrics_samples.len(), 1);
+        assert_eq!(session.thread_samples.len(), 1);
+        let heap = json(&session.metrics.heap_metrics.select(Selection::All));
+        assert_eq!(heap["rollups"], serde_json::json!([]));
+        assert_eq!(heap["values"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let file = TempFile::with_lines(
+            "newer-version",
+            &[
+                r#"{"type":"start","version":99,"pid":"4711","time":1000}"#,
+                r#"{"type":"hologram","time":2000}"#,
+            ],
+        );
+        assert_eq!(
+            Session::open(&file.0).err(),
+            Some(SessionError::UnsupportedVersion { version: 99 })
+        );
+    }
+
+    #[test]
+    fn skips_blank_lines_and_errors() {
+        let file = TempFile::with_lines(
+            "errors",
+            &[
+                START,
+                "",
+                r#"{"type":"error","time":2000,"error":{"kind":"processGone"}}"#,
+                "   ",
+            ],
+        );
+        let session = Session::open(&file.0).unwrap();
+        assert!(session.metrics_samples.is_empty());
+        assert!(session.thread_samples.is_empty());
+    }
+
+    #[test]
+    fn reports_the_line_of_malformed_entries() {
+        let format_error = |name: &str, lines: &[&str]| {
+            let file = TempFile::with_lines(name, lines);
+            match Session::open(&file.0) {
+                Err(SessionError::Format { line, message }) => (line, message),
+                other => panic!("unexpected result {:?}", other.map(|s| s.started)),
+            }
+        };
+
+        assert_eq!(format_error("empty", &[]), (1, "file is empty".to_string()));
+        assert_eq!(format_error("not-json", &["jcmd-ui"]).0, 1);
+        // Without a version the first line isn't a start entry
+        let (line, message) = format_error(
+            "missing-version",
+            &[r#"{"type":"error","time":1,"error":{"kind":"processGone"}}"#],
+        );
+        assert_eq!(line, 1);
+        assert!(message.starts_with("missing field `version`"), "{message}");
+        assert_eq!(
+            format_error(
+                "missing-start",
+                &[r#"{"type":"error","version":1,"time":1,"error":{"kind":"processGone"}}"#]
+            ),
+            (1, "missing start entry".to_string())
+        );
+        assert_eq!(
+            format_error("second-start", &[START, START]),
+            (2, "unexpected start entry".to_string())
+        );
+        assert_eq!(
+            format_error("truncated", &[START, "", r#"{"type":"metrics","#]).0,
+            3
+        );
+        assert_eq!(
+            format_error(
+                "unknown-type",
+                &[START, r#"{"type":"hologram","time":2000}"#]
+            )
+            .0,
+            2
+        );
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        let file = TempFile::new("missing");
+        assert!(matches!(
+            Session::open(&file.0),
+            Err(SessionError::Io { path, .. }) if path == file.0
+        ));
+    }
+}
@@ -0,0 +1,332 @@
+//! Lifecycle of the threads of a session. Threads are identified by their thread and native id,
+//! so that threads created and terminated between two thread dumps can be told apart from a
+//! stable pool.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::Serialize;
+
+use crate::collector::{thread_key, ThreadsSample};
+
+/// Number of terminated threads kept per session. Older ones are dropped.
+const MAX_TERMINATED_THREADS: usize = 1000;
+
+/// Number of state changes kept per thread. Older ones are dropped.
+const MAX_STATE_CHANGES: usize = 100;
+
+/// Window of the created and terminated threads in milliseconds.
+const CHURN_WINDOW: u128 = 60 * 1000;
+
+#[derive(Default)]
+pub(crate) struct ThreadHistories {
+    alive: HashMap<String, ThreadHistory>,
+    /// Oldest first
+    terminated: VecDeque<ThreadHistory>,
+    /// Times of the dumps with the number of threads created and terminated since the previous
+    /// one, within the churn window
+    changes: VecDeque<(u128, u32, u32)>,
+    /// Threads of the first dump were created before it
+    initialized: bool,
+}
+
+/// A thread seen in the thread dumps of a session.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadHistory {
+    pub name: String,
+    /// Java thread id, missing for jvm threads
+    pub id: Option<u32>,
+    pub jvm_thread: bool,
+    pub thread_id: String,
+    pub os_thread_id: String,
+    /// Time of the first dump the thread was in
+    pub first_seen: u128,
+    /// Time of the last dump the thread was in
+    pub last_seen: u128,
+    /// Whether the thread was created after the first dump of the session
+    pub created: bool,
+    /// Time of the first dump the thread wasn't in anymore
+    pub terminated: Option<u128>,
+    /// Cpu time spent in milliseconds while the thread was monitored
+    pub cpu: f32,
+    /// Changes of the state, oldest first
+    pub states: Vec<StateChange>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateChange {
+    pub time: u128,
+    pub status: String,
+}
+
+/// Number of threads created and terminated within the minute before a thread dump.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct ThreadChurn {
+    pub(crate) created: u32,
+    pub(crate) terminated: u32,
+}
+
+struct SeenThread<'a> {
+    name: &'a str,
+    id: Option<u32>,
+    jvm_thread: bool,
+    thread_id: &'a str,
+    os_thread_id: &'a str,
+    cpu: f32,
+    status: &'a str,
+}
+
+impl ThreadHistories {
+    /// Updates the threads with a thread dump whose cpu times are the times spent since the
+    /// previous dump. Returns the churn of the minute before the dump.
+    pub(crate) fn add(&mut self, sample: &ThreadsSample) -> ThreadChurn {
+        let time = sample.time;
+        let dump = &sample.thread_dump;
+        let application_threads = dump.application_threads.iter().map(|t| SeenThread {
+            name: &t.name,
+            id: Some(t.id),
+            jvm_thread: false,
+            thread_id: &t.thread_id,
+            os_thread_id: &t.os_thread_id,
+            cpu: t.cpu,
+            status: &t.status,
+        });
+        let jvm_threads = dump.jvm_threads.iter().map(|t| SeenThread {
+            name: &t.name,
+            id: None,
+            jvm_thread: true,
+            thread_id: &t.thread_id,
+            os_thread_id: &t.os_thread_id,
+            cpu: t.cpu,
+            status: &t.status,
+        });
+
+        let mut alive = HashMap::with_capacity(self.alive.len());
+        let mut created = 0;
+        for thread in application_threads.chain(jvm_threads) {
+            let key = thread_key(thread.thread_id, thread.os_thread_id);
+            let history = match self.alive.remove(&key) {
+                Some(history) => history,
+                None => {
+                    if self.initialized {
+                        created += 1;
+                    }
+                    ThreadHistory {
+                        name: thread.name.to_string(),
+                        id: thread.id,
+                        jvm_thread: thread.jvm_thread,
+                        thread_id: thread.thread_id.to_string(),
+                        os_thread_id: thread.os_thread_id.to_string(),
+                        first_seen: time,
+                        last_seen: time,
+                        created: self.initialized,
+                        terminated: None,
+                        cpu: 0.0,
+                        states: Vec::new(),
+                    }
+                }
+            };
+            alive.insert(key, update(history, &thread, time));
+        }
+
+        // Threads missing in the dump terminated since the previous one
+        let mut terminated: Vec<ThreadHistory> = std::mem::replace(&mut self.alive, alive)
+            .into_values()
+            .map(|mut history| {
+                history.terminated = Some(time);
+                history
+            })
+            .collect();
+        terminated.sort_by_key(|h| h.last_seen);
+        let terminated_count = u32::try_from(terminated.len()).unwrap_or(u32::MAX);
+        self.terminated.extend(terminated);
+        while self.terminated.len() > MAX_TERMINATED_THREADS {
+            self.terminated.pop_front();
+        }
+        self.initialized = true;
+
+        self.changes.push_back((time, created, terminated_count));
+        while self
+            .changes
+            .front()
+            .is_some_and(|(t, _, _)| *t + CHURN_WINDOW <= time)
+        {
+            self.changes.pop_front();
+        }
+        self.changes
+            .iter()
+            .fold(ThreadChurn::default(), |churn, (_, created, terminated)| {
+                ThreadChurn {
+                    created: churn.created + created,
+                    terminated: churn.terminated + terminated,
+                }
+            })
+    }
+
+    /// Returns the alive threads followed by the terminated ones, the latest terminated first.
+    pub(crate) fn threads(&self) -> Vec<ThreadHistory> {
+        let mut alive: Vec<ThreadHistory> = self.alive.values().cloned().collect();
+        alive.sort_by(|a, b| a.first_seen.cmp(&b.first_seen).then(a.name.cmp(&b.name)));
+        alive
+            .into_iter()
+            .chain(self.terminated.iter().rev().cloned())
+            .collect()
+    }
+}
+
+fn update(mut history: ThreadHistory, thread: &SeenThread, time: u128) -> ThreadHistory {
+    history.name = thread.name.to_string();
+    history.last_seen = time;
+    history.cpu += thread.cpu;
+    if history.states.last().map(|s| s.status.as_str()) != Some(thread.status) {
+        history.states.push(StateChange {
+            time,
+            status: thread.status.to_string(),
+        });
+        if history.states.len() > MAX_STATE_CHANGES {
+            history.states.remove(0);
+        }
+    }
+    history
+}
+
+#[cfg(test)]
+mod tests {
+    use jcmd_parse::{parse_thread_dump, ApplicationThread, ThreadDump};
+
+    use super::*;
+    use crate::collector::ThreadCpuCache;
+
+    /// A thread of the g1 fixture with the given name, ids and total cpu time.
+    fn thread(name: &str, thread_id: &str, os_thread_id: &str, cpu: f32) -> ApplicationThread {
+        let dump = parse_thread_dump(include_str!(
+            "../jcmd-parse/tests/fixtures/jdk17/g1/Thread.print.txt"
+        ))
+        .value;
+        ApplicationThread {
+            name: name.to_string(),
+            thread_id: thread_id.to_string(),
+            os_thread_id: os_thread_id.to_string(),
+            cpu,
+            ..dump.application_threads[0].clone()
+        }
+    }
+
+    fn sample(time: u128, threads: Vec<ApplicationThread>) -> ThreadsSample {
+        ThreadsSample {
+            time,
+            thread_dump: ThreadDump {
+                application_threads: threads,
+                ..ThreadDump::default()
+            },
+            warnings: Vec::new(),
+        }
+    }
+
+    fn names(histories: &[ThreadHistory]) -> Vec<(&str, bool, Option<u128>)> {
+        histories
+            .iter()
+            .map(|h| (h.name.as_str(), h.created, h.terminated))
+            .collect()
+    }
+
+    #[test]
+    fn detects_created_and_terminated_threads() {
+        let mut histories = ThreadHistories::default();
+        let churn = histories.add(&sample(
+            0,
+            vec![
+                thread("a", "0x1", "0x11", 0.0),
+                thread("b", "0x2", "0x12", 0.0),
+            ],
+        ));
+        // Threads of the first dump weren't created while monitored
+        assert_eq!((churn.created, churn.terminated), (0, 0));
+
+        let churn = histories.add(&sample(
+            2000,
+            vec![
+                thread("a", "0x1", "0x11", 0.0),
+                thread("c", "0x3", "0x13", 0.0),
+            ],
+        ));
+        assert_eq!((churn.created, churn.terminated), (1, 1));
+
+        let threads = histories.threads();
+        assert_eq!(
+            names(&threads),
+            vec![
+                ("a", false, None),
+                ("c", true, None),
+                ("b", false, Some(2000))
+            ]
+        );
+        assert_eq!((threads[0].first_seen, threads[0].last_seen), (0, 2000));
+        assert_eq!((threads[2].first_seen, threads[2].last_seen), (0, 0));
+    }
+
+    #[test]
+    fn tells_threads_with_reused_names_apart() {
+        let mut cpu = ThreadCpuCache::default();
+        let mut histories = ThreadHistories::default();
+        let mut add = |time, threads| {
+            let mut sample = sample(time, threads);
+            cpu.apply(&mut sample.thread_dump);
+            histories.add(&sample);
+        };
+        add(0, vec![thread("pool-1-thread-1", "0x1", "0x11", 100.0)]);
+        add(2000, vec![thread("pool-1-thread-1", "0x1", "0x11", 150.0)]);
+        // Another pool's thread with the same name and less cpu time than the previous one
+        add(4000, vec![thread("pool-1-thread-1", "0x2", "0x12", 30.0)]);
+        add(6000, vec![thread("pool-1-thread-1", "0x2", "0x12", 40.0)]);
+
+        let threads = histories.threads();
+        assert_eq!(
+            names(&threads),
+            vec![
+                ("pool-1-thread-1", true, None),
+                ("pool-1-thread-1", false, Some(4000))
+            ]
+        );
+        assert_eq!(threads[0].os_thread_id, "0x12");
+        assert_eq!(threads[0].cpu, 10.0);
+        assert_eq!(threads[1].os_thread_id, "0x11");
+        assert_eq!(threads[1].cpu, 50.0);
+    }
+
+    #[test]
+    fn counts_churn_of_the_last_minute() {
+        let mut histories = ThreadHistories::default();
+        histories.add(&sample(0, vec![]));
+        let churn = histories.add(&sample(1000, vec![thread("a", "0x1", "0x11", 0.0)]));
+        assert_eq!((churn.created, churn.terminated), (1, 0));
+        let churn = histories.add(&sample(60_000, vec![thread("b", "0x2", "0x12", 0.0)]));
+        assert_eq!((churn.created, churn.terminated), (2, 1));
+
+        // The creation of `a` left the window
+        let churn = histories.add(&sample(61_000, vec![thread("b", "0x2", "0x12", 0.0)]));
+        assert_eq!((churn.created, churn.terminated), (1, 1));
+        let churn = histories.add(&sample(120_000, vec![thread("b", "0x2", "0x12", 0.0)]));
+        assert_eq!((churn.created, churn.terminated), (0, 0));
+    }
+
+    #[test]
+    fn drops_the_oldest_terminated_threads() {
+        let mut histories = ThreadHistories::default();
+        let threads = |count: usize, time: u128| {
+            (0..count)
+                .map(|i| thread(&format!("t-{time}-{i}"), &format!("{time}/{i}"), "0x1", 0.0))
+                .collect()
+        };
+        histories.add(&sample(0, threads(MAX_TERMINATED_THREADS - 10, 0)));
+        histories.add(&sample(1000, threads(20, 1000)));
+        histories.add(&sample(2000, vec![]));
+
+        let threads = histories.threads();
+        assert_eq!(threads.len(), MAX_TERMINATED_THREADS);
+        // The latest terminated first, the threads of the first dump terminated before
+        assert!(threads[..20].iter().all(|t| t.terminated == Some(2000)));
+        assert!(threads[20..].iter().all(|t| t.terminated == Some(1000)));
+    }
+}
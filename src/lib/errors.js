@@ -0,0 +1,12 @@
+// Errors returned by the backend are objects of the form { kind, message, ... }.
+// Errors raised in the frontend are plain strings.
+export function errorMessage(error) {
+	if (error === undefined || typeof error === 'string') {
+		return error;
+	}
+	return error.message;
+}
+
+export function warningMessages(warnings) {
+	return warnings.map((w) => errorMessage(w)).join('\n');
+}
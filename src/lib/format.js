@@ -0,0 +1,12 @@
+// Formats a number of bytes (e.g. a size or a change of a size) with a binary unit.
+export function formatBytes(bytes) {
+	const abs = Math.abs(bytes);
+	if (abs >= 1073741824) {
+		return (bytes / 1073741824).toFixed(2) + ' GB';
+	} else if (abs >= 1048576) {
+		return (bytes / 1048576).toFixed(2) + ' MB';
+	} else if (abs >= 1024) {
+		return (bytes / 1024).toFixed(2) + ' KB';
+	}
+	return bytes + ' B';
+}
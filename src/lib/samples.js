@@ -0,0 +1,113 @@
+// The backend emits only the values of new samples and the rollups created with them. These
+// functions append them to the series received before. Values which are already known (e.g.
+// received with the initial history) are skipped.
+
+// The backend keeps rollups of one minute for 24 hours
+const maxRollups = 24 * 60;
+
+function appendValues(series, update) {
+	// Values older than the end of a new rollup have been rolled up by the backend
+	const rollups = series.rollups;
+	const lastRollup = rollups.length > 0 ? rollups[rollups.length - 1].time : -1;
+	rollups.push(...update.rollups.filter((r) => r.time > lastRollup));
+	if (rollups.length > maxRollups) {
+		rollups.splice(0, rollups.length - maxRollups);
+	}
+	if (rollups.length > 0) {
+		const end = rollups[rollups.length - 1].end;
+		series.values = series.values.filter((v) => v.time >= end);
+	}
+
+	const values = series.values;
+	const lastTime = values.length > 0 ? values[values.length - 1].time : -1;
+	values.push(...update.values.filter((v) => v.time > lastTime));
+}
+
+// Values of a series to show in a chart. Rolled up values are shown with their average.
+export function chartValues(series) {
+	return series.rollups.map((r) => r.avg).concat(series.values);
+}
+
+// Appends to the series with the same name. Series of new categories are added.
+function appendNamedSeries(seriesList, updates) {
+	for (const update of updates) {
+		const existing = seriesList.find((s) => s.name === update.name);
+		if (existing !== undefined) {
+			appendValues(existing, update);
+		} else {
+			seriesList.push(update);
+		}
+	}
+}
+
+export function mergeMetrics(metrics, update) {
+	if (metrics === undefined || metrics === null) {
+		return update !== null ? update : undefined;
+	}
+	if (update === undefined || update === null) {
+		return metrics;
+	}
+	for (const name of ['totalMemory', 'containerMemory', 'class', 'heap', 'metaspace', 'thread']) {
+		appendValues(metrics[name], update[name]);
+	}
+	appendNamedSeries(metrics.other, update.other);
+	appendNamedSeries(metrics.nativeMemory, update.nativeMemory);
+	metrics.warnings = update.warnings;
+	return metrics;
+}
+
+export function mergeThreads(threads, update) {
+	if (threads === undefined || threads === null) {
+		return update !== null ? update : undefined;
+	}
+	if (update === undefined || update === null) {
+		return threads;
+	}
+	appendValues(threads.threadCountApplication, update.threadCountApplication);
+	appendValues(threads.threadCountJvm, update.threadCountJvm);
+	appendValues(threads.threadChurn, update.threadChurn);
+	// Thread dumps are replaced by the latest one
+	threads.time = update.time;
+	threads.applicationThreads = update.applicationThreads;
+	threads.jvmThreads = update.jvmThreads;
+	threads.lockChains = update.lockChains;
+	threads.warnings = update.warnings;
+	return threads;
+}
+
+// Native memory diffs are compared to the baseline, so they are only kept once the baseline is
+// known (set in the ui or loaded with the history).
+export function mergeNmtDiff(nmtDiffs, sample) {
+	if (nmtDiffs === undefined || nmtDiffs.baseline === null) {
+		return nmtDiffs;
+	}
+	const samples = nmtDiffs.samples;
+	if (samples.length === 0 || samples[samples.length - 1].time < sample.time) {
+		samples.push(sample);
+	}
+	return nmtDiffs;
+}
+
+// Class histograms are only collected once enabled. The update holds the totals of the new
+// histogram and the new values of the tracked classes.
+export function mergeClassHistogram(classHistograms, update) {
+	if (classHistograms === undefined) {
+		return classHistograms;
+	}
+	const samples = classHistograms.samples;
+	if (samples.length === 0 || samples[samples.length - 1].time < update.time) {
+		samples.push({
+			time: update.time,
+			totalInstances: update.totalInstances,
+			totalBytes: update.totalBytes
+		});
+	}
+	appendNamedSeries(classHistograms.classes, update.classes);
+	return classHistograms;
+}
+
+// Heap dumps are identified by their start time. Updates replace the previous state of the dump.
+export function mergeHeapDump(heapDumps, heapDump) {
+	const others = heapDumps.filter((d) => d.started !== heapDump.started);
+	return [...others, heapDump].sort((a, b) => a.started - b.started);
+}